@@ -0,0 +1,66 @@
+use std::fs;
+use std::path::Path;
+
+use smdton::{SmDtonBuilder, SmDtonMap, SmDtonOptions, ST};
+
+const DOCS: [&str; 6] = [
+    r#"{}"#,
+    r#"{"a":1,"b":"text","key":true,"n":null}"#,
+    r#"{"a":[1,2.5,"s",{"b":false}],"key":{"a":{"b":[[]]}}}"#,
+    r#"{"bin":"$B64$AAECAwQ=","a":-7,"b":18446744073709551615}"#,
+    r#"[1,null,"a",[2,[3,{"key":"é"}]]]"#,
+    r#"{"long":"0123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789"}"#,
+];
+
+fn main() {
+    let mut seeds: Vec<Vec<u8>> = Vec::new();
+    for doc in DOCS {
+        let jsn = json::parse(doc).unwrap();
+        let mut bd = SmDtonBuilder::new_from_json(&jsn);
+        seeds.push(bd.build().buf);
+    }
+
+    let mut bd = SmDtonBuilder::new();
+    let root = bd.create_node(ST::SMDT_MAP);
+    let arr = bd.create_node(ST::SMDT_ARR);
+    bd.add_node(root, "a", arr);
+    bd.add_u8(root, "b", 7);
+    bd.add_i16(root, "key", -300);
+    bd.add_u32(root, "u", 70000);
+    bd.add_f32(root, "f", 0.5);
+    bd.add_bin(root, "bin", &[0, 1, 2]);
+    bd.push_u16(arr, 65535);
+    bd.push_i32(arr, -1);
+    bd.push_u64(arr, u64::MAX);
+    bd.push_bin(arr, &[9, 8]);
+    seeds.push(bd.build().buf);
+
+    let mut mp = SmDtonMap::new();
+    mp.add_string("a", "x");
+    mp.add_i64("b", 1);
+    seeds.push(mp.build().buf);
+
+    let sorted = SmDtonOptions {
+        sorted_keys: true,
+        ..Default::default()
+    };
+    let jsn = json::parse(DOCS[2]).unwrap();
+    let mut bd = SmDtonBuilder::new_from_json_with(&jsn, sorted);
+    seeds.push(bd.build().buf);
+
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("corpus");
+    for target in ["to_json", "getters", "sub_map"] {
+        let dir = root.join(target);
+        fs::create_dir_all(&dir).unwrap();
+        for (i, seed) in seeds.iter().enumerate() {
+            fs::write(dir.join(format!("seed_{:02}", i)), seed).unwrap();
+        }
+    }
+
+    // the json text itself for the streaming parser
+    let dir = root.join("from_json");
+    fs::create_dir_all(&dir).unwrap();
+    for (i, doc) in DOCS.iter().enumerate() {
+        fs::write(dir.join(format!("seed_{:02}", i)), doc).unwrap();
+    }
+}
@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use smdton::{SmDtonBuilder, SmDtonReader};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(mut builder) = SmDtonBuilder::from_json_reader(data) {
+        // the json crate is the more lenient parser
+        let text = std::str::from_utf8(data).unwrap();
+        assert!(json::parse(text).is_ok());
+
+        let smb = builder.build();
+        let rd = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+        assert!(rd.try_to_json(1).is_ok());
+    }
+});
@@ -0,0 +1,51 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use smdton::SmDtonReader;
+
+const KEYS: [&str; 5] = ["", "a", "b", "key", "\u{e9}"];
+
+fuzz_target!(|data: &[u8]| {
+    let rd = SmDtonReader::new(data);
+    for oid in 0..4 {
+        let _ = rd.node_type(oid);
+        let _ = rd.iter_entries(oid).count();
+        let _ = rd.iter_items(oid).count();
+        let _ = rd.clone().with_key_index().get_i64(oid, "a");
+        for index in 0..rd.node_sub_num(oid).min(8) + 1 {
+            let _ = rd.get_sub_key(oid, index);
+            let _ = rd.try_get_sub_key(oid, index);
+            let _ = rd.get_i64_by_id(oid, index);
+            let _ = rd.get_string_by_id(oid, index);
+            let _ = rd.get_bin_by_id(oid, index);
+            let _ = rd.try_get_node_id_by_id(oid, index);
+        }
+        for key in KEYS {
+            let _ = rd.get_bool(oid, key);
+            let _ = rd.get_u8(oid, key);
+            let _ = rd.get_i16(oid, key);
+            let _ = rd.get_u32(oid, key);
+            let _ = rd.get_f64(oid, key);
+            let _ = rd.get_string(oid, key);
+            let _ = rd.try_get_string(oid, key);
+            let _ = rd.try_get_u64(oid, key);
+            let _ = rd.get_node_id(oid, key);
+        }
+    }
+    for voff in 0..data.len() + 2 {
+        let _ = rd.get_type_by_voff(voff);
+        let _ = rd.get_bool_by_voff(voff);
+        let _ = rd.get_u8_by_voff(voff);
+        let _ = rd.get_u16_by_voff(voff);
+        let _ = rd.get_f32_by_voff(voff);
+        let _ = rd.get_i64_by_voff(voff);
+        let _ = rd.get_string_by_voff(voff);
+        let _ = rd.try_get_string_by_voff(voff);
+        let _ = rd.get_bin_by_voff(voff);
+        let _ = rd.try_get_bin_by_voff(voff);
+        let _ = rd.get_node_id_by_voff(voff);
+        let _ = rd.try_get_value_by_voff(voff);
+        let _ = rd.as_f64_by_voff(voff);
+        let _ = rd.get_int_lossless_by_voff(voff);
+    }
+});
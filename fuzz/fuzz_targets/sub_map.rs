@@ -0,0 +1,25 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use smdton::SmDtonReader;
+
+fuzz_target!(|data: &[u8]| {
+    let rd = SmDtonReader::new(data);
+    for oid in 0..4 {
+        for (_, voff) in rd.get_sub_map(oid) {
+            let _ = rd.get_type_by_voff(voff);
+            let _ = rd.get_node_id_by_voff(voff);
+        }
+        let _ = rd.try_get_sub_map(oid);
+    }
+
+    if let Ok(rd) = SmDtonReader::try_new(data) {
+        for oid in 1..4 {
+            if let Ok(mp) = rd.try_get_sub_map(oid) {
+                for (key, voff) in mp {
+                    assert_eq!(rd.get_field_voff(oid, &key), voff);
+                }
+            }
+        }
+    }
+});
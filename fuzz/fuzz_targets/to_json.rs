@@ -0,0 +1,22 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use smdton::{SmDton, SmDtonBuffer, SmDtonReader};
+
+fuzz_target!(|data: &[u8]| {
+    let rd = SmDtonReader::new(data);
+    let valid = rd.validate().is_ok();
+    for oid in 0..4 {
+        let r = rd.to_json(oid);
+        if valid && oid == 1 {
+            assert!(r.is_some());
+        }
+    }
+
+    let smb = SmDtonBuffer {
+        off: data.len(),
+        buf: data.to_vec(),
+    };
+    let _ = smb.stringify();
+    let _ = SmDton::new_from_buffer(&smb).stringify();
+});
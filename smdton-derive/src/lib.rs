@@ -0,0 +1,238 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::visit_mut::VisitMut;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, LitStr, Type};
+
+struct DtonField {
+    ident: Ident,
+    key: String,
+    ty: Type,
+    bin: bool,
+}
+
+// field attributes: #[dton(rename = "key")], #[dton(skip)], #[dton(bin)]
+fn parse_fields(input: &DeriveInput, derive: &str) -> syn::Result<Vec<DtonField>> {
+    if let Some(param) = input.generics.type_params().next() {
+        return Err(syn::Error::new_spanned(
+            param,
+            format!("{} does not support generic types", derive),
+        ));
+    }
+    let named = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(named) => named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    format!("{} needs a struct with named fields", derive),
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                format!("{} needs a struct with named fields", derive),
+            ))
+        }
+    };
+
+    let mut fields = Vec::new();
+    for field in &named.named {
+        let ident = field.ident.clone().unwrap();
+        let mut key = ident.to_string();
+        let mut skip = false;
+        let mut bin = false;
+        for attr in &field.attrs {
+            if !attr.path().is_ident("dton") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    let lit: LitStr = meta.value()?.parse()?;
+                    key = lit.value();
+                    return Ok(());
+                }
+                if meta.path.is_ident("skip") {
+                    skip = true;
+                    return Ok(());
+                }
+                if meta.path.is_ident("bin") {
+                    bin = true;
+                    return Ok(());
+                }
+                return Err(meta.error("unknown dton attribute"));
+            })?;
+        }
+        if !skip {
+            fields.push(DtonField {
+                ident: ident,
+                key: key,
+                ty: field.ty.clone(),
+                bin: bin,
+            });
+        }
+    }
+    return Ok(fields);
+}
+
+// accessor types must not name the lifetimes of the source struct
+struct StaticLifetimes;
+
+impl VisitMut for StaticLifetimes {
+    fn visit_lifetime_mut(&mut self, lifetime: &mut syn::Lifetime) {
+        *lifetime = syn::Lifetime::new("'static", lifetime.apostrophe);
+    }
+}
+
+#[proc_macro_derive(DtonView, attributes(dton))]
+pub fn derive_dton_view(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_view(&input) {
+        Ok(tokens) => return tokens.into(),
+        Err(e) => return e.to_compile_error().into(),
+    }
+}
+
+fn expand_view(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = parse_fields(input, "DtonView")?;
+    let name = &input.ident;
+    let vis = &input.vis;
+    let view = format_ident!("{}View", name);
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let lifetimes = input.generics.lifetimes();
+
+    let mut accessors = Vec::new();
+    for field in &fields {
+        let ident = &field.ident;
+        let key = &field.key;
+        if field.bin {
+            accessors.push(quote! {
+                pub fn #ident(&self) -> ::core::option::Option<&'a [u8]> {
+                    return self.reader.get_bin(self.oid, #key);
+                }
+            });
+            continue;
+        }
+        let mut ty = field.ty.clone();
+        StaticLifetimes.visit_type_mut(&mut ty);
+        accessors.push(quote! {
+            pub fn #ident(
+                &self,
+            ) -> ::core::option::Option<<#ty as ::smdton::SmDtonViewField<'r, 'a>>::View> {
+                let value_off = self.reader.get_field_voff(self.oid, #key);
+                if value_off == 0 {
+                    return ::core::option::Option::None;
+                }
+                return <#ty as ::smdton::SmDtonViewField<'r, 'a>>::view_by_voff(
+                    self.reader,
+                    value_off,
+                );
+            }
+        });
+    }
+
+    return Ok(quote! {
+        #[derive(Clone, Copy)]
+        #vis struct #view<'r, 'a: 'r> {
+            reader: &'r ::smdton::SmDtonReader<'a>,
+            oid: usize,
+        }
+
+        #[allow(dead_code)]
+        impl<'r, 'a: 'r> #view<'r, 'a> {
+            pub fn from_node(reader: &'r ::smdton::SmDtonReader<'a>, oid: usize) -> Self {
+                return #view {
+                    reader: reader,
+                    oid: oid,
+                };
+            }
+
+            pub fn node_id(&self) -> usize {
+                return self.oid;
+            }
+
+            #(#accessors)*
+        }
+
+        impl<'__r, '__a: '__r, #(#lifetimes),*> ::smdton::SmDtonViewField<'__r, '__a>
+            for #name #ty_generics
+        {
+            type View = #view<'__r, '__a>;
+
+            fn view_by_voff(
+                reader: &'__r ::smdton::SmDtonReader<'__a>,
+                value_off: usize,
+            ) -> ::core::option::Option<#view<'__r, '__a>> {
+                let oid = reader.get_node_id_by_voff(value_off)?;
+                if reader.node_type(oid) != ::smdton::ST::SMDT_MAP {
+                    return ::core::option::Option::None;
+                }
+                return ::core::option::Option::Some(#view::from_node(reader, oid));
+            }
+        }
+    });
+}
+
+#[proc_macro_derive(DtonBuild, attributes(dton))]
+pub fn derive_dton_build(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_build(&input) {
+        Ok(tokens) => return tokens.into(),
+        Err(e) => return e.to_compile_error().into(),
+    }
+}
+
+fn expand_build(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = parse_fields(input, "DtonBuild")?;
+    let name = &input.ident;
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+
+    let mut adds = Vec::new();
+    for field in &fields {
+        let ident = &field.ident;
+        let key = &field.key;
+        if field.bin {
+            adds.push(quote! {
+                builder.add_bin(oid, #key, ::core::convert::AsRef::<[u8]>::as_ref(&self.#ident));
+            });
+            continue;
+        }
+        adds.push(quote! {
+            ::smdton::SmDtonBuildField::add_field(&self.#ident, builder, oid, #key);
+        });
+    }
+
+    return Ok(quote! {
+        impl #impl_generics ::smdton::SmDtonBuild for #name #ty_generics {
+            fn build_into<'__b>(
+                &'__b self,
+                builder: &mut ::smdton::SmDtonBuilder<'__b>,
+                oid: usize,
+            ) {
+                #(#adds)*
+            }
+        }
+
+        impl #impl_generics ::smdton::SmDtonBuildField for #name #ty_generics {
+            fn add_field<'__b>(
+                &'__b self,
+                builder: &mut ::smdton::SmDtonBuilder<'__b>,
+                oid: usize,
+                key: &'__b str,
+            ) {
+                let sub = builder.create_node(::smdton::ST::SMDT_MAP);
+                ::smdton::SmDtonBuild::build_into(self, builder, sub);
+                builder.add_node(oid, key, sub);
+            }
+
+            fn push_item<'__b>(&'__b self, builder: &mut ::smdton::SmDtonBuilder<'__b>, oid: usize) {
+                let sub = builder.create_node(::smdton::ST::SMDT_MAP);
+                ::smdton::SmDtonBuild::build_into(self, builder, sub);
+                builder.push_node(oid, sub);
+            }
+        }
+    });
+}
@@ -1,17 +1,50 @@
 mod sd_buffer;
 mod sd_builder;
 mod sd_data;
+#[cfg(feature = "serde")]
+mod sd_de;
+mod sd_diff;
 mod sd_dton;
+mod sd_error;
+mod sd_field;
+mod sd_flatten;
+mod sd_json;
+mod sd_layer;
 mod sd_map;
 mod sd_node;
+mod sd_option;
 mod sd_pair;
+mod sd_patch;
+mod sd_path;
 mod sd_reader;
+#[cfg(feature = "serde")]
+mod sd_ser;
+mod sd_stream;
+mod sd_text;
+mod sd_value;
+mod sd_write;
 mod su;
 
 pub use sd_buffer::SmDtonBuffer;
 pub use sd_builder::SmDtonBuilder;
 pub use sd_data::ST;
+#[cfg(feature = "serde")]
+pub use sd_de::{from_buffer, from_dton, from_reader, SmDtonDe};
+pub use sd_diff::{diff, diff_with, try_diff, try_diff_with};
 pub use sd_dton::SmDton;
+pub use sd_error::SmDtonError;
+pub use sd_field::{SmDtonBuild, SmDtonBuildField, SmDtonViewField};
+pub use sd_json::{SmDtonJsonKind, SmDtonJsonValue};
 pub use sd_map::SmDtonMap;
+pub use sd_option::{SmDtonArrayMerge, SmDtonJsonFormat, SmDtonMergeOptions, SmDtonOptions};
 pub use sd_pair::SmDtonPair;
+pub use sd_patch::{
+    apply_json_patch, apply_merge_patch, parse_json_patch, try_apply_merge_patch, SmDtonPatchOp,
+};
+pub use sd_path::{SmDtonPath, SmDtonPathSeg};
 pub use sd_reader::SmDtonReader;
+#[cfg(feature = "serde")]
+pub use sd_ser::{to_buffer, to_buffer_with};
+pub use sd_value::{SmDtonEntries, SmDtonItems, SmDtonNodeRef, SmDtonValue};
+#[cfg(feature = "derive")]
+pub use smdton_derive::{DtonBuild, DtonView};
@@ -1,3 +1,4 @@
+#[cfg(feature = "json")]
 use crate::SmDton;
 
 use super::sd_data::{SmDtonData, ST};
@@ -18,6 +19,12 @@ pub struct SmDtonBuffer {
     pub buf: Vec<u8>,
 }
 
+impl Default for SmDtonBuffer {
+    fn default() -> Self {
+        return SmDtonBuffer::new();
+    }
+}
+
 impl SmDtonBuffer {
     // SDK
     pub fn new() -> Self {
@@ -37,24 +44,27 @@ impl SmDtonBuffer {
         return self.buf.len() == 0;
     }
 
+    #[cfg(feature = "json")]
     pub fn stringify(&self) -> Option<String> {
-        let sd = SmDton::new_from_buffer(&self);
+        let sd = SmDton::new_from_buffer(self);
         return sd.stringify();
     }
     // SDK -
 
     #[inline]
     pub fn build_start(&mut self, size: usize, oz: usize) {
-        self.buf = Vec::with_capacity(size);
-        unsafe {
-            self.buf.set_len(size);
-        }
+        self.buf = vec![0; size];
 
         self.buf[0] = ST::SMTY_DTR;
         self.buf[1] = oz as u8;
         self.off = 2;
     }
 
+    #[inline]
+    pub fn build_flag(&mut self, flag: u8) {
+        self.buf[0] |= flag;
+    }
+
     #[inline(always)]
     pub fn build_put_u8(&mut self, b: u8) {
         self.buf[self.off] = b;
@@ -86,22 +96,19 @@ impl SmDtonBuffer {
     }
 
     #[inline(always)]
-    pub fn calc_key_part<'a>(
+    pub fn calc_key_part<K: AsRef<str>>(
         &self,
         knum: usize,
         kseg_off: usize,
-        keys: &Vec<&'a str>,
+        keys: &[K],
     ) -> Vec<usize> {
         // key part
-        let mut kseg_offs: Vec<usize> = Vec::with_capacity(knum);
-        unsafe {
-            kseg_offs.set_len(knum);
-        }
+        let mut kseg_offs: Vec<usize> = vec![0; knum];
         let mut off = kseg_off;
         let oz = self.buf[1] as usize;
         for i in 0..knum {
             kseg_offs[i] = off;
-            off += oz + keys[i].len() + 1;
+            off += oz + keys[i].as_ref().len() + 1;
         }
         return kseg_offs;
     }
@@ -111,13 +118,10 @@ impl SmDtonBuffer {
         &self,
         vnum: usize,
         vseg_off: usize,
-        values: &Vec<SmDtonData<'a>>,
+        values: &[SmDtonData<'a>],
     ) -> Vec<usize> {
         // value part
-        let mut vseg_offs: Vec<usize> = Vec::with_capacity(vnum);
-        unsafe {
-            vseg_offs.set_len(vnum);
-        };
+        let mut vseg_offs: Vec<usize> = vec![0; vnum];
         let mut off = vseg_off;
         let oz = self.buf[1] as usize;
         for i in 0..vnum {
@@ -132,16 +136,16 @@ impl SmDtonBuffer {
     }
 
     #[inline(always)]
-    pub fn build_kvsegs<'a>(
+    pub fn build_kvsegs<'a, K: AsRef<str>>(
         &mut self,
         knum: usize,
         vnum: usize,
-        keys: &Vec<&'a str>,
-        values: &Vec<SmDtonData<'a>>,
+        keys: &[K],
+        values: &[SmDtonData<'a>],
     ) {
         // build key part
-        for i in 0..knum {
-            let ktm = keys[i].as_bytes();
+        for key in keys.iter().take(knum) {
+            let ktm = key.as_ref().as_bytes();
             self.build_put_int(ktm.len() + 1);
             self.build_put_bin(ktm, ktm.len());
             self.build_put_u8(0);
@@ -149,8 +153,7 @@ impl SmDtonBuffer {
         self.build_put_u8(0x77);
 
         // build value part
-        for i in 0..vnum {
-            let vtm = &values[i];
+        for vtm in values.iter().take(vnum) {
             let mut smdt = vtm.smdt;
             if smdt == ST::SMDT_B64 {
                 smdt = ST::SMDT_BIN;
@@ -167,24 +170,31 @@ impl SmDtonBuffer {
 
             match vtm.smdt {
                 ST::SMDT_STR => {
-                    if vtm.u8a.is_none() {
-                        continue;
-                    }
-                    let t = vtm.u8a.as_ref().unwrap();
-                    self.build_put_bin(&t, vtm.len - 1);
+                    // borrowed text, or owned in v8a
+                    let t = match vtm.u8a {
+                        Some(t) => t,
+                        None => match &vtm.v8a {
+                            Some(t) => t.as_slice(),
+                            None => continue,
+                        },
+                    };
+                    self.build_put_bin(t, vtm.len - 1);
                     self.build_put_u8(0);
                 }
                 ST::SMDT_BIN => {
-                    if vtm.u8a.is_none() {
-                        continue;
-                    }
-                    let t = vtm.u8a.as_ref().unwrap();
-                    self.build_put_bin(&t, vtm.len);
+                    let t = match vtm.u8a {
+                        Some(t) => t,
+                        None => match &vtm.v8a {
+                            Some(t) => t.as_slice(),
+                            None => continue,
+                        },
+                    };
+                    self.build_put_bin(t, vtm.len);
                 }
                 ST::SMDT_MAP | ST::SMDT_ARR => {}
                 _ => {
                     let t = vtm.v8a.as_ref().unwrap();
-                    self.build_put_bin(&t, vtm.len);
+                    self.build_put_bin(t, vtm.len);
                 }
             }
         }
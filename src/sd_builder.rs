@@ -1,42 +1,50 @@
-use json::JsonValue;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 
 use crate::sd_buffer::SmDtonBuffer;
 
 use super::sd_data::{SmDtonData, ST};
+use super::sd_error::SmDtonError;
+use super::sd_json::{SmDtonJsonKind, SmDtonJsonValue};
 use super::sd_node::SmDtonNode;
+use super::sd_option::SmDtonOptions;
+use super::sd_reader::{SmDtonReader, MAX_DEPTH};
+use super::sd_value::SmDtonValue;
 use super::su;
 
 macro_rules! def_func_add {
-    ($func_name:ident, $new_name:ident, $dty: ty) => {
+    ($func_name:ident, $try_name:ident, $new_name:ident, $dty: ty) => {
         #[allow(dead_code)]
         pub fn $func_name(&mut self, oid: usize, key: &'a str, value: $dty) {
-            if oid == 0 || oid > self.nnum {
-                return;
-            }
+            let _ = self.$try_name(oid, key, value);
+        }
+
+        #[allow(dead_code)]
+        pub fn $try_name(
+            &mut self,
+            oid: usize,
+            key: &'a str,
+            value: $dty,
+        ) -> Result<(), SmDtonError> {
+            self._check_node(oid, ST::SMDT_MAP)?;
             self._do_add(oid, key, SmDtonData::$new_name(value));
+            return Ok(());
         }
     };
 }
 
 macro_rules! def_func_push {
-    ($func_name:ident, $new_name:ident, $dty: ty) => {
+    ($func_name:ident, $try_name:ident, $new_name:ident, $dty: ty) => {
         #[allow(dead_code)]
         pub fn $func_name(&mut self, oid: usize, value: $dty) {
-            if oid == 0 || oid > self.nnum {
-                return;
-            }
-            self._do_push(oid, SmDtonData::$new_name(value));
+            let _ = self.$try_name(oid, value);
         }
-    };
-}
 
-macro_rules! smd_handle_data {
-    ($self: expr, $func_add:ident, $func_push:ident, $upoid: expr, $key: expr, $data: expr) => {
-        if $key.len() > 0 {
-            $self.$func_add($upoid, $key, $data);
-        } else {
-            $self.$func_push($upoid, $data);
+        #[allow(dead_code)]
+        pub fn $try_name(&mut self, oid: usize, value: $dty) -> Result<(), SmDtonError> {
+            self._check_node(oid, ST::SMDT_ARR)?;
+            self._do_push(oid, SmDtonData::$new_name(value));
+            return Ok(());
         }
     };
 }
@@ -46,14 +54,22 @@ pub struct SmDtonBuilder<'a> {
     nnum: usize,
 
     // key part
-    keys: Vec<&'a str>,
-    map: HashMap<&'a str, usize>,
+    keys: Vec<Cow<'a, str>>,
+    map: HashMap<Cow<'a, str>, usize>,
     kdata_size: usize,
 
     // value part
     values: Vec<SmDtonData<'a>>,
     vdata_size: usize,
     len_blk: usize,
+
+    options: SmDtonOptions,
+}
+
+impl<'a> Default for SmDtonBuilder<'a> {
+    fn default() -> Self {
+        return SmDtonBuilder::new();
+    }
 }
 
 impl<'a> SmDtonBuilder<'a> {
@@ -79,6 +95,9 @@ impl<'a> SmDtonBuilder<'a> {
 
         // build head
         smb.build_start(size, oz);
+        if self.options.sorted_keys {
+            smb.build_flag(ST::SMTY_FLAG_SORTED);
+        }
         smb.build_put_int(nnum);
         smb.build_put_int(knum);
         smb.build_put_int(vnum);
@@ -104,9 +123,14 @@ impl<'a> SmDtonBuilder<'a> {
 
         for i in 0..nnum {
             let node = &self.nodes[i];
+            let mut order: Vec<usize> = (0..node.values.len()).collect();
+            if self.options.sorted_keys && !node.keys.is_empty() {
+                // stable, so the last of equal keys still wins
+                order.sort_by(|x, y| self.keys[node.keys[*x]].cmp(&self.keys[node.keys[*y]]));
+            }
             smb.build_put_int(node.values.len());
-            for k in 0..node.values.len() {
-                if node.keys.len() > 0 {
+            for k in order {
+                if !node.keys.is_empty() {
                     smb.build_put_int(kseg_offs[node.keys[k]]);
                 }
                 smb.build_put_int(vseg_offs[node.values[k]]);
@@ -121,7 +145,12 @@ impl<'a> SmDtonBuilder<'a> {
     }
 
     #[inline]
-    fn _do_add(&mut self, oid: usize, key: &'a str, da: SmDtonData<'a>) {
+    pub(crate) fn _do_add<K: Into<Cow<'a, str>>>(
+        &mut self,
+        oid: usize,
+        key: K,
+        da: SmDtonData<'a>,
+    ) {
         let kid = self._add_key(key);
         let vid = self._add_value(da);
         let ma = &mut self.nodes[oid - 1];
@@ -130,23 +159,88 @@ impl<'a> SmDtonBuilder<'a> {
     }
 
     #[inline]
-    fn _do_push(&mut self, oid: usize, da: SmDtonData<'a>) {
+    pub(crate) fn _do_push(&mut self, oid: usize, da: SmDtonData<'a>) {
         let vid = self._add_value(da);
         let ma = &mut self.nodes[oid - 1];
         ma.values.push(vid);
     }
 
+    // a value read from another buffer, maps and arrays are copied node by node
+    pub(crate) fn _copy_value(
+        &mut self,
+        q: &SmDtonReader<'a>,
+        value: SmDtonValue<'a>,
+        seen: &mut HashSet<usize>,
+        depth: usize,
+    ) -> Result<SmDtonData<'a>, SmDtonError> {
+        match value {
+            SmDtonValue::Map(node) | SmDtonValue::Arr(node) => {
+                let oid = self._copy_node(q, node.oid, seen, depth)?;
+                return Ok(self._node_data(oid));
+            }
+            _ => return SmDtonData::from_scalar(value).ok_or(SmDtonError::InvalidNodeId(0)),
+        }
+    }
+
+    // every entry is copied as stored, repeated keys and tombstones included
+    pub(crate) fn _copy_node(
+        &mut self,
+        q: &SmDtonReader<'a>,
+        oid: usize,
+        seen: &mut HashSet<usize>,
+        depth: usize,
+    ) -> Result<usize, SmDtonError> {
+        if depth > MAX_DEPTH {
+            return Err(SmDtonError::TooDeep { depth: depth });
+        }
+        let smdt = q.node_type(oid);
+        if smdt != ST::SMDT_MAP && smdt != ST::SMDT_ARR {
+            return Err(SmDtonError::InvalidNodeId(oid));
+        }
+        if !seen.insert(oid) {
+            return Err(SmDtonError::RepeatedNode(oid));
+        }
+
+        let out = self.create_node(smdt);
+        for index in 0..q.node_sub_num(oid) {
+            if smdt == ST::SMDT_MAP {
+                let (key, value_off) = match q.try_get_sub_entry(oid, index)? {
+                    Some(entry) => entry,
+                    None => break,
+                };
+                let value = q.try_get_value_by_voff(value_off)?;
+                let da = self._copy_value(q, value, seen, depth + 1)?;
+                self._do_add(out, key, da);
+            } else {
+                let value_off = match q.try_get_sub_voff(oid, index)? {
+                    Some(value_off) => value_off,
+                    None => break,
+                };
+                let value = q.try_get_value_by_voff(value_off)?;
+                let da = self._copy_value(q, value, seen, depth + 1)?;
+                self._do_push(out, da);
+            }
+        }
+        return Ok(out);
+    }
+
     #[inline]
-    fn _add_key(&mut self, key: &'a str) -> usize {
-        let op = self.map.get(key);
+    pub(crate) fn _node_data(&self, oid: usize) -> SmDtonData<'a> {
+        return SmDtonData::new_node(&self.nodes[oid - 1]);
+    }
+
+    #[inline]
+    fn _add_key<K: Into<Cow<'a, str>>>(&mut self, key: K) -> usize {
+        let key = key.into();
+        let op = self.map.get(key.as_ref());
         match op {
             Some(di) => {
                 return *di;
             }
             _ => {
                 let ix = self.keys.len();
-                self.keys.push(key);
-                self.map.insert(key, ix);
+                self.keys.push(key.clone());
+                self.map.insert(key.clone(), ix);
                 self.kdata_size += key.len() + 1;
                 return ix;
             }
@@ -164,64 +258,112 @@ impl<'a> SmDtonBuilder<'a> {
         return ix;
     }
 
-    fn _explore_node(&mut self, upoid: usize, key: &'a str, jsn: &'a JsonValue) {
-        match jsn {
-            JsonValue::Null => {}
-            JsonValue::Boolean(data) => {
-                smd_handle_data!(self, add_bool, push_bool, upoid, key, *data);
-            }
-            JsonValue::Short(s) => {
-                if s.starts_with("$B64$") {
-                    self.add_base64(upoid, key, s);
-                } else {
-                    smd_handle_data!(self, add_string, push_string, upoid, key, s);
-                }
+    #[inline]
+    pub(crate) fn _check_node(&self, oid: usize, smdt: u8) -> Result<(), SmDtonError> {
+        if oid == 0 || oid > self.nnum {
+            return Err(SmDtonError::InvalidNodeId(oid));
+        }
+        let found = self.nodes[oid - 1].smdt;
+        if found != smdt {
+            return Err(SmDtonError::TypeMismatch {
+                expected: smdt,
+                found: found,
+            });
+        }
+        return Ok(());
+    }
+
+    // key is None for array items, so "" stays a valid map key. when not
+    // strict an entry that cannot be stored is left out and the rest of the
+    // document still goes in
+    fn _explore_node<J: SmDtonJsonValue>(
+        &mut self,
+        upoid: usize,
+        key: Option<&'a str>,
+        jsn: &'a J,
+        strict: bool,
+    ) -> Result<(), SmDtonError> {
+        let da = match jsn.json_kind() {
+            SmDtonJsonKind::Null => SmDtonData::new_null(),
+            SmDtonJsonKind::Bool(data) => SmDtonData::new_bool(data),
+            SmDtonJsonKind::Str(s) => _string_data(s, strict)?,
+            SmDtonJsonKind::Number(positive, mantissa, exponent, truncated) => {
+                let exact = self.options.exact_numbers;
+                let narrow = self.options.narrow_ints;
+                SmDtonData::try_new_number(positive, mantissa, exponent, truncated, exact, narrow)?
             }
-            JsonValue::String(s) => {
-                if s.starts_with("$B64$") {
-                    self.add_base64(upoid, key, s);
-                } else {
-                    smd_handle_data!(self, add_string, push_string, upoid, key, s);
+            SmDtonJsonKind::Float(data) => SmDtonData::new_f64(data),
+            SmDtonJsonKind::Object(obj) => {
+                let oid = self._explore_child(upoid, key, ST::SMDT_MAP)?;
+                for (kn, value) in obj {
+                    let r = self._explore_node(oid, Some(kn), value, strict);
+                    if strict {
+                        r?;
+                    }
                 }
+                return Ok(());
             }
-            JsonValue::Number(num) => {
-                let (positive, mantissa, exponent) = num.as_parts();
-                if exponent >= 0 {
-                    let pw = 10u64.pow(exponent as u32);
-                    let ab = (mantissa * pw) as i64;
-                    let mut v = ab;
-                    if !positive {
-                        v = -v;
-                    }
-                    smd_handle_data!(self, add_i64, push_i64, upoid, key, v);
-                } else {
-                    let pw = 10f64.powf(exponent as f64);
-                    let mut v = mantissa as f64 * pw;
-                    if !positive {
-                        v = -v;
+            SmDtonJsonKind::Array(arr) => {
+                let oid = self._explore_child(upoid, key, ST::SMDT_ARR)?;
+                for value in arr.iter() {
+                    let r = self._explore_node(oid, None, value, strict);
+                    if strict {
+                        r?;
                     }
-                    smd_handle_data!(self, add_f64, push_f64, upoid, key, v);
                 }
+                return Ok(());
             }
-            JsonValue::Object(obj) => {
-                let oid = self.create_node(ST::SMDT_MAP);
-                if upoid > 0 {
-                    smd_handle_data!(self, add_node, push_node, upoid, key, oid);
-                }
-                for (kn, value) in obj.iter() {
-                    self._explore_node(oid, kn, value);
-                }
+        };
+        return self._place(upoid, key, da);
+    }
+
+    // a new node, attached before its items so a root map is node 1
+    fn _explore_child(
+        &mut self,
+        upoid: usize,
+        key: Option<&'a str>,
+        smdt: u8,
+    ) -> Result<usize, SmDtonError> {
+        if upoid == 0 {
+            return Ok(self.create_node(smdt));
+        }
+        let up_smdt = if key.is_some() {
+            ST::SMDT_MAP
+        } else {
+            ST::SMDT_ARR
+        };
+        self._check_node(upoid, up_smdt)?;
+        let oid = self.create_node(smdt);
+        let da = self._node_data(oid);
+        self._place(upoid, key, da)?;
+        return Ok(oid);
+    }
+
+    fn _place(
+        &mut self,
+        upoid: usize,
+        key: Option<&'a str>,
+        da: SmDtonData<'a>,
+    ) -> Result<(), SmDtonError> {
+        match key {
+            Some(key) => {
+                self._check_node(upoid, ST::SMDT_MAP)?;
+                self._do_add(upoid, key, da);
             }
-            JsonValue::Array(arr) => {
-                let oid = self.create_node(ST::SMDT_ARR);
-                if upoid > 0 {
-                    smd_handle_data!(self, add_node, push_node, upoid, key, oid);
-                }
-                for value in arr.iter() {
-                    self._explore_node(oid, "", value);
-                }
+            None => {
+                self._check_node(upoid, ST::SMDT_ARR)?;
+                self._do_push(upoid, da);
             }
         }
+        return Ok(());
+    }
+
+    // "" pushes into an array node and is a plain key of a map node
+    fn _json_key(&self, oid: usize, key: &'a str) -> Option<&'a str> {
+        if key.is_empty() && self._check_node(oid, ST::SMDT_ARR).is_ok() {
+            return None;
+        }
+        return Some(key);
     }
 
     //+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
@@ -240,15 +382,49 @@ impl<'a> SmDtonBuilder<'a> {
             values: Vec::with_capacity(16),
             len_blk: 0,
             vdata_size: 0,
+
+            options: SmDtonOptions::default(),
         }
     }
 
-    pub fn new_from_json(jsn: &'a JsonValue) -> Self {
+    pub fn with_options(options: SmDtonOptions) -> Self {
+        let mut obj = SmDtonBuilder::new();
+        obj.options = options;
+        return obj;
+    }
+
+    pub fn set_options(&mut self, options: SmDtonOptions) {
+        self.options = options;
+    }
+
+    // entries that cannot be stored are left out, a scalar root builds nothing
+    pub fn new_from_json<J: SmDtonJsonValue>(jsn: &'a J) -> Self {
         let mut obj = SmDtonBuilder::new();
-        obj._explore_node(0, "", jsn);
+        let _ = obj._explore_node(0, None, jsn, false);
         return obj;
     }
 
+    pub fn try_new_from_json<J: SmDtonJsonValue>(jsn: &'a J) -> Result<Self, SmDtonError> {
+        let mut obj = SmDtonBuilder::new();
+        obj._explore_node(0, None, jsn, true)?;
+        return Ok(obj);
+    }
+
+    pub fn new_from_json_with<J: SmDtonJsonValue>(jsn: &'a J, options: SmDtonOptions) -> Self {
+        let mut obj = SmDtonBuilder::with_options(options);
+        let _ = obj._explore_node(0, None, jsn, false);
+        return obj;
+    }
+
+    pub fn try_new_from_json_with<J: SmDtonJsonValue>(
+        jsn: &'a J,
+        options: SmDtonOptions,
+    ) -> Result<Self, SmDtonError> {
+        let mut obj = SmDtonBuilder::with_options(options);
+        obj._explore_node(0, None, jsn, true)?;
+        return Ok(obj);
+    }
+
     pub fn create_node(&mut self, smdt: u8) -> usize {
         let id = self.nodes.len() + 1;
         let dton = SmDtonNode::new(smdt, id);
@@ -258,58 +434,183 @@ impl<'a> SmDtonBuilder<'a> {
     }
 
     #[allow(dead_code)]
-    pub fn add_from_json(&mut self, oid: usize, key: &'a str, jsn: &'a JsonValue) {
-        self._explore_node(oid, key, jsn);
+    pub fn add_from_json<J: SmDtonJsonValue>(&mut self, oid: usize, key: &'a str, jsn: &'a J) {
+        let _ = self._add_from_json(oid, key, jsn, false);
+    }
+
+    #[allow(dead_code)]
+    pub fn try_add_from_json<J: SmDtonJsonValue>(
+        &mut self,
+        oid: usize,
+        key: &'a str,
+        jsn: &'a J,
+    ) -> Result<(), SmDtonError> {
+        return self._add_from_json(oid, key, jsn, true);
+    }
+
+    fn _add_from_json<J: SmDtonJsonValue>(
+        &mut self,
+        oid: usize,
+        key: &'a str,
+        jsn: &'a J,
+        strict: bool,
+    ) -> Result<(), SmDtonError> {
+        let key = self._json_key(oid, key);
+        return self._explore_node(oid, key, jsn, strict);
     }
 
     // add to map node
 
-    def_func_add!(add_bool, new_bool, bool);
-    def_func_add!(add_u8, new_u8, u8);
+    #[allow(dead_code)]
+    pub fn add_null(&mut self, oid: usize, key: &'a str) {
+        let _ = self.try_add_null(oid, key);
+    }
 
-    def_func_add!(add_i16, new_i16, i16);
-    def_func_add!(add_u16, new_u16, u16);
+    #[allow(dead_code)]
+    pub fn try_add_null(&mut self, oid: usize, key: &'a str) -> Result<(), SmDtonError> {
+        self._check_node(oid, ST::SMDT_MAP)?;
+        self._do_add(oid, key, SmDtonData::new_null());
+        return Ok(());
+    }
 
-    def_func_add!(add_i32, new_i32, i32);
-    def_func_add!(add_u32, new_u32, u32);
-    def_func_add!(add_f32, new_f32, f32);
+    // tombstone for an overlay, hides the key in the layers below
+    #[allow(dead_code)]
+    pub fn add_delete(&mut self, oid: usize, key: &'a str) {
+        let _ = self.try_add_delete(oid, key);
+    }
 
-    def_func_add!(add_i64, new_i64, i64);
-    def_func_add!(add_u64, new_u64, u64);
-    def_func_add!(add_f64, new_f64, f64);
+    #[allow(dead_code)]
+    pub fn try_add_delete(&mut self, oid: usize, key: &'a str) -> Result<(), SmDtonError> {
+        self._check_node(oid, ST::SMDT_MAP)?;
+        self._do_add(oid, key, SmDtonData::new_delete());
+        return Ok(());
+    }
+
+    def_func_add!(add_bool, try_add_bool, new_bool, bool);
+    def_func_add!(add_u8, try_add_u8, new_u8, u8);
+
+    def_func_add!(add_i16, try_add_i16, new_i16, i16);
+    def_func_add!(add_u16, try_add_u16, new_u16, u16);
 
-    def_func_add!(add_string, new_string, &'a str);
-    def_func_add!(add_bin, new_bin, &'a [u8]);
-    def_func_add!(add_base64, new_b64, &'a str);
+    def_func_add!(add_i32, try_add_i32, new_i32, i32);
+    def_func_add!(add_u32, try_add_u32, new_u32, u32);
+    def_func_add!(add_f32, try_add_f32, new_f32, f32);
+
+    def_func_add!(add_i64, try_add_i64, new_i64, i64);
+    def_func_add!(add_u64, try_add_u64, new_u64, u64);
+    def_func_add!(add_f64, try_add_f64, new_f64, f64);
+
+    def_func_add!(add_string, try_add_string, new_string, &'a str);
+    def_func_add!(add_bin, try_add_bin, new_bin, &'a [u8]);
+
+    #[allow(dead_code)]
+    pub fn add_base64(&mut self, oid: usize, key: &'a str, value: &'a str) {
+        let _ = self.try_add_base64(oid, key, value);
+    }
+
+    #[allow(dead_code)]
+    pub fn try_add_base64(
+        &mut self,
+        oid: usize,
+        key: &'a str,
+        value: &'a str,
+    ) -> Result<(), SmDtonError> {
+        self._check_node(oid, ST::SMDT_MAP)?;
+        let da = SmDtonData::try_new_b64(value)?;
+        self._do_add(oid, key, da);
+        return Ok(());
+    }
 
     #[allow(dead_code)]
     pub fn add_node(&mut self, oid: usize, key: &'a str, new_oid: usize) {
+        let _ = self.try_add_node(oid, key, new_oid);
+    }
+
+    #[allow(dead_code)]
+    pub fn try_add_node(
+        &mut self,
+        oid: usize,
+        key: &'a str,
+        new_oid: usize,
+    ) -> Result<(), SmDtonError> {
+        self._check_node(oid, ST::SMDT_MAP)?;
+        if new_oid == 0 || new_oid > self.nnum {
+            return Err(SmDtonError::InvalidNodeId(new_oid));
+        }
         let ma = &self.nodes[new_oid - 1];
         self._do_add(oid, key, SmDtonData::new_node(ma));
+        return Ok(());
     }
 
     // push to array node
 
-    def_func_push!(push_bool, new_bool, bool);
-    def_func_push!(push_u8, new_u8, u8);
+    #[allow(dead_code)]
+    pub fn push_null(&mut self, oid: usize) {
+        let _ = self.try_push_null(oid);
+    }
+
+    #[allow(dead_code)]
+    pub fn try_push_null(&mut self, oid: usize) -> Result<(), SmDtonError> {
+        self._check_node(oid, ST::SMDT_ARR)?;
+        self._do_push(oid, SmDtonData::new_null());
+        return Ok(());
+    }
+
+    def_func_push!(push_bool, try_push_bool, new_bool, bool);
+    def_func_push!(push_u8, try_push_u8, new_u8, u8);
 
-    def_func_push!(push_i16, new_i16, i16);
-    def_func_push!(push_u16, new_u16, u16);
+    def_func_push!(push_i16, try_push_i16, new_i16, i16);
+    def_func_push!(push_u16, try_push_u16, new_u16, u16);
 
-    def_func_push!(push_i32, new_i32, i32);
-    def_func_push!(push_u32, new_u32, u32);
-    def_func_push!(push_f32, new_f32, f32);
+    def_func_push!(push_i32, try_push_i32, new_i32, i32);
+    def_func_push!(push_u32, try_push_u32, new_u32, u32);
+    def_func_push!(push_f32, try_push_f32, new_f32, f32);
 
-    def_func_push!(push_i64, new_i64, i64);
-    def_func_push!(push_u64, new_u64, u64);
-    def_func_push!(push_f64, new_f64, f64);
+    def_func_push!(push_i64, try_push_i64, new_i64, i64);
+    def_func_push!(push_u64, try_push_u64, new_u64, u64);
+    def_func_push!(push_f64, try_push_f64, new_f64, f64);
 
-    def_func_push!(push_string, new_string, &'a str);
-    def_func_push!(push_bin, new_bin, &'a [u8]);
+    def_func_push!(push_string, try_push_string, new_string, &'a str);
+    def_func_push!(push_bin, try_push_bin, new_bin, &'a [u8]);
+
+    #[allow(dead_code)]
+    pub fn push_base64(&mut self, oid: usize, value: &'a str) {
+        let _ = self.try_push_base64(oid, value);
+    }
+
+    #[allow(dead_code)]
+    pub fn try_push_base64(&mut self, oid: usize, value: &'a str) -> Result<(), SmDtonError> {
+        self._check_node(oid, ST::SMDT_ARR)?;
+        let da = SmDtonData::try_new_b64(value)?;
+        self._do_push(oid, da);
+        return Ok(());
+    }
 
     #[allow(dead_code)]
     pub fn push_node(&mut self, oid: usize, new_oid: usize) {
+        let _ = self.try_push_node(oid, new_oid);
+    }
+
+    #[allow(dead_code)]
+    pub fn try_push_node(&mut self, oid: usize, new_oid: usize) -> Result<(), SmDtonError> {
+        self._check_node(oid, ST::SMDT_ARR)?;
+        if new_oid == 0 || new_oid > self.nnum {
+            return Err(SmDtonError::InvalidNodeId(new_oid));
+        }
         let ma = &self.nodes[new_oid - 1];
         self._do_push(oid, SmDtonData::new_node(ma));
+        return Ok(());
+    }
+}
+
+// "$B64$" text is binary, when not strict text that does not decode stays text
+fn _string_data<'a>(s: &'a str, strict: bool) -> Result<SmDtonData<'a>, SmDtonError> {
+    if s.starts_with("$B64$") {
+        match SmDtonData::try_new_b64(s) {
+            Ok(da) => return Ok(da),
+            Err(e) if strict => return Err(e),
+            Err(_) => {}
+        }
     }
+    return Ok(SmDtonData::new_string(s));
 }
@@ -1,4 +1,6 @@
+use super::sd_error::SmDtonError;
 use super::sd_node::SmDtonNode;
+use super::sd_value::SmDtonValue;
 use base64::{engine::general_purpose, Engine as _};
 
 pub struct ST {}
@@ -6,12 +8,14 @@ pub struct ST {}
 impl ST {
     // format type
     pub const SMTY_DTR: u8 = 0x01;
+    pub const SMTY_FLAG_SORTED: u8 = 0x80; // map entries ordered by key bytes
 
     // node type
     pub const SMDT_MAP: u8 = 0x01;
     pub const SMDT_ARR: u8 = 0x02;
 
     // data type
+    pub const SMDT_NUL: u8 = 0x10; // json
     pub const SMDT_BOO: u8 = 0x11; // json
     pub const SMDT_UI8: u8 = 0x12;
 
@@ -25,6 +29,7 @@ impl ST {
     pub const SMDT_I64: u8 = 0x18; // json
     pub const SMDT_U64: u8 = 0x19; // rare
     pub const SMDT_F64: u8 = 0x1a; // json
+    pub const SMDT_DEL: u8 = 0x1f; // overlay tombstone, the key is absent
 
     pub const SMDT_STR: u8 = 0x21; // json
     pub const SMDT_BIN: u8 = 0x22;
@@ -72,6 +77,16 @@ pub struct SmDtonData<'a> {
 }
 
 impl<'a> SmDtonData<'a> {
+    #[inline]
+    pub fn new_null() -> Self {
+        smd_new_data!(ST::SMDT_NUL, 0, false, None, Some(Vec::new()))
+    }
+
+    #[inline]
+    pub fn new_delete() -> Self {
+        smd_new_data!(ST::SMDT_DEL, 0, false, None, Some(Vec::new()))
+    }
+
     #[inline]
     pub fn new_bool(data: bool) -> Self {
         let mut d = 0;
@@ -111,22 +126,125 @@ impl<'a> SmDtonData<'a> {
     }
 
     #[inline]
-    pub fn new_b64(data: &str) -> Self {
-        let piece = &data[5..];
+    pub fn new_string_owned(data: String) -> Self {
+        let v = data.into_bytes();
+        smd_new_data!(ST::SMDT_STR, v.len() + 1, true, None, Some(v))
+    }
+
+    #[inline]
+    pub fn new_bin_owned(data: Vec<u8>) -> Self {
+        smd_new_data!(ST::SMDT_BIN, data.len(), true, None, Some(data))
+    }
+
+    #[inline]
+    pub fn try_new_b64(data: &str) -> Result<Self, SmDtonError> {
+        let piece = data.get(5..).ok_or(SmDtonError::InvalidBase64)?;
         let bytes = general_purpose::STANDARD
             .decode(piece)
-            .expect("Found invalid");
-        SmDtonData {
+            .map_err(|_| SmDtonError::InvalidBase64)?;
+        Ok(SmDtonData {
             smdt: ST::SMDT_B64,
             len: bytes.len(),
             has_len: true,
             u8a: None,
             v8a: Some(bytes),
             oid: 0,
+        })
+    }
+
+    // json number parts to the narrowest exact type: i64, then u64, then f64.
+    // truncated parts lost digits in the parser and are never an exact integer
+    pub fn try_new_number(
+        positive: bool,
+        mantissa: u64,
+        exponent: i16,
+        truncated: bool,
+        exact: bool,
+        narrow: bool,
+    ) -> Result<Self, SmDtonError> {
+        if exponent >= 0 && !truncated {
+            let ab = match 10u64.checked_pow(exponent as u32) {
+                Some(pw) => mantissa.checked_mul(pw),
+                None => None,
+            };
+            match ab {
+                Some(ab) if positive => {
+                    return Ok(SmDtonData::_new_int(ab as i128, narrow));
+                }
+                Some(ab) if ab > 0 && ab - 1 <= i64::MAX as u64 => {
+                    return Ok(SmDtonData::_new_int(-(ab as i128), narrow));
+                }
+                _ => {}
+            }
         }
+
+        let text = format!("{}e{}", mantissa, exponent);
+        let mut v: f64 = text.parse().unwrap_or(f64::INFINITY);
+        if !positive {
+            v = -v;
+        }
+        if exact && truncated {
+            return Err(SmDtonError::InexactNumber);
+        }
+        if exact && exponent >= 0 && mantissa > 0 {
+            // an integer must come back digit for digit
+            let digits = format!("{:.0}", v.abs());
+            let want = mantissa.to_string() + &"0".repeat(exponent as usize);
+            if !v.is_finite() || digits != want {
+                return Err(SmDtonError::InexactNumber);
+            }
+        }
+        return Ok(SmDtonData::new_f64(v));
+    }
+
+    // v is always within i64::MIN..=u64::MAX
+    fn _new_int(v: i128, narrow: bool) -> Self {
+        if narrow {
+            if let Ok(n) = u8::try_from(v) {
+                return SmDtonData::new_u8(n);
+            }
+            if let Ok(n) = i16::try_from(v) {
+                return SmDtonData::new_i16(n);
+            }
+            if let Ok(n) = u16::try_from(v) {
+                return SmDtonData::new_u16(n);
+            }
+            if let Ok(n) = i32::try_from(v) {
+                return SmDtonData::new_i32(n);
+            }
+            if let Ok(n) = u32::try_from(v) {
+                return SmDtonData::new_u32(n);
+            }
+        }
+        if let Ok(n) = i64::try_from(v) {
+            return SmDtonData::new_i64(n);
+        }
+        return SmDtonData::new_u64(v as u64);
     }
 
     #[inline]
+    // scalar values as read, None for maps and arrays
+    pub(crate) fn from_scalar(value: SmDtonValue<'a>) -> Option<Self> {
+        let da = match value {
+            SmDtonValue::Null => SmDtonData::new_null(),
+            SmDtonValue::Bool(v) => SmDtonData::new_bool(v),
+            SmDtonValue::U8(v) => SmDtonData::new_u8(v),
+            SmDtonValue::I16(v) => SmDtonData::new_i16(v),
+            SmDtonValue::U16(v) => SmDtonData::new_u16(v),
+            SmDtonValue::I32(v) => SmDtonData::new_i32(v),
+            SmDtonValue::U32(v) => SmDtonData::new_u32(v),
+            SmDtonValue::F32(v) => SmDtonData::new_f32(v),
+            SmDtonValue::I64(v) => SmDtonData::new_i64(v),
+            SmDtonValue::U64(v) => SmDtonData::new_u64(v),
+            SmDtonValue::F64(v) => SmDtonData::new_f64(v),
+            SmDtonValue::Str(v) => SmDtonData::new_string(v),
+            SmDtonValue::Bin(v) => SmDtonData::new_bin(v),
+            SmDtonValue::Deleted => SmDtonData::new_delete(),
+            SmDtonValue::Map(_) | SmDtonValue::Arr(_) => return None,
+        };
+        return Some(da);
+    }
+
     pub fn new_node(data: &SmDtonNode) -> Self {
         SmDtonData {
             smdt: data.smdt,
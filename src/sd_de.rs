@@ -0,0 +1,552 @@
+// serde deserializer reading straight from a SmDtonReader
+//
+// strings and bytes are borrowed from the buffer, numbers may be read into
+// any rust type their value fits, and the encodings written by sd_ser come
+// back as they went in. errors below the root name the failing field in
+// path syntax, e.g. "servers[2].port: invalid type ..."
+
+use serde::de::{self, DeserializeSeed, Deserializer, IntoDeserializer, Visitor};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use crate::sd_layer::{self, SmDtonChain, SmDtonChains};
+use crate::sd_path;
+use crate::sd_reader::MAX_DEPTH;
+use crate::{SmDton, SmDtonBuffer, SmDtonError, SmDtonMergeOptions, SmDtonReader, SmDtonValue, ST};
+
+impl de::Error for SmDtonError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        return SmDtonError::Custom(msg.to_string());
+    }
+}
+
+pub fn from_buffer<'a, T: de::Deserialize<'a>>(smb: &'a SmDtonBuffer) -> Result<T, SmDtonError> {
+    let reader = SmDtonReader::try_new(smb.get_buffer())?;
+    return from_reader(&reader);
+}
+
+pub fn from_reader<'a, T: de::Deserialize<'a>>(
+    reader: &SmDtonReader<'a>,
+) -> Result<T, SmDtonError> {
+    return T::deserialize(reader);
+}
+
+// the top layer holding a key of the root map wins, tombstones remove keys.
+// nested maps and arrays are joined as the merge options of the SmDton say
+pub fn from_dton<'a, T: de::Deserialize<'a>>(dton: &SmDton<'a>) -> Result<T, SmDtonError> {
+    return T::deserialize(dton);
+}
+
+fn _root<'r, 'a>(
+    reader: &'r SmDtonReader<'a>,
+    under: Vec<SmDtonDeLayer<'r, 'a>>,
+    merge: SmDtonMergeOptions,
+) -> Result<SmDtonDe<'r, 'a>, SmDtonError> {
+    let value = match reader.node_type(1) {
+        ST::SMDT_MAP => SmDtonValue::Map(crate::SmDtonNodeRef { oid: 1 }),
+        ST::SMDT_ARR => SmDtonValue::Arr(crate::SmDtonNodeRef { oid: 1 }),
+        _ => return Err(SmDtonError::InvalidNodeId(1)),
+    };
+    return Ok(SmDtonDe {
+        reader: reader,
+        under: under,
+        merge: merge,
+        value: value,
+        path: String::new(),
+        depth: 0,
+        seen: Rc::new(RefCell::new(Vec::new())),
+    });
+}
+
+fn _at(path: &str, error: SmDtonError) -> SmDtonError {
+    match error {
+        SmDtonError::Field { .. } => return error,
+        _ => {
+            return SmDtonError::Field {
+                path: path.to_string(),
+                error: Box::new(error),
+            }
+        }
+    }
+}
+
+macro_rules! def_root_forward {
+    ($func_name:ident) => {
+        fn $func_name<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, SmDtonError> {
+            return self._root_de()?.$func_name(visitor);
+        }
+    };
+}
+
+macro_rules! def_root_deserializer {
+    ($ty: ty) => {
+        impl<'r, 'a> Deserializer<'a> for &'r $ty {
+            type Error = SmDtonError;
+
+            def_root_forward!(deserialize_any);
+            def_root_forward!(deserialize_option);
+
+            fn deserialize_newtype_struct<V: Visitor<'a>>(
+                self,
+                name: &'static str,
+                visitor: V,
+            ) -> Result<V::Value, SmDtonError> {
+                return self._root_de()?.deserialize_newtype_struct(name, visitor);
+            }
+
+            fn deserialize_enum<V: Visitor<'a>>(
+                self,
+                name: &'static str,
+                variants: &'static [&'static str],
+                visitor: V,
+            ) -> Result<V::Value, SmDtonError> {
+                return self._root_de()?.deserialize_enum(name, variants, visitor);
+            }
+
+            serde::forward_to_deserialize_any! {
+            <W: Visitor<'a>>
+                    bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+                    bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+                    identifier ignored_any
+                }
+        }
+    };
+}
+
+impl<'a> SmDtonReader<'a> {
+    fn _root_de<'r>(&'r self) -> Result<SmDtonDe<'r, 'a>, SmDtonError> {
+        return _root(self, Vec::new(), SmDtonMergeOptions::default());
+    }
+}
+
+impl<'a> SmDton<'a> {
+    fn _root_de<'r>(&'r self) -> Result<SmDtonDe<'r, 'a>, SmDtonError> {
+        let mut layers = self._layers();
+        if layers.is_empty() {
+            return Err(SmDtonError::InvalidNodeId(1));
+        }
+        let top = layers.remove(0);
+        // root maps of all layers merge whatever the options
+        let under = layers
+            .into_iter()
+            .filter(|q| q.node_type(1) == ST::SMDT_MAP)
+            .map(|q| (q, SmDtonValue::Map(crate::SmDtonNodeRef { oid: 1 })))
+            .collect();
+        return _root(top, under, self.merge_options());
+    }
+}
+
+def_root_deserializer!(SmDtonReader<'a>);
+def_root_deserializer!(SmDton<'a>);
+
+// one value of a reader, with the path that led to it
+pub struct SmDtonDe<'r, 'a> {
+    reader: &'r SmDtonReader<'a>,
+    // the same value in lower layers joined into this one, top first
+    under: Vec<SmDtonDeLayer<'r, 'a>>,
+    merge: SmDtonMergeOptions,
+    value: SmDtonValue<'a>,
+    path: String,
+    depth: usize,
+    seen: SmDtonDeSeen<'r, 'a>,
+}
+
+type SmDtonDeLayer<'r, 'a> = (&'r SmDtonReader<'a>, SmDtonValue<'a>);
+
+// nodes entered so far, per layer, shared by the whole walk
+type SmDtonDeSeen<'r, 'a> = Rc<RefCell<Vec<(&'r SmDtonReader<'a>, HashSet<usize>)>>>;
+
+// a key with its value in each layer holding it, lowest layer first
+type SmDtonDeEntry<'r, 'a> = (&'a str, SmDtonChain<'a, &'r SmDtonReader<'a>>);
+
+impl<'r, 'a> SmDtonDe<'r, 'a> {
+    // the value of a key with the lower values the merge options join into it,
+    // as SmDton::get_path reads them
+    fn _joined(
+        values: &[SmDtonDeLayer<'r, 'a>],
+        merge: SmDtonMergeOptions,
+        path: String,
+        depth: usize,
+        seen: SmDtonDeSeen<'r, 'a>,
+    ) -> Result<SmDtonDe<'r, 'a>, SmDtonError> {
+        let mut joined = sd_layer::joined(values, merge).to_vec();
+        let (reader, value) = match joined.pop() {
+            Some(top) => top,
+            None => return Err(SmDtonError::InvalidNodeId(0)),
+        };
+        joined.reverse();
+        return Ok(SmDtonDe {
+            reader: reader,
+            under: joined,
+            merge: merge,
+            value: value,
+            path: path,
+            depth: depth,
+            seen: seen,
+        });
+    }
+
+    // each node is entered once and no deeper than MAX_DEPTH, as with to_json,
+    // so a broken buffer cannot send the walk around a cycle
+    fn _enter(&self, reader: &'r SmDtonReader<'a>, oid: usize) -> Result<(), SmDtonError> {
+        if self.depth > MAX_DEPTH {
+            return Err(SmDtonError::TooDeep { depth: self.depth });
+        }
+        let mut seen = self.seen.borrow_mut();
+        let at = match seen.iter().position(|(q, _)| std::ptr::eq(*q, reader)) {
+            Some(at) => at,
+            None => {
+                seen.push((reader, HashSet::new()));
+                seen.len() - 1
+            }
+        };
+        if !seen[at].1.insert(oid) {
+            return Err(SmDtonError::RepeatedNode(oid));
+        }
+        return Ok(());
+    }
+}
+
+impl<'r, 'a> Deserializer<'a> for SmDtonDe<'r, 'a> {
+    type Error = SmDtonError;
+
+    fn deserialize_any<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, SmDtonError> {
+        match self.value {
+            SmDtonValue::Null | SmDtonValue::Deleted => return visitor.visit_unit(),
+            SmDtonValue::Bool(v) => return visitor.visit_bool(v),
+            SmDtonValue::U8(v) => return visitor.visit_u8(v),
+            SmDtonValue::I16(v) => return visitor.visit_i16(v),
+            SmDtonValue::U16(v) => return visitor.visit_u16(v),
+            SmDtonValue::I32(v) => return visitor.visit_i32(v),
+            SmDtonValue::U32(v) => return visitor.visit_u32(v),
+            SmDtonValue::F32(v) => return visitor.visit_f32(v),
+            SmDtonValue::I64(v) => return visitor.visit_i64(v),
+            SmDtonValue::U64(v) => return visitor.visit_u64(v),
+            SmDtonValue::F64(v) => return visitor.visit_f64(v),
+            SmDtonValue::Str(v) => return visitor.visit_borrowed_str(v),
+            SmDtonValue::Bin(v) => return visitor.visit_borrowed_bytes(v),
+            SmDtonValue::Map(node) => {
+                let mut entries = SmDtonChains::new(true);
+                for (q, v) in self.under.iter().rev() {
+                    if let SmDtonValue::Map(under) = v {
+                        self._enter(q, under.oid)?;
+                        entries.add(*q, q, under.oid)?;
+                    }
+                }
+                self._enter(self.reader, node.oid)?;
+                entries.add(self.reader, self.reader, node.oid)?;
+                return visitor.visit_map(SmDtonMapAccess {
+                    entries: entries.live().into_iter(),
+                    value: None,
+                    merge: self.merge,
+                    path: self.path,
+                    depth: self.depth + 1,
+                    seen: self.seen,
+                });
+            }
+            SmDtonValue::Arr(node) => {
+                // joined arrays run from the lowest layer up
+                let mut nodes = Vec::new();
+                for (q, v) in self.under.iter().rev() {
+                    if let SmDtonValue::Arr(under) = v {
+                        self._enter(q, under.oid)?;
+                        nodes.push((*q, under.oid));
+                    }
+                }
+                self._enter(self.reader, node.oid)?;
+                nodes.push((self.reader, node.oid));
+                let len = nodes.iter().map(|(q, oid)| q.node_sub_num(*oid)).sum();
+                return visitor.visit_seq(SmDtonSeqAccess {
+                    nodes: nodes,
+                    node: 0,
+                    index: 0,
+                    count: 0,
+                    len: len,
+                    path: self.path,
+                    depth: self.depth + 1,
+                    seen: self.seen,
+                });
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, SmDtonError> {
+        match self.value {
+            SmDtonValue::Null => return visitor.visit_none(),
+            _ => return visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'a>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SmDtonError> {
+        return visitor.visit_newtype_struct(self);
+    }
+
+    // a unit variant is its name, any other variant a map of one entry
+    fn deserialize_enum<V: Visitor<'a>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SmDtonError> {
+        match self.value {
+            SmDtonValue::Str(v) => return visitor.visit_enum(v.into_deserializer()),
+            SmDtonValue::Map(node) if self.reader.node_sub_num(node.oid) == 1 => {
+                self._enter(self.reader, node.oid)?;
+                let (key, value_off) = match self.reader.try_get_sub_entry(node.oid, 0)? {
+                    Some(entry) => entry,
+                    None => return Err(SmDtonError::InvalidNodeId(node.oid)),
+                };
+                let mut path = self.path;
+                sd_path::push_path_key(&mut path, key);
+                return visitor.visit_enum(SmDtonEnumAccess {
+                    variant: key,
+                    value: SmDtonDe {
+                        reader: self.reader,
+                        under: Vec::new(),
+                        merge: self.merge,
+                        value: self.reader.try_get_value_by_voff(value_off)?,
+                        path: path,
+                        depth: self.depth + 1,
+                        seen: self.seen,
+                    },
+                });
+            }
+            _ => {
+                return Err(SmDtonError::Custom(
+                    "expected a variant name or a map of one entry".to_string(),
+                ))
+            }
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, SmDtonError> {
+        return visitor.visit_unit();
+    }
+
+    serde::forward_to_deserialize_any! {
+        <W: Visitor<'a>>
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+    }
+}
+
+struct SmDtonMapAccess<'r, 'a> {
+    entries: std::vec::IntoIter<SmDtonDeEntry<'r, 'a>>,
+    value: Option<(SmDtonChain<'a, &'r SmDtonReader<'a>>, String)>,
+    merge: SmDtonMergeOptions,
+    path: String,
+    depth: usize,
+    seen: SmDtonDeSeen<'r, 'a>,
+}
+
+impl<'r, 'a> de::MapAccess<'a> for SmDtonMapAccess<'r, 'a> {
+    type Error = SmDtonError;
+
+    fn next_key_seed<K: DeserializeSeed<'a>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, SmDtonError> {
+        match self.entries.next() {
+            Some((key, values)) => {
+                let mut path = self.path.clone();
+                sd_path::push_path_key(&mut path, key);
+                self.value = Some((values, path));
+                return seed.deserialize(SmDtonKeyDe { key: key }).map(Some);
+            }
+            None => return Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'a>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, SmDtonError> {
+        match self.value.take() {
+            Some((values, path)) => {
+                let seen = self.seen.clone();
+                let de = SmDtonDe::_joined(&values, self.merge, path.clone(), self.depth, seen)
+                    .map_err(|e| _at(&path, e))?;
+                return seed.deserialize(de).map_err(|e| _at(&path, e));
+            }
+            None => return Err(SmDtonError::Custom("map value without a key".to_string())),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        return Some(self.entries.len());
+    }
+}
+
+// map keys are text, integer keys written by sd_ser are parsed back
+struct SmDtonKeyDe<'a> {
+    key: &'a str,
+}
+
+macro_rules! def_key_int {
+    ($func_name:ident, $visit:ident) => {
+        fn $func_name<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, SmDtonError> {
+            match self.key.parse() {
+                Ok(v) => return visitor.$visit(v),
+                Err(_) => return visitor.visit_borrowed_str(self.key),
+            }
+        }
+    };
+}
+
+impl<'a> Deserializer<'a> for SmDtonKeyDe<'a> {
+    type Error = SmDtonError;
+
+    fn deserialize_any<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, SmDtonError> {
+        return visitor.visit_borrowed_str(self.key);
+    }
+
+    def_key_int!(deserialize_i8, visit_i8);
+    def_key_int!(deserialize_i16, visit_i16);
+    def_key_int!(deserialize_i32, visit_i32);
+    def_key_int!(deserialize_i64, visit_i64);
+    def_key_int!(deserialize_i128, visit_i128);
+    def_key_int!(deserialize_u8, visit_u8);
+    def_key_int!(deserialize_u16, visit_u16);
+    def_key_int!(deserialize_u32, visit_u32);
+    def_key_int!(deserialize_u64, visit_u64);
+    def_key_int!(deserialize_u128, visit_u128);
+
+    fn deserialize_newtype_struct<V: Visitor<'a>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SmDtonError> {
+        return visitor.visit_newtype_struct(self);
+    }
+
+    fn deserialize_enum<V: Visitor<'a>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SmDtonError> {
+        return visitor.visit_enum(self.key.into_deserializer());
+    }
+
+    serde::forward_to_deserialize_any! {
+        <W: Visitor<'a>>
+        bool f32 f64 char str string bytes byte_buf option unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct SmDtonSeqAccess<'r, 'a> {
+    // array nodes read one after another
+    nodes: Vec<(&'r SmDtonReader<'a>, usize)>,
+    node: usize,
+    index: usize,
+    count: usize,
+    len: usize,
+    path: String,
+    depth: usize,
+    seen: SmDtonDeSeen<'r, 'a>,
+}
+
+impl<'r, 'a> de::SeqAccess<'a> for SmDtonSeqAccess<'r, 'a> {
+    type Error = SmDtonError;
+
+    fn next_element_seed<T: DeserializeSeed<'a>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, SmDtonError> {
+        let (reader, oid) = loop {
+            match self.nodes.get(self.node) {
+                Some((q, oid)) if self.index >= q.node_sub_num(*oid) => {
+                    self.node += 1;
+                    self.index = 0;
+                }
+                Some(node) => break *node,
+                None => return Ok(None),
+            }
+        };
+        let path = format!("{}[{}]", self.path, self.count);
+        let value_off = match reader.try_get_sub_voff(oid, self.index) {
+            Ok(Some(value_off)) => value_off,
+            Ok(None) => return Ok(None),
+            Err(e) => return Err(_at(&path, e)),
+        };
+        self.index += 1;
+        self.count += 1;
+        let value = reader
+            .try_get_value_by_voff(value_off)
+            .map_err(|e| _at(&path, e))?;
+        let de = SmDtonDe {
+            reader: reader,
+            under: Vec::new(),
+            merge: SmDtonMergeOptions::default(),
+            value: value,
+            path: path.clone(),
+            depth: self.depth,
+            seen: self.seen.clone(),
+        };
+        return seed.deserialize(de).map(Some).map_err(|e| _at(&path, e));
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        return Some(self.len - self.count);
+    }
+}
+
+struct SmDtonEnumAccess<'r, 'a> {
+    variant: &'a str,
+    value: SmDtonDe<'r, 'a>,
+}
+
+impl<'r, 'a> de::EnumAccess<'a> for SmDtonEnumAccess<'r, 'a> {
+    type Error = SmDtonError;
+    type Variant = SmDtonDe<'r, 'a>;
+
+    fn variant_seed<V: DeserializeSeed<'a>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, SmDtonDe<'r, 'a>), SmDtonError> {
+        let de: de::value::BorrowedStrDeserializer<'a, SmDtonError> =
+            de::value::BorrowedStrDeserializer::new(self.variant);
+        let variant = seed.deserialize(de)?;
+        return Ok((variant, self.value));
+    }
+}
+
+impl<'r, 'a> de::VariantAccess<'a> for SmDtonDe<'r, 'a> {
+    type Error = SmDtonError;
+
+    fn unit_variant(self) -> Result<(), SmDtonError> {
+        return de::Deserialize::deserialize(self);
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'a>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, SmDtonError> {
+        let path = self.path.clone();
+        return seed.deserialize(self).map_err(|e| _at(&path, e));
+    }
+
+    fn tuple_variant<V: Visitor<'a>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SmDtonError> {
+        let path = self.path.clone();
+        return self.deserialize_any(visitor).map_err(|e| _at(&path, e));
+    }
+
+    fn struct_variant<V: Visitor<'a>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SmDtonError> {
+        let path = self.path.clone();
+        return self.deserialize_any(visitor).map_err(|e| _at(&path, e));
+    }
+}
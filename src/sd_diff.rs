@@ -0,0 +1,245 @@
+// update buffer that turns old into new when laid over it
+//
+// root maps are compared key by key and only changed entries are kept, keys
+// missing from new become tombstones. values are equal only with the same
+// type, so a u8 turned i64 is written again. diff follows the default merge
+// of SmDtonPair and SmDton, a changed root entry is replaced whole. diff_with
+// follows the given options: with deep it recurses into nested maps, and with
+// concat an array can only grow at its end. anything else is replaced whole,
+// arrays included.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::sd_data::SmDtonData;
+use crate::sd_reader::MAX_DEPTH;
+use crate::{
+    SmDtonArrayMerge, SmDtonBuffer, SmDtonBuilder, SmDtonError, SmDtonMergeOptions, SmDtonReader,
+    SmDtonValue, ST,
+};
+
+type SmDtonDiffEntry<'a> = (&'a str, SmDtonValue<'a>);
+
+// an empty buffer when either side does not read
+pub fn diff(old: &SmDtonBuffer, new: &SmDtonBuffer) -> SmDtonBuffer {
+    return try_diff(old, new).unwrap_or_else(|_| SmDtonBuffer::new());
+}
+
+pub fn try_diff(old: &SmDtonBuffer, new: &SmDtonBuffer) -> Result<SmDtonBuffer, SmDtonError> {
+    return try_diff_with(old, new, SmDtonMergeOptions::default());
+}
+
+pub fn diff_with(
+    old: &SmDtonBuffer,
+    new: &SmDtonBuffer,
+    merge: SmDtonMergeOptions,
+) -> SmDtonBuffer {
+    return try_diff_with(old, new, merge).unwrap_or_else(|_| SmDtonBuffer::new());
+}
+
+pub fn try_diff_with(
+    old: &SmDtonBuffer,
+    new: &SmDtonBuffer,
+    merge: SmDtonMergeOptions,
+) -> Result<SmDtonBuffer, SmDtonError> {
+    let differ = SmDtonDiff {
+        old: SmDtonReader::try_new(old.get_buffer())?,
+        new: SmDtonReader::try_new(new.get_buffer())?,
+        merge: merge,
+    };
+    let mut bd = SmDtonBuilder::new();
+    let mut seen = HashSet::new();
+    if differ.old.node_type(1) != ST::SMDT_MAP || differ.new.node_type(1) != ST::SMDT_MAP {
+        // without two root maps the top layer is read alone
+        bd._copy_node(&differ.new, 1, &mut seen, 0)?;
+        return Ok(bd.build());
+    }
+
+    let root = bd.create_node(ST::SMDT_MAP);
+    differ.diff_map(&mut bd, 1, 1, root, &mut seen, 0)?;
+    return Ok(bd.build());
+}
+
+struct SmDtonDiff<'a> {
+    old: SmDtonReader<'a>,
+    new: SmDtonReader<'a>,
+    merge: SmDtonMergeOptions,
+}
+
+impl<'a> SmDtonDiff<'a> {
+    // entries of new that differ from old, added to the map node out
+    fn diff_map(
+        &self,
+        bd: &mut SmDtonBuilder<'a>,
+        a: usize,
+        b: usize,
+        out: usize,
+        seen: &mut HashSet<usize>,
+        depth: usize,
+    ) -> Result<(), SmDtonError> {
+        if depth > MAX_DEPTH {
+            return Err(SmDtonError::TooDeep { depth: depth });
+        }
+        let olds = _live_entries(&self.old, a)?;
+        let news = _live_entries(&self.new, b)?;
+        let old_at: HashMap<&str, SmDtonValue<'a>> = olds.iter().copied().collect();
+        let new_keys: HashSet<&str> = news.iter().map(|entry| entry.0).collect();
+
+        for (key, vb) in news.iter().copied() {
+            let va = match old_at.get(key) {
+                Some(va) => *va,
+                None => {
+                    let da = bd._copy_value(&self.new, vb, seen, depth + 1)?;
+                    bd._do_add(out, key, da);
+                    continue;
+                }
+            };
+            if self.same(va, vb, depth + 1)? {
+                continue;
+            }
+            match (va, vb) {
+                (SmDtonValue::Map(na), SmDtonValue::Map(nb)) if self.merge.deep => {
+                    let child = bd.create_node(ST::SMDT_MAP);
+                    self.diff_map(bd, na.oid, nb.oid, child, seen, depth + 1)?;
+                    let da = bd._node_data(child);
+                    bd._do_add(out, key, da);
+                }
+                (SmDtonValue::Arr(na), SmDtonValue::Arr(nb))
+                    if self.merge.arrays == SmDtonArrayMerge::Concat =>
+                {
+                    let child = self.diff_tail(bd, na.oid, nb.oid, seen, depth + 1)?;
+                    let da = bd._node_data(child);
+                    bd._do_add(out, key, da);
+                }
+                _ => {
+                    let da = bd._copy_value(&self.new, vb, seen, depth + 1)?;
+                    bd._do_add(out, key, da);
+                }
+            }
+        }
+
+        for (key, _) in olds.iter() {
+            if !new_keys.contains(key) {
+                bd._do_add(out, *key, SmDtonData::new_delete());
+            }
+        }
+        return Ok(());
+    }
+
+    // items of new past the ones of old, which must come first unchanged
+    fn diff_tail(
+        &self,
+        bd: &mut SmDtonBuilder<'a>,
+        a: usize,
+        b: usize,
+        seen: &mut HashSet<usize>,
+        depth: usize,
+    ) -> Result<usize, SmDtonError> {
+        let olds = _items(&self.old, a)?;
+        let news = _items(&self.new, b)?;
+        let mut kept = olds.len() <= news.len();
+        for (va, vb) in olds.iter().zip(news.iter()) {
+            if !kept {
+                break;
+            }
+            kept = self.same(*va, *vb, depth + 1)?;
+        }
+        if !kept {
+            return Err(SmDtonError::Custom(
+                "array changed before its end cannot be concatenated".to_string(),
+            ));
+        }
+
+        let out = bd.create_node(ST::SMDT_ARR);
+        for vb in news[olds.len()..].iter() {
+            let da = bd._copy_value(&self.new, *vb, seen, depth + 1)?;
+            bd._do_push(out, da);
+        }
+        return Ok(out);
+    }
+
+    // same type and value, floats by their bits
+    fn same(
+        &self,
+        va: SmDtonValue<'a>,
+        vb: SmDtonValue<'a>,
+        depth: usize,
+    ) -> Result<bool, SmDtonError> {
+        if depth > MAX_DEPTH {
+            return Err(SmDtonError::TooDeep { depth: depth });
+        }
+        match (va, vb) {
+            (SmDtonValue::Map(na), SmDtonValue::Map(nb)) => {
+                let olds = _live_entries(&self.old, na.oid)?;
+                let news = _live_entries(&self.new, nb.oid)?;
+                if olds.len() != news.len() {
+                    return Ok(false);
+                }
+                let old_at: HashMap<&str, SmDtonValue<'a>> = olds.into_iter().collect();
+                for (key, vb) in news {
+                    match old_at.get(key) {
+                        Some(va) if self.same(*va, vb, depth + 1)? => {}
+                        _ => return Ok(false),
+                    }
+                }
+                return Ok(true);
+            }
+            (SmDtonValue::Arr(na), SmDtonValue::Arr(nb)) => {
+                let olds = _items(&self.old, na.oid)?;
+                let news = _items(&self.new, nb.oid)?;
+                if olds.len() != news.len() {
+                    return Ok(false);
+                }
+                for (va, vb) in olds.into_iter().zip(news) {
+                    if !self.same(va, vb, depth + 1)? {
+                        return Ok(false);
+                    }
+                }
+                return Ok(true);
+            }
+            (SmDtonValue::Map(_), _) | (SmDtonValue::Arr(_), _) => return Ok(false),
+            (SmDtonValue::F32(x), SmDtonValue::F32(y)) => return Ok(x.to_bits() == y.to_bits()),
+            (SmDtonValue::F64(x), SmDtonValue::F64(y)) => return Ok(x.to_bits() == y.to_bits()),
+            _ => return Ok(va == vb),
+        }
+    }
+}
+
+// entries of a map as lookups see them: the last of equal keys wins in the
+// place of the first, tombstones are left out
+pub(crate) fn _live_entries<'a>(
+    q: &SmDtonReader<'a>,
+    oid: usize,
+) -> Result<Vec<SmDtonDiffEntry<'a>>, SmDtonError> {
+    let mut entries: Vec<SmDtonDiffEntry<'a>> = Vec::new();
+    let mut position: HashMap<&str, usize> = HashMap::new();
+    for index in 0..q.node_sub_num(oid) {
+        let (key, value_off) = match q.try_get_sub_entry(oid, index)? {
+            Some(entry) => entry,
+            None => break,
+        };
+        let value = q.try_get_value_by_voff(value_off)?;
+        match position.get(key) {
+            Some(&at) => entries[at] = (key, value),
+            None => {
+                position.insert(key, entries.len());
+                entries.push((key, value));
+            }
+        }
+    }
+    entries.retain(|entry| entry.1 != SmDtonValue::Deleted);
+    return Ok(entries);
+}
+
+pub(crate) fn _items<'a>(
+    q: &SmDtonReader<'a>,
+    oid: usize,
+) -> Result<Vec<SmDtonValue<'a>>, SmDtonError> {
+    let mut items = Vec::new();
+    for index in 0..q.node_sub_num(oid) {
+        match q.try_get_sub_voff(oid, index)? {
+            Some(value_off) => items.push(q.try_get_value_by_voff(value_off)?),
+            None => break,
+        }
+    }
+    return Ok(items);
+}
@@ -1,123 +1,246 @@
+#[cfg(feature = "json")]
 use json::JsonValue;
 
-use crate::{SmDtonBuffer, SmDtonPair, SmDtonReader};
+use crate::sd_layer;
+use crate::sd_path;
+use crate::{
+    SmDtonBuffer, SmDtonError, SmDtonMergeOptions, SmDtonPair, SmDtonPath, SmDtonPathSeg,
+    SmDtonReader, SmDtonValue, ST,
+};
 
 macro_rules! def_get_func {
     ($func_name:ident, $dty: ty) => {
         #[allow(dead_code)]
         pub fn $func_name(&self, key: &str) -> Option<$dty> {
-            match &self.nread {
-                Some(q) => {
-                    let op = q.$func_name(1, key);
-                    match op {
-                        Some(data) => return Some(data),
-                        _ => {}
-                    }
+            for q in self.layers.iter().rev() {
+                if _deleted(q, key) {
+                    return None;
                 }
-                _ => {}
-            }
-            match &self.oread {
-                Some(q) => {
-                    let op = q.$func_name(1, key);
-                    match op {
-                        Some(data) => return Some(data),
-                        _ => {}
-                    }
+                let op = q.$func_name(1, key);
+                match op {
+                    Some(data) => return Some(data),
+                    _ => {}
                 }
-                _ => {}
             }
             return None
         }
     };
 }
 
+macro_rules! def_try_get_func {
+    ($func_name:ident, $act_name:ident, $dty: ty) => {
+        #[allow(dead_code)]
+        pub fn $func_name(&self, key: &str) -> Result<Option<$dty>, SmDtonError> {
+            for q in self.layers.iter().rev() {
+                if _deleted(q, key) {
+                    return Ok(None);
+                }
+                match q.$act_name(1, key)? {
+                    Some(data) => return Ok(Some(data)),
+                    None => {}
+                }
+            }
+            return Ok(None);
+        }
+    };
+}
+
+macro_rules! def_get_path_func {
+    ($func_name:ident, $act_name:ident, $dty: ty) => {
+        #[allow(dead_code)]
+        pub fn $func_name(&self, path: &str) -> Option<$dty> {
+            let path = SmDtonPath::parse(path).ok()?;
+            let (q, value_off) = self._try_path_voff(&path.segs(), false).ok()??;
+            return q.$act_name(value_off);
+        }
+    };
+}
+
+macro_rules! def_try_get_path_func {
+    ($func_name:ident, $act_name:ident, $dty: ty) => {
+        #[allow(dead_code)]
+        pub fn $func_name(&self, path: &str) -> Result<Option<$dty>, SmDtonError> {
+            let path = SmDtonPath::parse(path)?;
+            match self._try_path_voff(&path.segs(), false)? {
+                Some((q, value_off)) => return Ok(Some(q.$act_name(value_off)?)),
+                None => return Ok(None),
+            }
+        }
+    };
+}
+
+macro_rules! def_get_pointer_func {
+    ($func_name:ident, $act_name:ident, $dty: ty) => {
+        #[allow(dead_code)]
+        pub fn $func_name(&self, pointer: &str) -> Option<$dty> {
+            let (q, value_off) = self._try_pointer_voff(pointer).ok()??;
+            return q.$act_name(value_off);
+        }
+    };
+}
+
+macro_rules! def_try_get_pointer_func {
+    ($func_name:ident, $act_name:ident, $dty: ty) => {
+        #[allow(dead_code)]
+        pub fn $func_name(&self, pointer: &str) -> Result<Option<$dty>, SmDtonError> {
+            match self._try_pointer_voff(pointer)? {
+                Some((q, value_off)) => return Ok(Some(q.$act_name(value_off)?)),
+                None => return Ok(None),
+            }
+        }
+    };
+}
+
 #[allow(dead_code)]
 pub struct SmDton<'a> {
-    oread: Option<SmDtonReader<'a>>,
-    nread: Option<SmDtonReader<'a>>,
+    // base layer first, lookups start from the last one
+    layers: Vec<SmDtonReader<'a>>,
+    merge: SmDtonMergeOptions,
 }
 
 impl<'a> SmDton<'a> {
     pub fn new_from_buffer(smb: &'a SmDtonBuffer) -> Self {
         let buf = smb.get_buffer();
-        if buf.len() > 0 {
-            SmDton {
-                oread: Some(SmDtonReader::new(buf)),
-                nread: None,
-            }
-        } else {
-            SmDton {
-                oread: None,
-                nread: None,
-            }
+        let mut layers = Vec::new();
+        if !buf.is_empty() {
+            layers.push(SmDtonReader::new(buf));
+        }
+        SmDton {
+            layers: layers,
+            merge: SmDtonMergeOptions::default(),
         }
     }
 
+    // empty buffers of the pair are skipped
     pub fn new_from_pair(pair: &'a SmDtonPair) -> Self {
-        let buf1 = pair.raw.get_buffer();
-        let buf2 = pair.update.get_buffer();
-        if buf1.len() > 0 {
-            if buf2.len() > 0 {
-                SmDton {
-                    oread: Some(SmDtonReader::new(buf1)),
-                    nread: Some(SmDtonReader::new(buf2)),
-                }
-            } else {
-                SmDton {
-                    oread: Some(SmDtonReader::new(buf1)),
-                    nread: None,
-                }
-            }
-        } else {
-            SmDton {
-                oread: None,
-                nread: None,
+        let layers = pair
+            .layers
+            .iter()
+            .filter(|smb| !smb.get_buffer().is_empty())
+            .map(|smb| SmDtonReader::new(smb.get_buffer()))
+            .collect();
+        SmDton {
+            layers: layers,
+            merge: SmDtonMergeOptions::default(),
+        }
+    }
+
+    pub fn try_new_from_buffer(smb: &'a SmDtonBuffer) -> Result<Self, SmDtonError> {
+        let buf = smb.get_buffer();
+        let mut layers = Vec::new();
+        if !buf.is_empty() {
+            layers.push(SmDtonReader::try_new(buf)?);
+        }
+        return Ok(SmDton {
+            layers: layers,
+            merge: SmDtonMergeOptions::default(),
+        });
+    }
+
+    pub fn try_new_from_pair(pair: &'a SmDtonPair) -> Result<Self, SmDtonError> {
+        let mut layers = Vec::new();
+        for smb in pair.layers.iter() {
+            if !smb.get_buffer().is_empty() {
+                layers.push(SmDtonReader::try_new(smb.get_buffer())?);
             }
         }
+        return Ok(SmDton {
+            layers: layers,
+            merge: SmDtonMergeOptions::default(),
+        });
     }
 
+    // replaces the top layer, the base stays
     pub fn update(&mut self, vec: &'a [u8]) {
-        self.nread = Some(SmDtonReader::new(vec));
+        self._set_update(SmDtonReader::new(vec));
+    }
+
+    pub fn try_update(&mut self, vec: &'a [u8]) -> Result<(), SmDtonError> {
+        self._set_update(SmDtonReader::try_new(vec)?);
+        return Ok(());
     }
 
     pub fn update_by_dton(&mut self, ndt: &SmDton<'a>) {
-        if self.oread.is_some() {
-            match &ndt.oread {
-                Some(rd) => {
-                    self.nread = Some(rd.clone());
-                }
-                _ => {}
-            }
+        if self.layers.is_empty() {
+            return;
+        }
+        if let Some(rd) = ndt.layers.first() {
+            self._set_update(rd.clone());
+        }
+    }
+
+    fn _set_update(&mut self, q: SmDtonReader<'a>) {
+        if self.layers.len() > 1 {
+            self.layers.pop();
         }
+        self.layers.push(q);
+    }
+
+    // stack of layers, index 0 is the base
+
+    pub fn push_layer(&mut self, vec: &'a [u8]) {
+        self.layers.push(SmDtonReader::new(vec));
+    }
+
+    pub fn try_push_layer(&mut self, vec: &'a [u8]) -> Result<(), SmDtonError> {
+        self.layers.push(SmDtonReader::try_new(vec)?);
+        return Ok(());
+    }
+
+    pub fn pop_layer(&mut self) -> Option<SmDtonReader<'a>> {
+        return self.layers.pop();
+    }
+
+    pub fn layer_count(&self) -> usize {
+        return self.layers.len();
+    }
+
+    pub fn layer(&self, index: usize) -> Option<&SmDtonReader<'a>> {
+        return self.layers.get(index);
+    }
+
+    // how layers join below the root keys, shallow unless set
+    pub fn set_merge(&mut self, merge: SmDtonMergeOptions) {
+        self.merge = merge;
+    }
+
+    pub fn merge_options(&self) -> SmDtonMergeOptions {
+        return self.merge;
     }
 
     pub fn get_string(&self, key: &str) -> Option<String> {
-        match &self.nread {
-            Some(q) => {
-                let op = q.get_string(1, key);
-                match op {
-                    Some(data) => return Some(data.to_string()),
-                    _ => {}
-                }
+        for q in self.layers.iter().rev() {
+            if _deleted(q, key) {
+                return None;
             }
-            None => {}
-        }
-        match &self.oread {
-            Some(q) => {
-                let op = q.get_string(1, key);
-                match op {
-                    Some(data) => return Some(data.to_string()),
-                    _ => {}
-                }
+            let op = q.get_string(1, key);
+            if let Some(data) = op {
+                return Some(data.to_string());
             }
-            None => {}
         }
         return None;
     }
 
+    pub fn try_get_string(&self, key: &str) -> Result<Option<String>, SmDtonError> {
+        for q in self.layers.iter().rev() {
+            if _deleted(q, key) {
+                return Ok(None);
+            }
+            if let Some(data) = q.try_get_string(1, key)? {
+                return Ok(Some(data.to_string()));
+            }
+        }
+        return Ok(None);
+    }
+
+    pub fn is_null(&self, key: &str) -> bool {
+        return self.get_null(key).is_some();
+    }
+
+    def_get_func!(get_null, ());
     def_get_func!(get_bool, bool);
     def_get_func!(get_u8, u8);
-    def_get_func!(get_bin, &[u8]);
+    def_get_func!(get_bin, &'a [u8]);
 
     def_get_func!(get_i16, i16);
     def_get_func!(get_u16, u16);
@@ -130,107 +253,371 @@ impl<'a> SmDton<'a> {
     def_get_func!(get_u64, u64);
     def_get_func!(get_f64, f64);
 
-    pub fn stringify(&self) -> Option<String> {
-        let mut ret: Option<JsonValue> = None;
-        match &self.oread {
-            Some(q) => {
-                let op = q.to_json(1);
-                match op {
-                    Some(jsn) => {
-                        ret = Some(jsn);
-                    }
-                    _ => {}
-                }
-            }
-            _ => {}
+    def_get_func!(get_int_lossless, i128);
+    def_get_func!(get_float, f64);
+
+    def_try_get_func!(try_get_null, try_get_null, ());
+    def_try_get_func!(try_get_bool, try_get_bool, bool);
+    def_try_get_func!(try_get_u8, try_get_u8, u8);
+    def_try_get_func!(try_get_bin, try_get_bin, &'a [u8]);
+
+    def_try_get_func!(try_get_i16, try_get_i16, i16);
+    def_try_get_func!(try_get_u16, try_get_u16, u16);
+
+    def_try_get_func!(try_get_i32, try_get_i32, i32);
+    def_try_get_func!(try_get_u32, try_get_u32, u32);
+    def_try_get_func!(try_get_f32, try_get_f32, f32);
+
+    def_try_get_func!(try_get_i64, try_get_i64, i64);
+    def_try_get_func!(try_get_u64, try_get_u64, u64);
+    def_try_get_func!(try_get_f64, try_get_f64, f64);
+
+    def_try_get_func!(as_i64, as_i64, i64);
+    def_try_get_func!(as_u64, as_u64, u64);
+    def_try_get_func!(as_f64, as_f64, f64);
+
+    // get value from path, the layer holding its first key answers alone
+    // unless the merge options join nested maps or arrays
+
+    #[allow(dead_code)]
+    pub fn get_path(&self, segs: &[SmDtonPathSeg]) -> Option<SmDtonValue<'a>> {
+        let (q, value_off) = self._try_path_voff(segs, false).ok()??;
+        return q.get_value_by_voff(value_off);
+    }
+
+    #[allow(dead_code)]
+    pub fn try_get_path(
+        &self,
+        segs: &[SmDtonPathSeg],
+    ) -> Result<Option<SmDtonValue<'a>>, SmDtonError> {
+        match self._try_path_voff(segs, false)? {
+            Some((q, value_off)) => return Ok(Some(q.try_get_value_by_voff(value_off)?)),
+            None => return Ok(None),
         }
-        match &self.nread {
-            Some(q) => {
-                let op = q.to_json(1);
-                match op {
-                    Some(jsn) => {
-                        if ret.is_some() {
-                            let mut r = ret.unwrap();
-                            for x in jsn.entries() {
-                                let k = x.0.to_string();
-                                let v = x.1;
-
-                                r[k] = v.clone();
-                            }
-                            return Some(r.to_string());
-                        } else {
-                            return Some(jsn.to_string());
+    }
+
+    // top layer holding the first key or index, or the base. pointer keys
+    // count as indexes on a root array
+    fn _root_layer(&self, seg: Option<SmDtonPathSeg>, pointer: bool) -> Option<&SmDtonReader<'a>> {
+        if let Some(seg) = seg {
+            for q in self.layers.iter().rev() {
+                let array = q.node_type(1) == ST::SMDT_ARR;
+                let index = match seg {
+                    SmDtonPathSeg::Key(key) if pointer && array => sd_path::pointer_index(key),
+                    SmDtonPathSeg::Key(key) => {
+                        if q.get_field_voff(1, key) > 0 {
+                            return Some(q);
                         }
+                        continue;
                     }
-                    _ => {}
+                    SmDtonPathSeg::Index(index) if array => Some(index),
+                    SmDtonPathSeg::Index(_) => None,
+                };
+                if index.is_some_and(|index| index < q.node_sub_num(1)) {
+                    return Some(q);
                 }
             }
-            _ => {}
         }
+        return self.layers.first();
+    }
 
-        if ret.is_some() {
-            return Some(ret.unwrap().pretty(4));
-        }
-        return None;
+    // which layer supplied a value, 0 is the base. a joined map or array
+    // reports the top layer holding it
+
+    #[allow(dead_code)]
+    pub fn layer_of(&self, path: &str) -> Option<usize> {
+        return self.try_layer_of(path).ok()?;
     }
 
-    pub fn combine<'b>(&self, ndt: &SmDton<'a>) -> Option<JsonValue> {
-        let ops1 = self.stringify();
-        let ops2 = ndt.stringify();
+    #[allow(dead_code)]
+    pub fn try_layer_of(&self, path: &str) -> Result<Option<usize>, SmDtonError> {
+        let path = SmDtonPath::parse(path)?;
+        let found = self._try_path_voff(&path.segs(), false)?;
+        return Ok(found.and_then(|(q, _)| self._layer_index(q)));
+    }
+
+    #[allow(dead_code)]
+    pub fn layer_of_pointer(&self, pointer: &str) -> Option<usize> {
+        return self.try_layer_of_pointer(pointer).ok()?;
+    }
 
-        let mut s1 = "".to_string();
-        if ops1.is_some() {
-            s1 = ops1.unwrap();
+    #[allow(dead_code)]
+    pub fn try_layer_of_pointer(&self, pointer: &str) -> Result<Option<usize>, SmDtonError> {
+        let found = self._try_pointer_voff(pointer)?;
+        return Ok(found.and_then(|(q, _)| self._layer_index(q)));
+    }
+
+    fn _layer_index(&self, q: &SmDtonReader<'a>) -> Option<usize> {
+        return self.layers.iter().position(|r| std::ptr::eq(r, q));
+    }
+
+    fn _try_pointer_voff(
+        &self,
+        pointer: &str,
+    ) -> Result<Option<(&SmDtonReader<'a>, usize)>, SmDtonError> {
+        let tokens = sd_path::pointer_tokens(pointer)?;
+        if self.merge == SmDtonMergeOptions::default() {
+            let first = tokens.first().map(|t| SmDtonPathSeg::Key(t));
+            let q = match self._root_layer(first, true) {
+                Some(q) => q,
+                None => return Ok(None),
+            };
+            return Ok(_live(
+                q.try_get_pointer_voff(1, pointer)?.map(|off| (q, off)),
+            ));
         }
-        let mut s2 = "".to_string();
-        if ops2.is_some() {
-            s2 = ops2.unwrap();
+        let segs: Vec<SmDtonPathSeg> = tokens.iter().map(|t| SmDtonPathSeg::Key(t)).collect();
+        return self._try_path_voff(&segs, true);
+    }
+
+    // layer and value offset at the end of a path, pointer keys become indexes on arrays.
+    // a tombstone on the way or at the end leaves the path absent
+    fn _try_path_voff(
+        &self,
+        segs: &[SmDtonPathSeg],
+        pointer: bool,
+    ) -> Result<Option<(&SmDtonReader<'a>, usize)>, SmDtonError> {
+        if self.merge == SmDtonMergeOptions::default() {
+            let q = match self._root_layer(segs.first().copied(), false) {
+                Some(q) => q,
+                None => return Ok(None),
+            };
+            return Ok(_live(q.try_get_path_voff(1, segs)?.map(|off| (q, off))));
         }
 
-        let r1 = json::parse(&s1);
-        let r2 = json::parse(&s2);
-        match r1 {
-            Ok(mut jsn1) => {
-                match r2 {
-                    Ok(jsn2) => {
-                        for x in jsn2.entries() {
-                            let k = x.0.to_string();
-                            let v = x.1;
+        // nodes standing for the current value, top layer first
+        let layers = self._layers();
+        let mut nodes: Vec<(&SmDtonReader<'a>, usize)> = layers
+            .iter()
+            .filter(|q| q.node_type(1) == ST::SMDT_MAP)
+            .map(|q| (*q, 1))
+            .collect();
+        if nodes.is_empty() {
+            match layers.first() {
+                Some(q) => nodes.push((*q, 1)),
+                None => return Ok(None),
+            }
+        }
+
+        let mut found: Vec<(&SmDtonReader<'a>, usize)> = Vec::new();
+        for (i, seg) in segs.iter().enumerate() {
+            if i > 0 {
+                nodes = self._merged_nodes(&found)?;
+                if nodes.is_empty() {
+                    return Ok(None);
+                }
+            }
+            let (top, top_oid) = nodes[0];
+            let array = top.node_type(top_oid) == ST::SMDT_ARR;
+            let seg = match seg {
+                SmDtonPathSeg::Key(key) if pointer && array => match sd_path::pointer_index(key) {
+                    Some(index) => SmDtonPathSeg::Index(index),
+                    None => return Ok(None),
+                },
+                _ => *seg,
+            };
 
-                            jsn1[k] = v.clone();
+            found.clear();
+            match seg {
+                SmDtonPathSeg::Key(key) => {
+                    for (q, oid) in nodes.iter() {
+                        if q.node_type(*oid) == ST::SMDT_ARR {
+                            continue;
+                        }
+                        if let Some(off) = q.try_get_field_voff(*oid, key)? {
+                            found.push((*q, off));
                         }
                     }
-                    _ => {}
                 }
-                return Some(jsn1);
-            }
-            _ => match r2 {
-                Ok(jsn2) => {
-                    return Some(jsn2);
+                SmDtonPathSeg::Index(index) if array => {
+                    // joined arrays count from the lowest layer up
+                    let mut index = index;
+                    for (q, oid) in nodes.iter().rev() {
+                        let sub_num = q.node_sub_num(*oid);
+                        if index < sub_num {
+                            if let Some(off) = q.try_get_sub_voff(*oid, index)? {
+                                found.push((*q, off));
+                            }
+                            break;
+                        }
+                        index -= sub_num;
+                    }
                 }
-                _ => {
-                    return None;
+                SmDtonPathSeg::Index(index) => {
+                    if let Some(off) = top.try_get_sub_voff(top_oid, index)? {
+                        found.push((top, off));
+                    }
                 }
-            },
+            }
+            if found.is_empty() {
+                return Ok(None);
+            }
         }
+        return Ok(_live(found.first().copied()));
     }
 
-    pub fn clone(&self) -> Self {
-        match &self.oread {
-            Some(q) => match &self.nread {
-                Some(r) => SmDton {
-                    oread: Some(q.clone()),
-                    nread: Some(r.clone()),
-                },
-                _ => SmDton {
-                    oread: Some(q.clone()),
-                    nread: None,
-                },
-            },
-            _ => SmDton {
-                oread: None,
-                nread: None,
-            },
+    // map or array nodes the values found under one key read as, top first
+    fn _merged_nodes<'q>(
+        &self,
+        found: &[(&'q SmDtonReader<'a>, usize)],
+    ) -> Result<Vec<(&'q SmDtonReader<'a>, usize)>, SmDtonError> {
+        let mut chain = Vec::new();
+        for (q, off) in found.iter() {
+            match q.try_get_value_by_voff(*off) {
+                Ok(value) => chain.push((*q, value)),
+                Err(SmDtonError::InvalidValueType { .. }) => break,
+                Err(e) => return Err(e),
+            }
         }
+        chain.reverse();
+        let nodes = sd_layer::joined(&chain, self.merge)
+            .iter()
+            .rev()
+            .filter_map(|(q, value)| value.node_id().map(|oid| (*q, oid)))
+            .collect();
+        return Ok(nodes);
+    }
+
+    // readers from the top layer down
+    pub(crate) fn _layers(&self) -> Vec<&SmDtonReader<'a>> {
+        return self.layers.iter().rev().collect();
+    }
+
+    def_get_path_func!(get_null_path, get_null_by_voff, ());
+    def_get_path_func!(get_bool_path, get_bool_by_voff, bool);
+    def_get_path_func!(get_u8_path, get_u8_by_voff, u8);
+
+    def_get_path_func!(get_i16_path, get_i16_by_voff, i16);
+    def_get_path_func!(get_u16_path, get_u16_by_voff, u16);
+
+    def_get_path_func!(get_i32_path, get_i32_by_voff, i32);
+    def_get_path_func!(get_u32_path, get_u32_by_voff, u32);
+    def_get_path_func!(get_f32_path, get_f32_by_voff, f32);
+
+    def_get_path_func!(get_i64_path, get_i64_by_voff, i64);
+    def_get_path_func!(get_u64_path, get_u64_by_voff, u64);
+    def_get_path_func!(get_f64_path, get_f64_by_voff, f64);
+
+    def_get_path_func!(get_string_path, get_string_by_voff, &'a str);
+    def_get_path_func!(get_bin_path, get_bin_by_voff, &'a [u8]);
+    def_get_path_func!(get_value_path, get_value_by_voff, SmDtonValue<'a>);
+
+    def_try_get_path_func!(try_get_null_path, try_get_null_by_voff, ());
+    def_try_get_path_func!(try_get_bool_path, try_get_bool_by_voff, bool);
+    def_try_get_path_func!(try_get_u8_path, try_get_u8_by_voff, u8);
+
+    def_try_get_path_func!(try_get_i16_path, try_get_i16_by_voff, i16);
+    def_try_get_path_func!(try_get_u16_path, try_get_u16_by_voff, u16);
+
+    def_try_get_path_func!(try_get_i32_path, try_get_i32_by_voff, i32);
+    def_try_get_path_func!(try_get_u32_path, try_get_u32_by_voff, u32);
+    def_try_get_path_func!(try_get_f32_path, try_get_f32_by_voff, f32);
+
+    def_try_get_path_func!(try_get_i64_path, try_get_i64_by_voff, i64);
+    def_try_get_path_func!(try_get_u64_path, try_get_u64_by_voff, u64);
+    def_try_get_path_func!(try_get_f64_path, try_get_f64_by_voff, f64);
+
+    def_try_get_path_func!(try_get_string_path, try_get_string_by_voff, &'a str);
+    def_try_get_path_func!(try_get_bin_path, try_get_bin_by_voff, &'a [u8]);
+    def_try_get_path_func!(try_get_value_path, try_get_value_by_voff, SmDtonValue<'a>);
+
+    // get value from json pointer, layered like paths
+
+    def_get_pointer_func!(get_pointer, get_value_by_voff, SmDtonValue<'a>);
+    def_try_get_pointer_func!(try_get_pointer, try_get_value_by_voff, SmDtonValue<'a>);
+
+    def_get_pointer_func!(get_null_pointer, get_null_by_voff, ());
+    def_get_pointer_func!(get_bool_pointer, get_bool_by_voff, bool);
+    def_get_pointer_func!(get_u8_pointer, get_u8_by_voff, u8);
+
+    def_get_pointer_func!(get_i16_pointer, get_i16_by_voff, i16);
+    def_get_pointer_func!(get_u16_pointer, get_u16_by_voff, u16);
+
+    def_get_pointer_func!(get_i32_pointer, get_i32_by_voff, i32);
+    def_get_pointer_func!(get_u32_pointer, get_u32_by_voff, u32);
+    def_get_pointer_func!(get_f32_pointer, get_f32_by_voff, f32);
+
+    def_get_pointer_func!(get_i64_pointer, get_i64_by_voff, i64);
+    def_get_pointer_func!(get_u64_pointer, get_u64_by_voff, u64);
+    def_get_pointer_func!(get_f64_pointer, get_f64_by_voff, f64);
+
+    def_get_pointer_func!(get_string_pointer, get_string_by_voff, &'a str);
+    def_get_pointer_func!(get_bin_pointer, get_bin_by_voff, &'a [u8]);
+
+    def_try_get_pointer_func!(try_get_null_pointer, try_get_null_by_voff, ());
+    def_try_get_pointer_func!(try_get_bool_pointer, try_get_bool_by_voff, bool);
+    def_try_get_pointer_func!(try_get_u8_pointer, try_get_u8_by_voff, u8);
+
+    def_try_get_pointer_func!(try_get_i16_pointer, try_get_i16_by_voff, i16);
+    def_try_get_pointer_func!(try_get_u16_pointer, try_get_u16_by_voff, u16);
+
+    def_try_get_pointer_func!(try_get_i32_pointer, try_get_i32_by_voff, i32);
+    def_try_get_pointer_func!(try_get_u32_pointer, try_get_u32_by_voff, u32);
+    def_try_get_pointer_func!(try_get_f32_pointer, try_get_f32_by_voff, f32);
+
+    def_try_get_pointer_func!(try_get_i64_pointer, try_get_i64_by_voff, i64);
+    def_try_get_pointer_func!(try_get_u64_pointer, try_get_u64_by_voff, u64);
+    def_try_get_pointer_func!(try_get_f64_pointer, try_get_f64_by_voff, f64);
+
+    def_try_get_pointer_func!(try_get_string_pointer, try_get_string_by_voff, &'a str);
+    def_try_get_pointer_func!(try_get_bin_pointer, try_get_bin_by_voff, &'a [u8]);
+
+    // json through write_json, a single layer prints pretty, layered output is compact
+    #[cfg(feature = "json")]
+    pub fn try_stringify(&self) -> Result<Option<String>, SmDtonError> {
+        if self.layers.is_empty() {
+            return Ok(None);
+        }
+        let format = crate::SmDtonJsonFormat {
+            pretty: self.layers.len() == 1,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        self.write_json(&mut out, format)?;
+        let text = String::from_utf8(out).map_err(|e| SmDtonError::InvalidUtf8 {
+            offset: e.utf8_error().valid_up_to(),
+        })?;
+        return Ok(Some(text));
+    }
+
+    #[cfg(feature = "json")]
+    pub fn stringify(&self) -> Option<String> {
+        return self.try_stringify().ok()?;
+    }
+
+    // layers of ndt go on top of these ones, joined by the merge options of self
+    #[cfg(feature = "json")]
+    pub fn combine(&self, ndt: &SmDton<'a>) -> Option<JsonValue> {
+        let mut stack = self.clone();
+        for q in ndt.layers.iter() {
+            stack.layers.push(q.clone());
+        }
+        let smb = stack.try_flatten().ok()?;
+        return SmDtonReader::try_new(smb.get_buffer()).ok()?.to_json(1);
+    }
+}
+
+impl<'a> Clone for SmDton<'a> {
+    fn clone(&self) -> Self {
+        SmDton {
+            layers: self.layers.clone(),
+            merge: self.merge,
+        }
+    }
+}
+
+// a tombstone in a layer hides the key of the layers below
+fn _deleted(q: &SmDtonReader, key: &str) -> bool {
+    let value_off = q.get_field_voff(1, key);
+    return value_off > 0 && q.get_type_by_voff(value_off) == Some(ST::SMDT_DEL);
+}
+
+// a tombstone found at the end of a lookup means the value is absent
+fn _live<'q, 'a>(
+    found: Option<(&'q SmDtonReader<'a>, usize)>,
+) -> Option<(&'q SmDtonReader<'a>, usize)> {
+    match found {
+        Some((q, off)) if q.get_type_by_voff(off) == Some(ST::SMDT_DEL) => return None,
+        _ => return found,
     }
 }
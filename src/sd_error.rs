@@ -0,0 +1,127 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SmDtonError {
+    InvalidHeader(u8),
+    InvalidOffsetSize(u8),
+    Truncated {
+        offset: usize,
+    },
+    InvalidSentinel {
+        offset: usize,
+    },
+    InvalidOffset {
+        offset: usize,
+    },
+    InvalidNodeId(usize),
+    InvalidNodeType {
+        oid: usize,
+        found: u8,
+    },
+    InvalidValueType {
+        offset: usize,
+        found: u8,
+    },
+    InvalidUtf8 {
+        offset: usize,
+    },
+    TrailingBytes {
+        offset: usize,
+    },
+    TypeMismatch {
+        expected: u8,
+        found: u8,
+    },
+    NotANode {
+        found: u8,
+    },
+    InvalidBase64,
+    RepeatedNode(usize),
+    TooDeep {
+        depth: usize,
+    },
+    InexactNumber,
+    OutOfRange {
+        found: u8,
+    },
+    InvalidPath {
+        offset: usize,
+    },
+    UnsortedKeys(usize),
+    Field {
+        path: String,
+        error: Box<SmDtonError>,
+    },
+    InvalidJson {
+        offset: usize,
+    },
+    Io(std::io::ErrorKind),
+    InvalidText {
+        offset: usize,
+    },
+    InvalidPatch {
+        index: usize,
+    },
+    PatchFailed {
+        index: usize,
+        path: String,
+    },
+    Custom(String),
+}
+
+impl fmt::Display for SmDtonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SmDtonError::InvalidHeader(b) => write!(f, "invalid format type 0x{:02x}", b),
+            SmDtonError::InvalidOffsetSize(oz) => write!(f, "invalid offset size {}", oz),
+            SmDtonError::Truncated { offset } => write!(f, "buffer truncated at offset {}", offset),
+            SmDtonError::InvalidSentinel { offset } => {
+                write!(f, "missing segment sentinel at offset {}", offset)
+            }
+            SmDtonError::InvalidOffset { offset } => write!(f, "invalid offset {}", offset),
+            SmDtonError::InvalidNodeId(oid) => write!(f, "invalid node id {}", oid),
+            SmDtonError::InvalidNodeType { oid, found } => {
+                write!(f, "node {} has invalid type 0x{:02x}", oid, found)
+            }
+            SmDtonError::InvalidValueType { offset, found } => {
+                write!(
+                    f,
+                    "value at offset {} has invalid type 0x{:02x}",
+                    offset, found
+                )
+            }
+            SmDtonError::InvalidUtf8 { offset } => write!(f, "invalid utf-8 at offset {}", offset),
+            SmDtonError::TrailingBytes { offset } => {
+                write!(f, "unexpected trailing bytes at offset {}", offset)
+            }
+            SmDtonError::TypeMismatch { expected, found } => write!(
+                f,
+                "type mismatch: expected 0x{:02x}, found 0x{:02x}",
+                expected, found
+            ),
+            SmDtonError::NotANode { found } => {
+                write!(f, "expected a map or array, found 0x{:02x}", found)
+            }
+            SmDtonError::InvalidBase64 => write!(f, "invalid base64 data"),
+            SmDtonError::RepeatedNode(oid) => write!(f, "node {} is reached more than once", oid),
+            SmDtonError::TooDeep { depth } => write!(f, "nesting deeper than {}", depth),
+            SmDtonError::InexactNumber => write!(f, "number has no exact representation"),
+            SmDtonError::OutOfRange { found } => {
+                write!(f, "value of type 0x{:02x} is out of range", found)
+            }
+            SmDtonError::InvalidPath { offset } => write!(f, "invalid path at offset {}", offset),
+            SmDtonError::UnsortedKeys(oid) => write!(f, "keys of node {} are not sorted", oid),
+            SmDtonError::Field { path, error } => write!(f, "{}: {}", path, error),
+            SmDtonError::InvalidJson { offset } => write!(f, "invalid json at offset {}", offset),
+            SmDtonError::Io(kind) => write!(f, "i/o failed: {}", kind),
+            SmDtonError::InvalidText { offset } => write!(f, "invalid text at offset {}", offset),
+            SmDtonError::InvalidPatch { index } => write!(f, "invalid patch operation {}", index),
+            SmDtonError::PatchFailed { index, path } => {
+                write!(f, "patch operation {} failed at \"{}\"", index, path)
+            }
+            SmDtonError::Custom(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SmDtonError {}
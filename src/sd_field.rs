@@ -0,0 +1,236 @@
+use super::sd_buffer::SmDtonBuffer;
+use super::sd_builder::SmDtonBuilder;
+use super::sd_data::ST;
+use super::sd_reader::SmDtonReader;
+use super::sd_value::SmDtonNodeRef;
+
+// field types readable by #[derive(DtonView)] accessors
+pub trait SmDtonViewField<'r, 'a: 'r> {
+    type View;
+
+    fn view_by_voff(reader: &'r SmDtonReader<'a>, value_off: usize) -> Option<Self::View>;
+}
+
+// field types writable by #[derive(DtonBuild)]
+pub trait SmDtonBuildField {
+    fn add_field<'a>(&'a self, builder: &mut SmDtonBuilder<'a>, oid: usize, key: &'a str);
+
+    fn push_item<'a>(&'a self, builder: &mut SmDtonBuilder<'a>, oid: usize);
+}
+
+// structs writing their fields into a map node
+pub trait SmDtonBuild {
+    fn build_into<'a>(&'a self, builder: &mut SmDtonBuilder<'a>, oid: usize);
+
+    fn to_buffer(&self) -> SmDtonBuffer {
+        let mut builder = SmDtonBuilder::new();
+        let oid = builder.create_node(ST::SMDT_MAP);
+        self.build_into(&mut builder, oid);
+        return builder.build();
+    }
+}
+
+macro_rules! def_field_build {
+    ($dty: ty, $add_name:ident, $push_name:ident) => {
+        impl SmDtonBuildField for $dty {
+            #[inline]
+            fn add_field<'a>(&'a self, builder: &mut SmDtonBuilder<'a>, oid: usize, key: &'a str) {
+                builder.$add_name(oid, key, *self);
+            }
+
+            #[inline]
+            fn push_item<'a>(&'a self, builder: &mut SmDtonBuilder<'a>, oid: usize) {
+                builder.$push_name(oid, *self);
+            }
+        }
+    };
+}
+
+// integers read any number holding an exact value in range, as json ints are i64
+macro_rules! def_field_int {
+    ($dty: ty, $add_name:ident, $push_name:ident) => {
+        impl<'r, 'a: 'r> SmDtonViewField<'r, 'a> for $dty {
+            type View = $dty;
+
+            #[inline]
+            fn view_by_voff(reader: &'r SmDtonReader<'a>, value_off: usize) -> Option<$dty> {
+                return <$dty>::try_from(reader.get_int_lossless_by_voff(value_off)?).ok();
+            }
+        }
+
+        def_field_build!($dty, $add_name, $push_name);
+    };
+}
+
+impl<'r, 'a: 'r> SmDtonViewField<'r, 'a> for bool {
+    type View = bool;
+
+    #[inline]
+    fn view_by_voff(reader: &'r SmDtonReader<'a>, value_off: usize) -> Option<bool> {
+        return reader.get_bool_by_voff(value_off);
+    }
+}
+
+def_field_build!(bool, add_bool, push_bool);
+
+def_field_int!(u8, add_u8, push_u8);
+
+def_field_int!(i16, add_i16, push_i16);
+def_field_int!(u16, add_u16, push_u16);
+
+def_field_int!(i32, add_i32, push_i32);
+def_field_int!(u32, add_u32, push_u32);
+
+def_field_int!(i64, add_i64, push_i64);
+def_field_int!(u64, add_u64, push_u64);
+
+impl<'r, 'a: 'r> SmDtonViewField<'r, 'a> for f32 {
+    type View = f32;
+
+    #[inline]
+    fn view_by_voff(reader: &'r SmDtonReader<'a>, value_off: usize) -> Option<f32> {
+        let d = reader.get_float_by_voff(value_off)?;
+        if d as f32 as f64 != d && !d.is_nan() {
+            return None;
+        }
+        return Some(d as f32);
+    }
+}
+
+def_field_build!(f32, add_f32, push_f32);
+
+impl<'r, 'a: 'r> SmDtonViewField<'r, 'a> for f64 {
+    type View = f64;
+
+    #[inline]
+    fn view_by_voff(reader: &'r SmDtonReader<'a>, value_off: usize) -> Option<f64> {
+        return reader.get_float_by_voff(value_off);
+    }
+}
+
+def_field_build!(f64, add_f64, push_f64);
+
+// strings and bytes are viewed in place
+impl<'r, 'a: 'r> SmDtonViewField<'r, 'a> for String {
+    type View = &'a str;
+
+    #[inline]
+    fn view_by_voff(reader: &'r SmDtonReader<'a>, value_off: usize) -> Option<&'a str> {
+        return reader.get_string_by_voff(value_off);
+    }
+}
+
+impl SmDtonBuildField for String {
+    #[inline]
+    fn add_field<'a>(&'a self, builder: &mut SmDtonBuilder<'a>, oid: usize, key: &'a str) {
+        builder.add_string(oid, key, self);
+    }
+
+    #[inline]
+    fn push_item<'a>(&'a self, builder: &mut SmDtonBuilder<'a>, oid: usize) {
+        builder.push_string(oid, self);
+    }
+}
+
+impl<'r, 'a: 'r> SmDtonViewField<'r, 'a> for &str {
+    type View = &'a str;
+
+    #[inline]
+    fn view_by_voff(reader: &'r SmDtonReader<'a>, value_off: usize) -> Option<&'a str> {
+        return reader.get_string_by_voff(value_off);
+    }
+}
+
+impl SmDtonBuildField for &str {
+    #[inline]
+    fn add_field<'a>(&'a self, builder: &mut SmDtonBuilder<'a>, oid: usize, key: &'a str) {
+        builder.add_string(oid, key, self);
+    }
+
+    #[inline]
+    fn push_item<'a>(&'a self, builder: &mut SmDtonBuilder<'a>, oid: usize) {
+        builder.push_string(oid, self);
+    }
+}
+
+impl<'r, 'a: 'r> SmDtonViewField<'r, 'a> for &[u8] {
+    type View = &'a [u8];
+
+    #[inline]
+    fn view_by_voff(reader: &'r SmDtonReader<'a>, value_off: usize) -> Option<&'a [u8]> {
+        return reader.get_bin_by_voff(value_off);
+    }
+}
+
+impl SmDtonBuildField for &[u8] {
+    #[inline]
+    fn add_field<'a>(&'a self, builder: &mut SmDtonBuilder<'a>, oid: usize, key: &'a str) {
+        builder.add_bin(oid, key, self);
+    }
+
+    #[inline]
+    fn push_item<'a>(&'a self, builder: &mut SmDtonBuilder<'a>, oid: usize) {
+        builder.push_bin(oid, self);
+    }
+}
+
+// null reads as Some(None), so the accessor tells it from a missing field
+impl<'r, 'a: 'r, T: SmDtonViewField<'r, 'a>> SmDtonViewField<'r, 'a> for Option<T> {
+    type View = Option<T::View>;
+
+    #[inline]
+    fn view_by_voff(reader: &'r SmDtonReader<'a>, value_off: usize) -> Option<Option<T::View>> {
+        if reader.get_type_by_voff(value_off) == Some(ST::SMDT_NUL) {
+            return Some(None);
+        }
+        return T::view_by_voff(reader, value_off).map(Some);
+    }
+}
+
+impl<T: SmDtonBuildField> SmDtonBuildField for Option<T> {
+    fn add_field<'a>(&'a self, builder: &mut SmDtonBuilder<'a>, oid: usize, key: &'a str) {
+        match self {
+            Some(v) => v.add_field(builder, oid, key),
+            None => builder.add_null(oid, key),
+        }
+    }
+
+    fn push_item<'a>(&'a self, builder: &mut SmDtonBuilder<'a>, oid: usize) {
+        match self {
+            Some(v) => v.push_item(builder, oid),
+            None => builder.push_null(oid),
+        }
+    }
+}
+
+// a vec is an array node, its items are left to iter_items
+impl<'r, 'a: 'r, T> SmDtonViewField<'r, 'a> for Vec<T> {
+    type View = SmDtonNodeRef;
+
+    #[inline]
+    fn view_by_voff(reader: &'r SmDtonReader<'a>, value_off: usize) -> Option<SmDtonNodeRef> {
+        let oid = reader.get_node_id_by_voff(value_off)?;
+        if reader.node_type(oid) != ST::SMDT_ARR {
+            return None;
+        }
+        return Some(SmDtonNodeRef { oid: oid });
+    }
+}
+
+impl<T: SmDtonBuildField> SmDtonBuildField for Vec<T> {
+    fn add_field<'a>(&'a self, builder: &mut SmDtonBuilder<'a>, oid: usize, key: &'a str) {
+        let sub = builder.create_node(ST::SMDT_ARR);
+        for item in self {
+            item.push_item(builder, sub);
+        }
+        builder.add_node(oid, key, sub);
+    }
+
+    fn push_item<'a>(&'a self, builder: &mut SmDtonBuilder<'a>, oid: usize) {
+        let sub = builder.create_node(ST::SMDT_ARR);
+        for item in self {
+            item.push_item(builder, sub);
+        }
+        builder.push_node(oid, sub);
+    }
+}
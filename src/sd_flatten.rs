@@ -0,0 +1,176 @@
+// layers merged into one fresh buffer, no json tree is built
+//
+// the merge follows write_json: root maps of all layers join when every root
+// is a map, otherwise the top layer stands alone. scalars are copied from
+// the layer that wins with their own type. repeated keys keep the value of
+// the last entry and tombstones are applied, so the result holds neither.
+
+use std::collections::HashSet;
+
+use crate::sd_data::SmDtonData;
+use crate::sd_layer::{self, SmDtonChain, SmDtonChains};
+use crate::sd_reader::MAX_DEPTH;
+use crate::{
+    SmDton, SmDtonBuffer, SmDtonBuilder, SmDtonError, SmDtonMergeOptions, SmDtonPair, SmDtonReader,
+    SmDtonValue, ST,
+};
+
+impl<'a> SmDton<'a> {
+    // an empty buffer when a layer does not read
+    #[allow(dead_code)]
+    pub fn flatten(&self) -> SmDtonBuffer {
+        return self.try_flatten().unwrap_or_else(|_| SmDtonBuffer::new());
+    }
+
+    #[allow(dead_code)]
+    pub fn try_flatten(&self) -> Result<SmDtonBuffer, SmDtonError> {
+        let mut layers = self._layers();
+        layers.reverse();
+        if layers.is_empty() {
+            return Err(SmDtonError::InvalidNodeId(1));
+        }
+
+        let merge = layers.len() > 1 && layers.iter().all(|q| q.node_type(1) == ST::SMDT_MAP);
+        let roots = match merge {
+            true => (0..layers.len()).map(|layer| (layer, 1)).collect(),
+            false => {
+                let top = layers.len() - 1;
+                layers = vec![layers[top]];
+                vec![(0, 1)]
+            }
+        };
+        match layers[0].node_type(1) {
+            ST::SMDT_MAP | ST::SMDT_ARR => {}
+            0 => return Err(SmDtonError::InvalidNodeId(1)),
+            found => {
+                return Err(SmDtonError::InvalidNodeType {
+                    oid: 1,
+                    found: found,
+                })
+            }
+        }
+
+        let mut flat = SmDtonFlatten {
+            bd: SmDtonBuilder::new(),
+            merge: self.merge_options(),
+            seen: vec![HashSet::new(); layers.len()],
+            layers: layers,
+        };
+        flat.flatten_nodes(roots, 0)?;
+        return Ok(flat.bd.build());
+    }
+}
+
+impl SmDtonPair {
+    #[allow(dead_code)]
+    pub fn compact(&self) -> SmDtonBuffer {
+        return self.try_compact().unwrap_or_else(|_| SmDtonBuffer::new());
+    }
+
+    #[allow(dead_code)]
+    pub fn try_compact(&self) -> Result<SmDtonBuffer, SmDtonError> {
+        return self.try_compact_with(SmDtonMergeOptions::default());
+    }
+
+    #[allow(dead_code)]
+    pub fn compact_with(&self, merge: SmDtonMergeOptions) -> SmDtonBuffer {
+        return self
+            .try_compact_with(merge)
+            .unwrap_or_else(|_| SmDtonBuffer::new());
+    }
+
+    #[allow(dead_code)]
+    pub fn try_compact_with(&self, merge: SmDtonMergeOptions) -> Result<SmDtonBuffer, SmDtonError> {
+        let mut dton = SmDton::try_new_from_pair(self)?;
+        dton.set_merge(merge);
+        return dton.try_flatten();
+    }
+}
+
+struct SmDtonFlatten<'r, 'a> {
+    bd: SmDtonBuilder<'a>,
+    merge: SmDtonMergeOptions,
+    // lowest layer first
+    layers: Vec<&'r SmDtonReader<'a>>,
+    // nodes copied so far, per layer
+    seen: Vec<HashSet<usize>>,
+}
+
+impl<'r, 'a> SmDtonFlatten<'r, 'a> {
+    // nodes of one type written as one node, lowest layer first
+    fn flatten_nodes(
+        &mut self,
+        nodes: Vec<(usize, usize)>,
+        depth: usize,
+    ) -> Result<usize, SmDtonError> {
+        if depth > MAX_DEPTH {
+            return Err(SmDtonError::TooDeep { depth: depth });
+        }
+        let (layer, oid) = nodes[0];
+        let smdt = self.layers[layer].node_type(oid);
+        let out = self.bd.create_node(smdt);
+        if smdt == ST::SMDT_MAP {
+            let mut entries = SmDtonChains::new(true);
+            for (layer, oid) in nodes {
+                self.mark(layer, oid)?;
+                entries.add(layer, self.layers[layer], oid)?;
+            }
+            for (key, chain) in entries.live() {
+                let da = self.resolve(&chain, depth)?;
+                self.bd._do_add(out, key, da);
+            }
+            return Ok(out);
+        }
+
+        for (layer, oid) in nodes {
+            self.mark(layer, oid)?;
+            for value in sd_layer::items(self.layers[layer], oid)? {
+                let da = self.copy_value(layer, value, depth)?;
+                self.bd._do_push(out, da);
+            }
+        }
+        return Ok(out);
+    }
+
+    fn mark(&mut self, layer: usize, oid: usize) -> Result<(), SmDtonError> {
+        if !self.seen[layer].insert(oid) {
+            return Err(SmDtonError::RepeatedNode(oid));
+        }
+        return Ok(());
+    }
+
+    fn resolve(
+        &mut self,
+        chain: &SmDtonChain<'a, usize>,
+        depth: usize,
+    ) -> Result<SmDtonData<'a>, SmDtonError> {
+        let joined = sd_layer::joined(chain, self.merge);
+        match joined {
+            [] => return Err(SmDtonError::InvalidNodeId(0)),
+            [(layer, top)] => return self.copy_value(*layer, *top, depth),
+            _ => {
+                let nodes = joined
+                    .iter()
+                    .map(|(layer, value)| (*layer, value.node_id().unwrap_or(0)))
+                    .collect();
+                let oid = self.flatten_nodes(nodes, depth + 1)?;
+                return Ok(self.bd._node_data(oid));
+            }
+        }
+    }
+
+    fn copy_value(
+        &mut self,
+        layer: usize,
+        value: SmDtonValue<'a>,
+        depth: usize,
+    ) -> Result<SmDtonData<'a>, SmDtonError> {
+        match value {
+            SmDtonValue::Map(node) | SmDtonValue::Arr(node) => {
+                let oid = self.flatten_nodes(vec![(layer, node.oid)], depth + 1)?;
+                return Ok(self.bd._node_data(oid));
+            }
+            _ => return SmDtonData::from_scalar(value).ok_or(SmDtonError::InvalidNodeId(0)),
+        }
+    }
+}
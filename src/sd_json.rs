@@ -0,0 +1,218 @@
+use super::sd_data::ST;
+
+// a json value as seen by the builders
+pub enum SmDtonJsonKind<'j, J> {
+    Null,
+    Bool(bool),
+    // decimal parts: positive, mantissa, exponent, and whether the parser
+    // dropped digits the parts cannot hold
+    Number(bool, u64, i16, bool),
+    Float(f64),
+    Str(&'j str),
+    Object(Vec<(&'j str, &'j J)>),
+    Array(&'j [J]),
+}
+
+// json backends usable by the builders and by the reader conversions
+pub trait SmDtonJsonValue: Sized {
+    fn json_kind(&self) -> SmDtonJsonKind<'_, Self>;
+
+    fn json_null() -> Self;
+    fn json_bool(v: bool) -> Self;
+    fn json_i64(v: i64) -> Self;
+    fn json_u64(v: u64) -> Self;
+    fn json_f64(v: f64) -> Self;
+    fn json_str(v: &str) -> Self;
+    fn json_object() -> Self;
+    fn json_array() -> Self;
+
+    // last write of a key wins
+    fn json_insert(&mut self, key: &str, value: Self);
+    fn json_push(&mut self, value: Self);
+
+    // type tag reported when a map is expected
+    fn json_found(&self) -> u8 {
+        match self.json_kind() {
+            SmDtonJsonKind::Null => return ST::SMDT_NUL,
+            SmDtonJsonKind::Bool(_) => return ST::SMDT_BOO,
+            SmDtonJsonKind::Number(..) | SmDtonJsonKind::Float(_) => return ST::SMDT_F64,
+            SmDtonJsonKind::Str(_) => return ST::SMDT_STR,
+            SmDtonJsonKind::Object(_) => return ST::SMDT_MAP,
+            SmDtonJsonKind::Array(_) => return ST::SMDT_ARR,
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+mod json_backend {
+    use super::{SmDtonJsonKind, SmDtonJsonValue};
+    use json::{number::Number, JsonValue};
+
+    // json 0.12 keeps no record of dropped digits. from this mantissa on its
+    // parser trades integer digits for the exponent, so a positive exponent
+    // here may hide lost digits, written e notation included
+    const JSON_CHECKED_FROM: u64 = 576460752303423500;
+
+    impl SmDtonJsonValue for JsonValue {
+        fn json_kind(&self) -> SmDtonJsonKind<'_, Self> {
+            match self {
+                JsonValue::Null => return SmDtonJsonKind::Null,
+                JsonValue::Boolean(v) => return SmDtonJsonKind::Bool(*v),
+                JsonValue::Short(s) => return SmDtonJsonKind::Str(s.as_str()),
+                JsonValue::String(s) => return SmDtonJsonKind::Str(s),
+                JsonValue::Number(num) => {
+                    let (positive, mantissa, exponent) = num.as_parts();
+                    let truncated = exponent > 0 && mantissa >= JSON_CHECKED_FROM;
+                    return SmDtonJsonKind::Number(positive, mantissa, exponent, truncated);
+                }
+                JsonValue::Object(obj) => return SmDtonJsonKind::Object(obj.iter().collect()),
+                JsonValue::Array(arr) => return SmDtonJsonKind::Array(arr),
+            }
+        }
+
+        fn json_null() -> Self {
+            return JsonValue::Null;
+        }
+
+        fn json_bool(v: bool) -> Self {
+            return JsonValue::Boolean(v);
+        }
+
+        // json 0.12 negates signed integers in their own width, which overflows on MIN
+        fn json_i64(v: i64) -> Self {
+            return JsonValue::Number(Number::from_parts(v >= 0, v.unsigned_abs(), 0));
+        }
+
+        fn json_u64(v: u64) -> Self {
+            return JsonValue::from(v);
+        }
+
+        fn json_f64(v: f64) -> Self {
+            return JsonValue::from(v);
+        }
+
+        fn json_str(v: &str) -> Self {
+            return JsonValue::from(v);
+        }
+
+        fn json_object() -> Self {
+            return JsonValue::new_object();
+        }
+
+        fn json_array() -> Self {
+            return JsonValue::new_array();
+        }
+
+        fn json_insert(&mut self, key: &str, value: Self) {
+            self[key] = value;
+        }
+
+        fn json_push(&mut self, value: Self) {
+            let _ = self.push(value);
+        }
+    }
+}
+
+#[cfg(feature = "serde_json")]
+mod serde_json_backend {
+    use super::{SmDtonJsonKind, SmDtonJsonValue};
+    use crate::{SmDtonBuffer, SmDtonBuilder, SmDtonError, SmDtonReader};
+    use serde_json::{Map, Number, Value};
+
+    impl SmDtonJsonValue for Value {
+        fn json_kind(&self) -> SmDtonJsonKind<'_, Self> {
+            match self {
+                Value::Null => return SmDtonJsonKind::Null,
+                Value::Bool(v) => return SmDtonJsonKind::Bool(*v),
+                Value::String(s) => return SmDtonJsonKind::Str(s),
+                Value::Number(num) => {
+                    if let Some(v) = num.as_u64() {
+                        return SmDtonJsonKind::Number(true, v, 0, false);
+                    }
+                    if let Some(v) = num.as_i64() {
+                        return SmDtonJsonKind::Number(v >= 0, v.unsigned_abs(), 0, false);
+                    }
+                    return SmDtonJsonKind::Float(num.as_f64().unwrap_or(f64::NAN));
+                }
+                Value::Object(obj) => {
+                    let entries = obj.iter().map(|(k, v)| (k.as_str(), v)).collect();
+                    return SmDtonJsonKind::Object(entries);
+                }
+                Value::Array(arr) => return SmDtonJsonKind::Array(arr),
+            }
+        }
+
+        fn json_null() -> Self {
+            return Value::Null;
+        }
+
+        fn json_bool(v: bool) -> Self {
+            return Value::Bool(v);
+        }
+
+        fn json_i64(v: i64) -> Self {
+            return Value::Number(Number::from(v));
+        }
+
+        fn json_u64(v: u64) -> Self {
+            return Value::Number(Number::from(v));
+        }
+
+        // nan and infinity have no json number, as with the json backend they become null
+        fn json_f64(v: f64) -> Self {
+            match Number::from_f64(v) {
+                Some(num) => return Value::Number(num),
+                None => return Value::Null,
+            }
+        }
+
+        fn json_str(v: &str) -> Self {
+            return Value::String(v.to_string());
+        }
+
+        fn json_object() -> Self {
+            return Value::Object(Map::new());
+        }
+
+        fn json_array() -> Self {
+            return Value::Array(Vec::new());
+        }
+
+        fn json_insert(&mut self, key: &str, value: Self) {
+            if let Value::Object(obj) = self {
+                obj.insert(key.to_string(), value);
+            }
+        }
+
+        fn json_push(&mut self, value: Self) {
+            if let Value::Array(arr) = self {
+                arr.push(value);
+            }
+        }
+    }
+
+    impl TryFrom<&Value> for SmDtonBuffer {
+        type Error = SmDtonError;
+
+        fn try_from(jsn: &Value) -> Result<Self, SmDtonError> {
+            return Ok(SmDtonBuilder::try_new_from_json(jsn)?.build());
+        }
+    }
+
+    impl TryFrom<Value> for SmDtonBuffer {
+        type Error = SmDtonError;
+
+        fn try_from(jsn: Value) -> Result<Self, SmDtonError> {
+            return SmDtonBuffer::try_from(&jsn);
+        }
+    }
+
+    impl TryFrom<&SmDtonBuffer> for Value {
+        type Error = SmDtonError;
+
+        fn try_from(smb: &SmDtonBuffer) -> Result<Self, SmDtonError> {
+            let reader = SmDtonReader::try_new(smb.get_buffer())?;
+            return reader.try_to_json_value(1);
+        }
+    }
+}
@@ -0,0 +1,125 @@
+// how the values of one key in several layers come together
+//
+// the entries of each layer are added lowest layer first, so a key holds a
+// chain of values, each with the layer that gave it. a repeated key within
+// one layer replaces its value, as get_field_voff reads it, only layers
+// merge. the last value of a chain decides, and the merge options let maps
+// or arrays right below it join in. values of unknown type are skipped.
+
+use std::collections::HashMap;
+
+use crate::{SmDtonArrayMerge, SmDtonError, SmDtonMergeOptions, SmDtonReader, SmDtonValue};
+
+// values of one key with their layer, lowest layer first
+pub(crate) type SmDtonChain<'a, L> = Vec<(L, SmDtonValue<'a>)>;
+
+pub(crate) struct SmDtonChains<'a, L> {
+    chains: Vec<(&'a str, SmDtonChain<'a, L>)>,
+    // chain of each key and the last add that touched it
+    position: HashMap<&'a str, (usize, usize)>,
+    adds: usize,
+    // typed text keeps every entry as it stands
+    merge_keys: bool,
+}
+
+impl<'a, L: Copy> SmDtonChains<'a, L> {
+    pub(crate) fn new(merge_keys: bool) -> Self {
+        return SmDtonChains {
+            chains: Vec::new(),
+            position: HashMap::new(),
+            adds: 0,
+            merge_keys: merge_keys,
+        };
+    }
+
+    // entries of a map node, on top of the layers added before
+    pub(crate) fn add(
+        &mut self,
+        layer: L,
+        reader: &SmDtonReader<'a>,
+        oid: usize,
+    ) -> Result<(), SmDtonError> {
+        self.adds += 1;
+        for index in 0..reader.node_sub_num(oid) {
+            let (key, value_off) = match reader.try_get_sub_entry(oid, index)? {
+                Some(entry) => entry,
+                None => break,
+            };
+            let value = match reader.try_get_value_by_voff(value_off) {
+                Ok(value) => value,
+                Err(SmDtonError::InvalidValueType { .. }) => continue,
+                Err(e) => return Err(e),
+            };
+            match self.position.get_mut(key) {
+                Some((at, add)) if self.merge_keys => {
+                    let chain = &mut self.chains[*at].1;
+                    if *add == self.adds {
+                        chain.pop();
+                    }
+                    chain.push((layer, value));
+                    *add = self.adds;
+                }
+                _ => {
+                    self.position.insert(key, (self.chains.len(), self.adds));
+                    self.chains.push((key, vec![(layer, value)]));
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    // keys in the order they first came
+    pub(crate) fn all(self) -> Vec<(&'a str, SmDtonChain<'a, L>)> {
+        return self.chains;
+    }
+
+    // a key whose last value is a tombstone is left out
+    pub(crate) fn live(self) -> Vec<(&'a str, SmDtonChain<'a, L>)> {
+        let mut chains = self.chains;
+        chains.retain(|(_, chain)| !matches!(chain.last(), Some((_, SmDtonValue::Deleted))));
+        return chains;
+    }
+}
+
+// items of an array node, a tombstone only stands in a map
+pub(crate) fn items<'a>(
+    reader: &SmDtonReader<'a>,
+    oid: usize,
+) -> Result<Vec<SmDtonValue<'a>>, SmDtonError> {
+    let mut items = Vec::new();
+    for index in 0..reader.node_sub_num(oid) {
+        let value_off = reader.try_get_sub_voff(oid, index)?.unwrap_or(0);
+        match reader.try_get_value_by_voff(value_off) {
+            Ok(SmDtonValue::Deleted) => {}
+            Ok(value) => items.push(value),
+            Err(SmDtonError::InvalidValueType { .. }) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    return Ok(items);
+}
+
+// the end of a chain that reads as one value: its last value and the maps or
+// arrays right below it the merge options join in, lowest layer first
+pub(crate) fn joined<'c, 'a, L>(
+    chain: &'c [(L, SmDtonValue<'a>)],
+    merge: SmDtonMergeOptions,
+) -> &'c [(L, SmDtonValue<'a>)] {
+    let top = match chain.last() {
+        Some((_, top)) => top,
+        None => return chain,
+    };
+    let joins = match top {
+        SmDtonValue::Map(_) => merge.deep,
+        SmDtonValue::Arr(_) => merge.arrays == SmDtonArrayMerge::Concat,
+        _ => false,
+    };
+    let mut start = chain.len() - 1;
+    while joins
+        && start > 0
+        && std::mem::discriminant(&chain[start - 1].1) == std::mem::discriminant(top)
+    {
+        start -= 1;
+    }
+    return &chain[start..];
+}
@@ -1,8 +1,9 @@
-use json::JsonValue;
-
 use super::{
     sd_buffer::SmDtonBuffer,
     sd_data::{SmDtonData, ST},
+    sd_error::SmDtonError,
+    sd_json::{SmDtonJsonKind, SmDtonJsonValue},
+    sd_option::SmDtonOptions,
     su,
 };
 
@@ -24,6 +25,14 @@ pub struct SmDtonMap<'a> {
     values: Vec<SmDtonData<'a>>,
     vdata_size: usize,
     len_blk: usize,
+
+    options: SmDtonOptions,
+}
+
+impl<'a> Default for SmDtonMap<'a> {
+    fn default() -> Self {
+        return SmDtonMap::new();
+    }
 }
 
 impl<'a> SmDtonMap<'a> {
@@ -44,6 +53,9 @@ impl<'a> SmDtonMap<'a> {
 
         // build head
         smb.build_start(size, oz);
+        if self.options.sorted_keys {
+            smb.build_flag(ST::SMTY_FLAG_SORTED);
+        }
         smb.build_put_int(1);
         smb.build_put_int(knum);
         smb.build_put_int(vnum);
@@ -61,9 +73,13 @@ impl<'a> SmDtonMap<'a> {
         let kseg_offs = smb.calc_key_part(knum, kseg_off, &self.keys);
         let vseg_offs = smb.calc_value_part(vnum, vseg_off, &self.values);
 
+        let mut order: Vec<usize> = (0..vnum).collect();
+        if self.options.sorted_keys {
+            order.sort_by(|x, y| self.keys[*x].cmp(self.keys[*y]));
+        }
         smb.build_put_int(vnum);
-        for k in 0..vnum {
-            if self.keys.len() > 0 {
+        for k in order {
+            if !self.keys.is_empty() {
                 smb.build_put_int(kseg_offs[k]);
             }
             smb.build_put_int(vseg_offs[k]);
@@ -88,46 +104,59 @@ impl<'a> SmDtonMap<'a> {
         self.values.push(da);
     }
 
-    fn _explore_node(&mut self, key: &'a str, jsn: &'a JsonValue) {
-        match jsn {
-            JsonValue::Boolean(data) => {
-                self.add_bool(key, *data);
+    fn _explore_node<J: SmDtonJsonValue>(
+        &mut self,
+        key: &'a str,
+        jsn: &'a J,
+        strict: bool,
+    ) -> Result<(), SmDtonError> {
+        match jsn.json_kind() {
+            SmDtonJsonKind::Null => {
+                self.add_null(key);
             }
-            JsonValue::Short(s) => {
-                if s.starts_with("$B64$") {
-                    self.add_base64(key, s);
-                } else {
-                    self.add_string(key, s);
-                }
+            SmDtonJsonKind::Bool(data) => {
+                self.add_bool(key, data);
             }
-            JsonValue::String(s) => {
-                if s.starts_with("$B64$") {
-                    self.add_base64(key, s);
-                } else {
-                    self.add_string(key, s);
-                }
+            SmDtonJsonKind::Str(s) => {
+                self._explore_string(key, s, strict)?;
             }
-            JsonValue::Number(num) => {
-                let (positive, mantissa, exponent) = num.as_parts();
-                if exponent >= 0 {
-                    let pw = 10u64.pow(exponent as u32);
-                    let ab = (mantissa * pw) as i64;
-                    let mut v = ab;
-                    if !positive {
-                        v = -v;
-                    }
-                    self.add_i64(key, v);
-                } else {
-                    let pw = 10f64.powf(exponent as f64);
-                    let mut v = mantissa as f64 * pw;
-                    if !positive {
-                        v = -v;
+            SmDtonJsonKind::Number(positive, mantissa, exponent, truncated) => {
+                let exact = self.options.exact_numbers;
+                let narrow = self.options.narrow_ints;
+                let da = SmDtonData::try_new_number(
+                    positive, mantissa, exponent, truncated, exact, narrow,
+                )?;
+                self._do_add(key, da);
+            }
+            SmDtonJsonKind::Float(data) => {
+                self.add_f64(key, data);
+            }
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    fn _explore_string(
+        &mut self,
+        key: &'a str,
+        s: &'a str,
+        strict: bool,
+    ) -> Result<(), SmDtonError> {
+        if s.starts_with("$B64$") {
+            match SmDtonData::try_new_b64(s) {
+                Ok(da) => {
+                    self._do_add(key, da);
+                    return Ok(());
+                }
+                Err(e) => {
+                    if strict {
+                        return Err(e);
                     }
-                    self.add_f64(key, v);
                 }
             }
-            _ => {}
         }
+        self.add_string(key, s);
+        return Ok(());
     }
 
     //+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
@@ -140,22 +169,59 @@ impl<'a> SmDtonMap<'a> {
             values: Vec::with_capacity(16),
             vdata_size: 0,
             len_blk: 0,
+
+            options: SmDtonOptions::default(),
+        }
+    }
+
+    pub fn with_options(options: SmDtonOptions) -> Self {
+        let mut obj = SmDtonMap::new();
+        obj.options = options;
+        return obj;
+    }
+
+    pub fn set_options(&mut self, options: SmDtonOptions) {
+        self.options = options;
+    }
+
+    #[allow(dead_code)]
+    pub fn add_from_json<J: SmDtonJsonValue>(&mut self, jsn: &'a J) {
+        if let SmDtonJsonKind::Object(obj) = jsn.json_kind() {
+            for (kn, value) in obj {
+                let _ = self._explore_node(kn, value, false);
+            }
         }
     }
 
     #[allow(dead_code)]
-    pub fn add_from_json(&mut self, jsn: &'a JsonValue) {
-        match jsn {
-            JsonValue::Object(obj) => {
-                for (kn, value) in obj.iter() {
-                    self._explore_node(kn, value);
+    pub fn try_add_from_json<J: SmDtonJsonValue>(&mut self, jsn: &'a J) -> Result<(), SmDtonError> {
+        match jsn.json_kind() {
+            SmDtonJsonKind::Object(obj) => {
+                for (kn, value) in obj {
+                    self._explore_node(kn, value, true)?;
                 }
+                return Ok(());
+            }
+            _ => {
+                return Err(SmDtonError::TypeMismatch {
+                    expected: ST::SMDT_MAP,
+                    found: jsn.json_found(),
+                });
             }
-            _ => {}
         }
     }
 
     // add to map
+    #[allow(dead_code)]
+    pub fn add_null(&mut self, key: &'a str) {
+        self._do_add(key, SmDtonData::new_null());
+    }
+
+    #[allow(dead_code)]
+    pub fn add_delete(&mut self, key: &'a str) {
+        self._do_add(key, SmDtonData::new_delete());
+    }
+
     def_map_add!(add_bool, new_bool, bool);
     def_map_add!(add_u8, new_u8, u8);
 
@@ -172,5 +238,16 @@ impl<'a> SmDtonMap<'a> {
 
     def_map_add!(add_string, new_string, &'a str);
     def_map_add!(add_bin, new_bin, &'a [u8]);
-    def_map_add!(add_base64, new_b64, &'a str);
+
+    #[allow(dead_code)]
+    pub fn add_base64(&mut self, key: &'a str, value: &'a str) {
+        let _ = self.try_add_base64(key, value);
+    }
+
+    #[allow(dead_code)]
+    pub fn try_add_base64(&mut self, key: &'a str, value: &'a str) -> Result<(), SmDtonError> {
+        let da = SmDtonData::try_new_b64(value)?;
+        self._do_add(key, da);
+        return Ok(());
+    }
 }
@@ -1,4 +1,3 @@
-
 pub struct SmDtonNode {
     pub smdt: u8,
     pub oid: usize,
@@ -6,7 +5,7 @@ pub struct SmDtonNode {
     pub values: Vec<usize>,
 }
 
-impl<'a> SmDtonNode {
+impl SmDtonNode {
     #[inline]
     pub fn new(smdt: u8, oid: usize) -> Self {
         SmDtonNode {
@@ -0,0 +1,50 @@
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SmDtonOptions {
+    // fail with InexactNumber instead of rounding an integer into f64, the
+    // lenient builders leave such a number out
+    pub exact_numbers: bool,
+    // store json integers in the narrowest type that holds them
+    pub narrow_ints: bool,
+    // order map entries by key so lookups can binary search, changes entry order
+    pub sorted_keys: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SmDtonJsonFormat {
+    // newline and indent between entries, a space after each colon
+    pub pretty: bool,
+    // spaces per level when pretty
+    pub indent: usize,
+    // write map entries ordered by key instead of stored order
+    pub sort_keys: bool,
+    // prepended to the base64 text of binary values
+    pub b64_prefix: &'static str,
+}
+
+impl Default for SmDtonJsonFormat {
+    fn default() -> Self {
+        return SmDtonJsonFormat {
+            pretty: false,
+            indent: 4,
+            sort_keys: false,
+            b64_prefix: "$B64$",
+        };
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SmDtonArrayMerge {
+    // the update array stands alone
+    #[default]
+    Replace,
+    // base items first, then the update items
+    Concat,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SmDtonMergeOptions {
+    // maps under the same key merge recursively instead of the update replacing them
+    pub deep: bool,
+    // arrays under the same key, applies at any depth
+    pub arrays: SmDtonArrayMerge,
+}
@@ -1,16 +1,33 @@
 use crate::SmDtonBuffer;
 
+// buffers from the base layer up, lookups start from the last one. new gives
+// the raw and update pair, more layers are pushed on top
 #[allow(dead_code)]
+#[derive(Default)]
 pub struct SmDtonPair {
-    pub raw: SmDtonBuffer,
-    pub update: SmDtonBuffer,
+    pub layers: Vec<SmDtonBuffer>,
 }
 
 impl SmDtonPair {
     pub fn new(raw: SmDtonBuffer, update: SmDtonBuffer) -> Self {
         SmDtonPair {
-            raw: raw,
-            update: update,
+            layers: vec![raw, update],
         }
     }
+
+    pub fn push(&mut self, smb: SmDtonBuffer) {
+        self.layers.push(smb);
+    }
+
+    pub fn pop(&mut self) -> Option<SmDtonBuffer> {
+        return self.layers.pop();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.layers.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.layers.is_empty();
+    }
 }
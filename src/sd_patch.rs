@@ -0,0 +1,616 @@
+// json merge patch (rfc 7386) and json patch (rfc 6902) applied to a buffer
+//
+// the document is opened only along the paths a patch touches, everything
+// else is copied from the reader with its own type, so a u16 stays a u16 and
+// binary stays binary. values given by a patch are stored the way from_json
+// stores them by default. opened maps hold the entries lookups see: the last
+// of equal keys, no tombstones. the result root must be a map or an array.
+
+use base64::{engine::general_purpose, Engine as _};
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use crate::sd_data::SmDtonData;
+use crate::sd_diff::{_items, _live_entries};
+use crate::sd_path::{pointer_index, pointer_tokens};
+use crate::sd_reader::MAX_DEPTH;
+use crate::{
+    SmDtonBuffer, SmDtonBuilder, SmDtonError, SmDtonJsonKind, SmDtonJsonValue, SmDtonNodeRef,
+    SmDtonOptions, SmDtonReader, SmDtonValue, ST,
+};
+
+// one json patch operation, paths are json pointers
+#[derive(Clone, Debug, PartialEq)]
+pub enum SmDtonPatchOp<J> {
+    Add { path: String, value: J },
+    Remove { path: String },
+    Replace { path: String, value: J },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: J },
+}
+
+impl<J> SmDtonPatchOp<J> {
+    #[allow(dead_code)]
+    pub fn path(&self) -> &str {
+        match self {
+            SmDtonPatchOp::Add { path, .. }
+            | SmDtonPatchOp::Remove { path }
+            | SmDtonPatchOp::Replace { path, .. }
+            | SmDtonPatchOp::Move { path, .. }
+            | SmDtonPatchOp::Copy { path, .. }
+            | SmDtonPatchOp::Test { path, .. } => return path,
+        }
+    }
+}
+
+// operations from their json form, [{"op":"add","path":"/a","value":1}]
+pub fn parse_json_patch<J: SmDtonJsonValue + Clone>(
+    jsn: &J,
+) -> Result<Vec<SmDtonPatchOp<J>>, SmDtonError> {
+    let arr = match jsn.json_kind() {
+        SmDtonJsonKind::Array(arr) => arr,
+        _ => return Err(SmDtonError::InvalidPatch { index: 0 }),
+    };
+    let mut ops = Vec::with_capacity(arr.len());
+    for (index, op) in arr.iter().enumerate() {
+        ops.push(_parse_op(op).ok_or(SmDtonError::InvalidPatch { index: index })?);
+    }
+    return Ok(ops);
+}
+
+// an empty buffer when the document does not read or the root is lost
+pub fn apply_merge_patch<'a, J: SmDtonJsonValue>(
+    reader: &SmDtonReader<'a>,
+    patch: &J,
+) -> SmDtonBuffer {
+    return try_apply_merge_patch(reader, patch).unwrap_or_else(|_| SmDtonBuffer::new());
+}
+
+pub fn try_apply_merge_patch<'a, J: SmDtonJsonValue>(
+    reader: &SmDtonReader<'a>,
+    patch: &J,
+) -> Result<SmDtonBuffer, SmDtonError> {
+    let mut patcher = SmDtonPatcher::try_new(reader)?;
+    _merge(reader, &mut patcher.root, patch, 0)?;
+    return patcher.build();
+}
+
+// operations apply in order, the first that fails stops the patch
+pub fn apply_json_patch<'a, J: SmDtonJsonValue>(
+    reader: &SmDtonReader<'a>,
+    ops: &[SmDtonPatchOp<J>],
+) -> Result<SmDtonBuffer, SmDtonError> {
+    let mut patcher = SmDtonPatcher::try_new(reader)?;
+    for (index, op) in ops.iter().enumerate() {
+        match patcher.apply(op) {
+            Ok(true) => {}
+            Ok(false) => {
+                return Err(SmDtonError::PatchFailed {
+                    index: index,
+                    path: op.path().to_string(),
+                })
+            }
+            Err(SmDtonError::InvalidPath { .. }) => {
+                return Err(SmDtonError::InvalidPatch { index: index })
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    return patcher.build();
+}
+
+fn _parse_op<J: SmDtonJsonValue + Clone>(jsn: &J) -> Option<SmDtonPatchOp<J>> {
+    let fields = match jsn.json_kind() {
+        SmDtonJsonKind::Object(obj) => obj,
+        _ => return None,
+    };
+    let field = |name: &str| fields.iter().find(|f| f.0 == name).map(|f| f.1);
+    let text = |name: &str| match field(name)?.json_kind() {
+        SmDtonJsonKind::Str(s) => Some(s.to_string()),
+        _ => None,
+    };
+
+    let path = text("path")?;
+    let op = match text("op")?.as_str() {
+        "add" => SmDtonPatchOp::Add {
+            path: path,
+            value: field("value")?.clone(),
+        },
+        "remove" => SmDtonPatchOp::Remove { path: path },
+        "replace" => SmDtonPatchOp::Replace {
+            path: path,
+            value: field("value")?.clone(),
+        },
+        "move" => SmDtonPatchOp::Move {
+            from: text("from")?,
+            path: path,
+        },
+        "copy" => SmDtonPatchOp::Copy {
+            from: text("from")?,
+            path: path,
+        },
+        "test" => SmDtonPatchOp::Test {
+            path: path,
+            value: field("value")?.clone(),
+        },
+        _ => return None,
+    };
+    return Some(op);
+}
+
+// the document as far as it is opened, keys of the patch are copied
+enum SmDtonPatchNode<'a, 'p, J> {
+    // untouched, copied from the reader
+    Read(SmDtonValue<'a>),
+    // given by the patch
+    Json(&'p J),
+    Map(Vec<(Cow<'a, str>, SmDtonPatchNode<'a, 'p, J>)>),
+    Arr(Vec<SmDtonPatchNode<'a, 'p, J>>),
+}
+
+impl<'a, 'p, J> Clone for SmDtonPatchNode<'a, 'p, J> {
+    fn clone(&self) -> Self {
+        match self {
+            SmDtonPatchNode::Read(value) => return SmDtonPatchNode::Read(*value),
+            SmDtonPatchNode::Json(jsn) => return SmDtonPatchNode::Json(jsn),
+            SmDtonPatchNode::Map(entries) => return SmDtonPatchNode::Map(entries.clone()),
+            SmDtonPatchNode::Arr(items) => return SmDtonPatchNode::Arr(items.clone()),
+        }
+    }
+}
+
+struct SmDtonPatcher<'r, 'a, 'p, J> {
+    reader: &'r SmDtonReader<'a>,
+    root: SmDtonPatchNode<'a, 'p, J>,
+}
+
+impl<'r, 'a, 'p, J: SmDtonJsonValue> SmDtonPatcher<'r, 'a, 'p, J> {
+    fn try_new(reader: &'r SmDtonReader<'a>) -> Result<Self, SmDtonError> {
+        let node = SmDtonNodeRef { oid: 1 };
+        let root = match reader.node_type(1) {
+            ST::SMDT_MAP => SmDtonValue::Map(node),
+            ST::SMDT_ARR => SmDtonValue::Arr(node),
+            _ => return Err(SmDtonError::InvalidNodeId(1)),
+        };
+        return Ok(SmDtonPatcher {
+            reader: reader,
+            root: SmDtonPatchNode::Read(root),
+        });
+    }
+
+    // false when a path does not lead where the operation needs it to
+    fn apply(&mut self, op: &'p SmDtonPatchOp<J>) -> Result<bool, SmDtonError> {
+        let q = self.reader;
+        match op {
+            SmDtonPatchOp::Add { path, value } => {
+                return self.add(&pointer_tokens(path)?, SmDtonPatchNode::Json(value));
+            }
+            SmDtonPatchOp::Remove { path } => {
+                return Ok(self.remove(&pointer_tokens(path)?)?.is_some());
+            }
+            SmDtonPatchOp::Replace { path, value } => {
+                match _find(q, &mut self.root, &pointer_tokens(path)?)? {
+                    Some(node) => *node = SmDtonPatchNode::Json(value),
+                    None => return Ok(false),
+                }
+                return Ok(true);
+            }
+            SmDtonPatchOp::Move { from, path } => {
+                let from = pointer_tokens(from)?;
+                let to = pointer_tokens(path)?;
+                if from == to {
+                    return Ok(_find(q, &mut self.root, &from)?.is_some());
+                }
+                // a value cannot move into itself
+                if to.len() > from.len() && to[..from.len()] == from[..] {
+                    return Ok(false);
+                }
+                match self.remove(&from)? {
+                    Some(node) => return self.add(&to, node),
+                    None => return Ok(false),
+                }
+            }
+            SmDtonPatchOp::Copy { from, path } => {
+                let node = match _find(q, &mut self.root, &pointer_tokens(from)?)? {
+                    Some(node) => node.clone(),
+                    None => return Ok(false),
+                };
+                return self.add(&pointer_tokens(path)?, node);
+            }
+            SmDtonPatchOp::Test { path, value } => {
+                match _find(q, &mut self.root, &pointer_tokens(path)?)? {
+                    Some(node) => return _equal(q, node, value, 0),
+                    None => return Ok(false),
+                }
+            }
+        }
+    }
+
+    // a map key is set, an array gets the value inserted, "-" appends
+    fn add(
+        &mut self,
+        tokens: &[String],
+        value: SmDtonPatchNode<'a, 'p, J>,
+    ) -> Result<bool, SmDtonError> {
+        let (last, parent) = match tokens.split_last() {
+            Some(split) => split,
+            None => {
+                self.root = value;
+                return Ok(true);
+            }
+        };
+        let parent = match _find(self.reader, &mut self.root, parent)? {
+            Some(node) => node,
+            None => return Ok(false),
+        };
+        _open(self.reader, parent)?;
+        match parent {
+            SmDtonPatchNode::Map(entries) => {
+                match entries.iter().position(|entry| entry.0 == last.as_str()) {
+                    Some(at) => entries[at].1 = value,
+                    None => entries.push((Cow::Owned(last.clone()), value)),
+                }
+            }
+            SmDtonPatchNode::Arr(items) => {
+                let at = match last.as_str() {
+                    "-" => items.len(),
+                    token => match pointer_index(token) {
+                        Some(at) if at <= items.len() => at,
+                        _ => return Ok(false),
+                    },
+                };
+                items.insert(at, value);
+            }
+            _ => return Ok(false),
+        }
+        return Ok(true);
+    }
+
+    fn remove(
+        &mut self,
+        tokens: &[String],
+    ) -> Result<Option<SmDtonPatchNode<'a, 'p, J>>, SmDtonError> {
+        let (last, parent) = match tokens.split_last() {
+            Some(split) => split,
+            None => return Ok(None),
+        };
+        let parent = match _find(self.reader, &mut self.root, parent)? {
+            Some(node) => node,
+            None => return Ok(None),
+        };
+        _open(self.reader, parent)?;
+        match parent {
+            SmDtonPatchNode::Map(entries) => {
+                match entries.iter().position(|entry| entry.0 == last.as_str()) {
+                    Some(at) => return Ok(Some(entries.remove(at).1)),
+                    None => return Ok(None),
+                }
+            }
+            SmDtonPatchNode::Arr(items) => match pointer_index(last) {
+                Some(at) if at < items.len() => return Ok(Some(items.remove(at))),
+                _ => return Ok(None),
+            },
+            _ => return Ok(None),
+        }
+    }
+
+    fn build(self) -> Result<SmDtonBuffer, SmDtonError> {
+        let mut root = self.root;
+        _open(self.reader, &mut root)?;
+        let found = match &root {
+            SmDtonPatchNode::Map(_) | SmDtonPatchNode::Arr(_) => 0,
+            SmDtonPatchNode::Json(jsn) => jsn.json_found(),
+            SmDtonPatchNode::Read(value) => SmDtonData::from_scalar(*value).map_or(0, |da| da.smdt),
+        };
+        if found != 0 {
+            return Err(SmDtonError::NotANode { found: found });
+        }
+
+        let mut bd = SmDtonBuilder::new();
+        _data(&mut bd, self.reader, root, 0)?;
+        return Ok(bd.build());
+    }
+}
+
+// rfc 7386: null removes a key, an object merges, anything else replaces
+fn _merge<'a, 'p, J: SmDtonJsonValue>(
+    q: &SmDtonReader<'a>,
+    node: &mut SmDtonPatchNode<'a, 'p, J>,
+    patch: &'p J,
+    depth: usize,
+) -> Result<(), SmDtonError> {
+    if depth > MAX_DEPTH {
+        return Err(SmDtonError::TooDeep { depth: depth });
+    }
+    let obj = match patch.json_kind() {
+        SmDtonJsonKind::Object(obj) => obj,
+        _ => {
+            *node = SmDtonPatchNode::Json(patch);
+            return Ok(());
+        }
+    };
+    _open(q, node)?;
+    if !matches!(node, SmDtonPatchNode::Map(_)) {
+        *node = SmDtonPatchNode::Map(Vec::new());
+    }
+    let entries = match node {
+        SmDtonPatchNode::Map(entries) => entries,
+        _ => return Ok(()),
+    };
+
+    for (key, value) in obj {
+        let at = entries.iter().position(|entry| entry.0 == key);
+        match (value.json_kind(), at) {
+            (SmDtonJsonKind::Null, Some(at)) => {
+                entries.remove(at);
+            }
+            (SmDtonJsonKind::Null, None) => {}
+            (_, Some(at)) => _merge(q, &mut entries[at].1, value, depth + 1)?,
+            (_, None) => {
+                let mut child = SmDtonPatchNode::Map(Vec::new());
+                _merge(q, &mut child, value, depth + 1)?;
+                entries.push((Cow::Owned(key.to_string()), child));
+            }
+        }
+    }
+    return Ok(());
+}
+
+// a map or array of the reader or the patch turned into its children
+fn _open<'a, 'p, J: SmDtonJsonValue>(
+    q: &SmDtonReader<'a>,
+    node: &mut SmDtonPatchNode<'a, 'p, J>,
+) -> Result<(), SmDtonError> {
+    let opened = match node {
+        SmDtonPatchNode::Read(SmDtonValue::Map(n)) => {
+            let entries = _live_entries(q, n.oid)?.into_iter();
+            SmDtonPatchNode::Map(
+                entries
+                    .map(|(key, value)| (Cow::Borrowed(key), SmDtonPatchNode::Read(value)))
+                    .collect(),
+            )
+        }
+        SmDtonPatchNode::Read(SmDtonValue::Arr(n)) => {
+            let items = _items(q, n.oid)?.into_iter();
+            SmDtonPatchNode::Arr(
+                items
+                    .filter(|value| *value != SmDtonValue::Deleted)
+                    .map(SmDtonPatchNode::Read)
+                    .collect(),
+            )
+        }
+        SmDtonPatchNode::Json(jsn) => {
+            let jsn: &'p J = jsn;
+            match jsn.json_kind() {
+                SmDtonJsonKind::Object(obj) => SmDtonPatchNode::Map(
+                    obj.into_iter()
+                        .map(|(key, value)| {
+                            (Cow::Owned(key.to_string()), SmDtonPatchNode::Json(value))
+                        })
+                        .collect(),
+                ),
+                SmDtonJsonKind::Array(arr) => {
+                    SmDtonPatchNode::Arr(arr.iter().map(SmDtonPatchNode::Json).collect())
+                }
+                _ => return Ok(()),
+            }
+        }
+        _ => return Ok(()),
+    };
+    *node = opened;
+    return Ok(());
+}
+
+fn _find<'n, 'a, 'p, J: SmDtonJsonValue>(
+    q: &SmDtonReader<'a>,
+    node: &'n mut SmDtonPatchNode<'a, 'p, J>,
+    tokens: &[String],
+) -> Result<Option<&'n mut SmDtonPatchNode<'a, 'p, J>>, SmDtonError> {
+    let mut node = node;
+    for token in tokens {
+        _open(q, node)?;
+        node = match node {
+            SmDtonPatchNode::Map(entries) => {
+                match entries.iter_mut().find(|entry| entry.0 == token.as_str()) {
+                    Some(entry) => &mut entry.1,
+                    None => return Ok(None),
+                }
+            }
+            SmDtonPatchNode::Arr(items) => match pointer_index(token) {
+                Some(at) if at < items.len() => &mut items[at],
+                _ => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+    }
+    return Ok(Some(node));
+}
+
+// json equality, numbers by value whatever their stored type
+fn _equal<'a, 'p, J: SmDtonJsonValue>(
+    q: &SmDtonReader<'a>,
+    node: &SmDtonPatchNode<'a, 'p, J>,
+    jsn: &J,
+    depth: usize,
+) -> Result<bool, SmDtonError> {
+    if depth > MAX_DEPTH {
+        return Err(SmDtonError::TooDeep { depth: depth });
+    }
+    match node {
+        SmDtonPatchNode::Read(SmDtonValue::Map(n)) | SmDtonPatchNode::Read(SmDtonValue::Arr(n)) => {
+            let value: J = q.try_to_json_value(n.oid)?;
+            return Ok(_json_eq(&value, jsn));
+        }
+        SmDtonPatchNode::Read(value) => return Ok(_json_eq(&_scalar_json::<J>(*value), jsn)),
+        SmDtonPatchNode::Json(value) => return Ok(_json_eq(*value, jsn)),
+        SmDtonPatchNode::Map(entries) => {
+            let obj = match jsn.json_kind() {
+                SmDtonJsonKind::Object(obj) => obj,
+                _ => return Ok(false),
+            };
+            if obj.len() != entries.len() {
+                return Ok(false);
+            }
+            for (key, value) in entries {
+                match obj.iter().find(|field| field.0 == key) {
+                    Some(field) if _equal(q, value, field.1, depth + 1)? => {}
+                    _ => return Ok(false),
+                }
+            }
+            return Ok(true);
+        }
+        SmDtonPatchNode::Arr(items) => {
+            let arr = match jsn.json_kind() {
+                SmDtonJsonKind::Array(arr) => arr,
+                _ => return Ok(false),
+            };
+            if arr.len() != items.len() {
+                return Ok(false);
+            }
+            for (value, other) in items.iter().zip(arr.iter()) {
+                if !_equal(q, value, other, depth + 1)? {
+                    return Ok(false);
+                }
+            }
+            return Ok(true);
+        }
+    }
+}
+
+fn _json_eq<J: SmDtonJsonValue>(a: &J, b: &J) -> bool {
+    match (a.json_kind(), b.json_kind()) {
+        (SmDtonJsonKind::Null, SmDtonJsonKind::Null) => return true,
+        (SmDtonJsonKind::Bool(x), SmDtonJsonKind::Bool(y)) => return x == y,
+        (SmDtonJsonKind::Str(x), SmDtonJsonKind::Str(y)) => return x == y,
+        (SmDtonJsonKind::Object(x), SmDtonJsonKind::Object(y)) => {
+            return x.len() == y.len()
+                && x.iter()
+                    .all(|(k, v)| y.iter().any(|(l, w)| k == l && _json_eq(*v, *w)));
+        }
+        (SmDtonJsonKind::Array(x), SmDtonJsonKind::Array(y)) => {
+            return x.len() == y.len() && x.iter().zip(y.iter()).all(|(v, w)| _json_eq(v, w));
+        }
+        (SmDtonJsonKind::Number(p, m, e, _), SmDtonJsonKind::Number(q, n, f, _)) => {
+            return _parts(p, m, e) == _parts(q, n, f);
+        }
+        (x, y) => match (_float(&x), _float(&y)) {
+            (Some(v), Some(w)) => return v == w,
+            _ => return false,
+        },
+    }
+}
+
+// decimal parts without trailing zeros, zero has no sign
+fn _parts(positive: bool, mut mantissa: u64, mut exponent: i16) -> (bool, u64, i16) {
+    if mantissa == 0 {
+        return (true, 0, 0);
+    }
+    while mantissa.is_multiple_of(10) {
+        mantissa /= 10;
+        exponent = exponent.saturating_add(1);
+    }
+    return (positive, mantissa, exponent);
+}
+
+fn _float<J>(kind: &SmDtonJsonKind<J>) -> Option<f64> {
+    match kind {
+        SmDtonJsonKind::Number(positive, mantissa, exponent, _) => {
+            let v: f64 = format!("{}e{}", mantissa, exponent).parse().ok()?;
+            return Some(if *positive { v } else { -v });
+        }
+        SmDtonJsonKind::Float(v) => return Some(*v),
+        _ => return None,
+    }
+}
+
+// as the reader converts them
+fn _scalar_json<J: SmDtonJsonValue>(value: SmDtonValue) -> J {
+    match value {
+        SmDtonValue::Bool(v) => return J::json_bool(v),
+        SmDtonValue::U8(v) => return J::json_u64(v.into()),
+        SmDtonValue::I16(v) => return J::json_i64(v.into()),
+        SmDtonValue::U16(v) => return J::json_u64(v.into()),
+        SmDtonValue::I32(v) => return J::json_i64(v.into()),
+        SmDtonValue::U32(v) => return J::json_u64(v.into()),
+        SmDtonValue::F32(v) => return J::json_f64(v.into()),
+        SmDtonValue::I64(v) => return J::json_i64(v),
+        SmDtonValue::U64(v) => return J::json_u64(v),
+        SmDtonValue::F64(v) => return J::json_f64(v),
+        SmDtonValue::Str(v) => return J::json_str(v),
+        SmDtonValue::Bin(v) => {
+            let data = general_purpose::STANDARD.encode(v);
+            return J::json_str(&("$B64$".to_string() + &data));
+        }
+        _ => return J::json_null(),
+    }
+}
+
+fn _data<'a, 'p, J: SmDtonJsonValue>(
+    bd: &mut SmDtonBuilder<'a>,
+    q: &SmDtonReader<'a>,
+    node: SmDtonPatchNode<'a, 'p, J>,
+    depth: usize,
+) -> Result<SmDtonData<'a>, SmDtonError> {
+    if depth > MAX_DEPTH {
+        return Err(SmDtonError::TooDeep { depth: depth });
+    }
+    match node {
+        SmDtonPatchNode::Read(value) => {
+            // a copied value may repeat a node, each copy stands alone
+            return bd._copy_value(q, value, &mut HashSet::new(), depth);
+        }
+        SmDtonPatchNode::Json(jsn) => match jsn.json_kind() {
+            SmDtonJsonKind::Object(_) | SmDtonJsonKind::Array(_) => {
+                let mut node = SmDtonPatchNode::Json(jsn);
+                _open(q, &mut node)?;
+                return _data(bd, q, node, depth);
+            }
+            kind => return _json_data(kind),
+        },
+        SmDtonPatchNode::Map(entries) => {
+            let oid = bd.create_node(ST::SMDT_MAP);
+            for (key, value) in entries {
+                let da = _data(bd, q, value, depth + 1)?;
+                bd._do_add(oid, key, da);
+            }
+            return Ok(bd._node_data(oid));
+        }
+        SmDtonPatchNode::Arr(items) => {
+            let oid = bd.create_node(ST::SMDT_ARR);
+            for value in items {
+                let da = _data(bd, q, value, depth + 1)?;
+                bd._do_push(oid, da);
+            }
+            return Ok(bd._node_data(oid));
+        }
+    }
+}
+
+// scalars as from_json stores them with default options, strings copied
+fn _json_data<'a, J>(kind: SmDtonJsonKind<J>) -> Result<SmDtonData<'a>, SmDtonError> {
+    let options = SmDtonOptions::default();
+    match kind {
+        SmDtonJsonKind::Null => return Ok(SmDtonData::new_null()),
+        SmDtonJsonKind::Bool(v) => return Ok(SmDtonData::new_bool(v)),
+        SmDtonJsonKind::Number(positive, mantissa, exponent, truncated) => {
+            return SmDtonData::try_new_number(
+                positive,
+                mantissa,
+                exponent,
+                truncated,
+                options.exact_numbers,
+                options.narrow_ints,
+            );
+        }
+        SmDtonJsonKind::Float(v) => return Ok(SmDtonData::new_f64(v)),
+        SmDtonJsonKind::Str(s) if s.starts_with("$B64$") => {
+            return Ok(SmDtonData::try_new_b64(s)
+                .unwrap_or_else(|_| SmDtonData::new_string_owned(s.to_string())));
+        }
+        SmDtonJsonKind::Str(s) => return Ok(SmDtonData::new_string_owned(s.to_string())),
+        SmDtonJsonKind::Object(_) | SmDtonJsonKind::Array(_) => {
+            return Err(SmDtonError::InvalidNodeId(0))
+        }
+    }
+}
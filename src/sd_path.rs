@@ -0,0 +1,148 @@
+use crate::SmDtonError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SmDtonPathSeg<'p> {
+    Key(&'p str),
+    Index(usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum SmDtonPathPart {
+    Key(String),
+    Index(usize),
+}
+
+// parsed form of "servers[2].port", keys escape . [ ] and \ with a backslash
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SmDtonPath {
+    parts: Vec<SmDtonPathPart>,
+}
+
+impl SmDtonPath {
+    pub fn parse(path: &str) -> Result<Self, SmDtonError> {
+        let mut parts = Vec::new();
+        let bytes = path.as_bytes();
+        let mut pos = 0;
+        let mut want_key = !bytes.is_empty() && bytes[0] != b'[';
+        while pos < bytes.len() || want_key {
+            if want_key {
+                let mut key = String::new();
+                let mut start = pos;
+                while pos < bytes.len() {
+                    match bytes[pos] {
+                        b'\\' => {
+                            key.push_str(&path[start..pos]);
+                            match bytes.get(pos + 1) {
+                                Some(b'.') | Some(b'[') | Some(b']') | Some(b'\\') => {}
+                                _ => return Err(SmDtonError::InvalidPath { offset: pos }),
+                            }
+                            start = pos + 1;
+                            pos += 2;
+                        }
+                        b'.' | b'[' => break,
+                        b']' => return Err(SmDtonError::InvalidPath { offset: pos }),
+                        _ => pos += 1,
+                    }
+                }
+                key.push_str(&path[start..pos]);
+                parts.push(SmDtonPathPart::Key(key));
+                want_key = false;
+                continue;
+            }
+
+            match bytes[pos] {
+                b'.' => {
+                    pos += 1;
+                    want_key = true;
+                }
+                b'[' => {
+                    let start = pos + 1;
+                    let mut end = start;
+                    while end < bytes.len() && bytes[end].is_ascii_digit() {
+                        end += 1;
+                    }
+                    if end == start || bytes.get(end) != Some(&b']') {
+                        return Err(SmDtonError::InvalidPath { offset: end });
+                    }
+                    match path[start..end].parse::<usize>() {
+                        Ok(index) => parts.push(SmDtonPathPart::Index(index)),
+                        Err(_) => return Err(SmDtonError::InvalidPath { offset: start }),
+                    }
+                    pos = end + 1;
+                }
+                _ => return Err(SmDtonError::InvalidPath { offset: pos }),
+            }
+        }
+        return Ok(SmDtonPath { parts: parts });
+    }
+
+    pub fn segs(&self) -> Vec<SmDtonPathSeg<'_>> {
+        let mut segs = Vec::with_capacity(self.parts.len());
+        for part in self.parts.iter() {
+            match part {
+                SmDtonPathPart::Key(key) => segs.push(SmDtonPathSeg::Key(key)),
+                SmDtonPathPart::Index(index) => segs.push(SmDtonPathSeg::Index(*index)),
+            }
+        }
+        return segs;
+    }
+}
+
+// append a key in path syntax, escaping . [ ] and \
+#[allow(dead_code)]
+pub(crate) fn push_path_key(path: &mut String, key: &str) {
+    if !path.is_empty() {
+        path.push('.');
+    }
+    for ch in key.chars() {
+        if ch == '.' || ch == '[' || ch == ']' || ch == '\\' {
+            path.push('\\');
+        }
+        path.push(ch);
+    }
+}
+
+// rfc 6901 reference tokens, "" is the whole document
+pub(crate) fn pointer_tokens(pointer: &str) -> Result<Vec<String>, SmDtonError> {
+    let mut tokens = Vec::new();
+    if pointer.is_empty() {
+        return Ok(tokens);
+    }
+    if !pointer.starts_with('/') {
+        return Err(SmDtonError::InvalidPath { offset: 0 });
+    }
+    let mut offset = 1;
+    for raw in pointer[1..].split('/') {
+        let mut token = String::with_capacity(raw.len());
+        let mut chars = raw.char_indices();
+        while let Some((i, ch)) = chars.next() {
+            if ch != '~' {
+                token.push(ch);
+                continue;
+            }
+            match chars.next() {
+                Some((_, '0')) => token.push('~'),
+                Some((_, '1')) => token.push('/'),
+                _ => return Err(SmDtonError::InvalidPath { offset: offset + i }),
+            }
+        }
+        tokens.push(token);
+        offset += raw.len() + 1;
+    }
+    return Ok(tokens);
+}
+
+pub(crate) fn escape_pointer_token(token: &str) -> String {
+    return token.replace('~', "~0").replace('/', "~1");
+}
+
+// array index token, digits without a leading zero
+pub(crate) fn pointer_index(token: &str) -> Option<usize> {
+    if token.is_empty() || (token.len() > 1 && token.starts_with('0')) {
+        return None;
+    }
+    if !token.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    return token.parse().ok();
+}
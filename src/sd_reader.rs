@@ -1,15 +1,21 @@
 use base64::{engine::general_purpose, Engine as _};
+#[cfg(feature = "json")]
 use json::JsonValue;
 use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
 
 use super::sd_data::ST;
+use super::sd_error::SmDtonError;
+use super::sd_json::SmDtonJsonValue;
+use super::sd_path::{self, SmDtonPath, SmDtonPathSeg};
+use super::sd_value::{SmDtonEntries, SmDtonItems, SmDtonNodeRef, SmDtonValue};
 use super::su;
 
 macro_rules! smd_check_type {
     ($off: expr, $self: expr, $smdt: expr) => {
-        let body_type = $self.u8a[$off];
-        if body_type != $smdt {
-            return None;
+        match $self.u8a.get($off) {
+            Some(body_type) if *body_type == $smdt => {}
+            _ => return None,
         }
     };
 }
@@ -22,11 +28,19 @@ macro_rules! smd_check_node_id {
     };
 }
 
+macro_rules! smd_try_node_id {
+    ($self: expr, $oid: expr) => {
+        if $oid == 0 || $oid > $self.nnum {
+            return Err(SmDtonError::InvalidNodeId($oid));
+        }
+    };
+}
+
 macro_rules! smd_handle_node {
     ($self: expr, $oid: expr, $n_off: ident, $p_off: ident, $sub_num: ident) => {
         let $n_off = $self.node_off + ($oid - 1) * (1 + $self.oz);
         let $p_off = $self.get_int(1 + $n_off);
-        let $sub_num = $self.get_int($p_off);
+        let $sub_num = $self._sub_num($n_off, $p_off);
     };
 }
 
@@ -34,18 +48,46 @@ macro_rules! def_func_get_by_voff {
     ($func_name:ident, $len: expr, $rty: ty, $smdt: expr) => {
         #[inline]
         pub fn $func_name(&self, value_off: usize) -> Option<$rty> {
-            let body_type = self.u8a[value_off];
-            if body_type != $smdt {
-                return None;
-            }
+            smd_check_type!(value_off, self, $smdt);
             let mut bytes: [u8; $len] = [0; $len];
-            bytes.copy_from_slice(&self.u8a[value_off + 1..value_off + 1 + $len]);
+            bytes.copy_from_slice(self.u8a.get(value_off + 1..value_off + 1 + $len)?);
             let d = <$rty>::from_le_bytes(bytes) as $rty;
             return Some(d);
         }
     };
 }
 
+macro_rules! def_func_try_get_by_voff {
+    ($func_name:ident, $len: expr, $rty: ty, $smdt: expr) => {
+        #[inline]
+        pub fn $func_name(&self, value_off: usize) -> Result<$rty, SmDtonError> {
+            self._try_type(value_off, $smdt)?;
+            let mut bytes: [u8; $len] = [0; $len];
+            bytes.copy_from_slice(self._try_slice(value_off + 1, $len)?);
+            let d = <$rty>::from_le_bytes(bytes) as $rty;
+            return Ok(d);
+        }
+    };
+}
+
+macro_rules! def_func_get_int_by_voff {
+    ($func_name:ident, $try_name:ident, $rty: ty, $smdt: expr) => {
+        #[inline]
+        pub fn $try_name(&self, value_off: usize) -> Result<$rty, SmDtonError> {
+            let (found, v) = self._try_int_by_voff(value_off, $smdt)?;
+            match <$rty>::try_from(v) {
+                Ok(d) => return Ok(d),
+                Err(_) => return Err(SmDtonError::OutOfRange { found: found }),
+            }
+        }
+
+        #[inline]
+        pub fn $func_name(&self, value_off: usize) -> Option<$rty> {
+            return self.$try_name(value_off).ok();
+        }
+    };
+}
+
 macro_rules! def_func_get_by_key {
     ($func_name:ident, $act_name: ident, $rty: ty) => {
         #[allow(dead_code)]
@@ -72,41 +114,122 @@ macro_rules! def_func_get_by_id {
     };
 }
 
+macro_rules! def_func_try_get_by_key {
+    ($func_name:ident, $act_name: ident, $rty: ty) => {
+        #[allow(dead_code)]
+        pub fn $func_name(&self, oid: usize, key: &str) -> Result<Option<$rty>, SmDtonError> {
+            match self.try_get_field_voff(oid, key)? {
+                Some(value_off) => return Ok(Some(self.$act_name(value_off)?)),
+                None => return Ok(None),
+            }
+        }
+    };
+}
+
+macro_rules! def_func_try_get_by_id {
+    ($func_name:ident, $act_name: ident, $rty: ty) => {
+        #[allow(dead_code)]
+        pub fn $func_name(&self, oid: usize, index: usize) -> Result<Option<$rty>, SmDtonError> {
+            match self.try_get_sub_voff(oid, index)? {
+                Some(value_off) => return Ok(Some(self.$act_name(value_off)?)),
+                None => return Ok(None),
+            }
+        }
+    };
+}
+
+macro_rules! def_func_get_by_path {
+    ($func_name:ident, $act_name: ident, $rty: ty) => {
+        #[allow(dead_code)]
+        pub fn $func_name(&self, oid: usize, path: &str) -> Option<$rty> {
+            let path = SmDtonPath::parse(path).ok()?;
+            let value_off = self.get_path_voff(oid, &path.segs());
+            if value_off == 0 {
+                return None;
+            }
+            return self.$act_name(value_off);
+        }
+    };
+}
+
+macro_rules! def_func_try_get_by_path {
+    ($func_name:ident, $act_name: ident, $rty: ty) => {
+        #[allow(dead_code)]
+        pub fn $func_name(&self, oid: usize, path: &str) -> Result<Option<$rty>, SmDtonError> {
+            let path = SmDtonPath::parse(path)?;
+            match self.try_get_path_voff(oid, &path.segs())? {
+                Some(value_off) => return Ok(Some(self.$act_name(value_off)?)),
+                None => return Ok(None),
+            }
+        }
+    };
+}
+
+macro_rules! def_func_get_by_pointer {
+    ($func_name:ident, $act_name: ident, $rty: ty) => {
+        #[allow(dead_code)]
+        pub fn $func_name(&self, oid: usize, pointer: &str) -> Option<$rty> {
+            let value_off = self.get_pointer_voff(oid, pointer);
+            if value_off == 0 {
+                return None;
+            }
+            return self.$act_name(value_off);
+        }
+    };
+}
+
+macro_rules! def_func_try_get_by_pointer {
+    ($func_name:ident, $act_name: ident, $rty: ty) => {
+        #[allow(dead_code)]
+        pub fn $func_name(&self, oid: usize, pointer: &str) -> Result<Option<$rty>, SmDtonError> {
+            match self.try_get_pointer_voff(oid, pointer)? {
+                Some(value_off) => return Ok(Some(self.$act_name(value_off)?)),
+                None => return Ok(None),
+            }
+        }
+    };
+}
+
 macro_rules! smd_get_key {
     ($self: expr, $p_off: expr, $index: expr, $txt: ident) => {
         let sub_off = $p_off + (1 + 2 * $index) * $self.oz;
-        let key_off = $self.get_int(sub_off);
-        let kw = $self.get_int(key_off);
-
-        let piece = &$self.u8a[key_off + $self.oz..key_off + $self.oz + kw - 1];
-        let $txt = std::str::from_utf8(piece).unwrap().to_string();
+        let $txt = $self._try_key($self.get_int(sub_off))?;
     };
 }
 
 macro_rules! smd_add_number {
-    ($self: expr, $len: expr, $voff: expr, $rty: ty, $obj: expr, $key: expr) => {
+    ($self: expr, $len: expr, $voff: expr, $rty: ty, $make: expr, $obj: expr, $key: expr) => {
         let mut bytes: [u8; $len] = [0; $len];
-        bytes.copy_from_slice(&$self.u8a[$voff + 1..$voff + 1 + $len]);
+        bytes.copy_from_slice($self._try_slice($voff + 1, $len)?);
         let data = <$rty>::from_le_bytes(bytes) as $rty;
-        $obj[$key] = JsonValue::from(data);
+        $obj.json_insert($key, $make(data.into()));
     };
 }
 
 macro_rules! smd_push_number {
-    ($self: expr, $len: expr, $voff: expr, $rty: ty, $obj: expr) => {
+    ($self: expr, $len: expr, $voff: expr, $rty: ty, $make: expr, $obj: expr) => {
         let mut bytes: [u8; $len] = [0; $len];
-        bytes.copy_from_slice(&$self.u8a[$voff + 1..$voff + 1 + $len]);
+        bytes.copy_from_slice($self._try_slice($voff + 1, $len)?);
         let data = <$rty>::from_le_bytes(bytes) as $rty;
-        $obj.push(data).unwrap();
+        $obj.json_push($make(data.into()));
     };
 }
 
+pub(crate) const MAX_DEPTH: usize = 256;
+
+// per node key lookup, filled on first use
+type SmDtonKeyIndex<'a> = Arc<Vec<OnceLock<HashMap<&'a [u8], usize>>>>;
+
 pub struct SmDtonReader<'a> {
     u8a: &'a [u8],
     oz: usize,
     nnum: usize,
 
     node_off: usize,
+    sorted: bool,
+    key_index: Option<SmDtonKeyIndex<'a>>,
+    // node and entry holding each node, built by the first pointer_of
+    parents: OnceLock<Vec<(usize, usize)>>,
 }
 
 impl<'a> SmDtonReader<'a> {
@@ -124,14 +247,24 @@ impl<'a> SmDtonReader<'a> {
         let kw = kbs.len() + 1;
 
         smd_handle_node!(self, oid, n_off, p_off, sub_num);
+        if self.u8a.get(n_off) != Some(&ST::SMDT_MAP) {
+            return 0;
+        }
+        if self.sorted {
+            return self._find_sorted(p_off, sub_num, kbs).unwrap_or(0);
+        }
+        if let Some(index) = &self.key_index {
+            let table = index[oid].get_or_init(|| self._index_node(p_off, sub_num));
+            return table.get(kbs).copied().unwrap_or(0);
+        }
         for i in (0..sub_num).rev() {
             let p_i_off = p_off + self.oz + self.oz * 2 * i;
             let mut key_off = self.get_int(p_i_off);
             let key_len = self.get_int(key_off);
             if kw == key_len {
                 key_off += self.oz;
-                let kbody = &self.u8a[key_off..key_off + kw - 1];
-                if kbs == kbody {
+                let kbody = self.u8a.get(key_off..key_off + kw - 1);
+                if Some(kbs) == kbody {
                     let value_off = self.get_int(p_i_off + self.oz);
                     return value_off;
                 }
@@ -149,46 +282,614 @@ impl<'a> SmDtonReader<'a> {
         }
 
         let mut off = index * self.oz;
-        let node_type = self.u8a[n_off];
-        if node_type == ST::SMDT_MAP {
+        if self.u8a.get(n_off) == Some(&ST::SMDT_MAP) {
             off = off * 2 + self.oz;
         }
         let voff = self.get_int(p_off + self.oz + off);
         return voff;
     }
 
+    pub fn try_get_field_voff(&self, oid: usize, key: &str) -> Result<Option<usize>, SmDtonError> {
+        smd_try_node_id!(self, oid);
+        smd_handle_node!(self, oid, n_off, p_off, sub_num);
+        self._try_type(n_off, ST::SMDT_MAP)?;
+
+        if self.sorted {
+            let mut lo = 0;
+            let mut hi = sub_num;
+            while lo < hi {
+                let mid = lo + (hi - lo) / 2;
+                if self._try_entry_key(p_off, mid)? <= key {
+                    lo = mid + 1;
+                } else {
+                    hi = mid;
+                }
+            }
+            if lo > 0 && self._try_entry_key(p_off, lo - 1)? == key {
+                return Ok(Some(self.get_int(p_off + 2 * self.oz * lo)));
+            }
+            return Ok(None);
+        }
+        if let Some(index) = &self.key_index {
+            let table = index[oid].get_or_init(|| self._index_node(p_off, sub_num));
+            return Ok(table.get(key.as_bytes()).copied());
+        }
+
+        for i in (0..sub_num).rev() {
+            let p_i_off = p_off + self.oz + self.oz * 2 * i;
+            self._try_slice(p_i_off, 2 * self.oz)?;
+            if self._try_key(self.get_int(p_i_off))? == key {
+                return Ok(Some(self.get_int(p_i_off + self.oz)));
+            }
+        }
+        return Ok(None);
+    }
+
+    pub fn try_get_sub_voff(&self, oid: usize, index: usize) -> Result<Option<usize>, SmDtonError> {
+        smd_try_node_id!(self, oid);
+        smd_handle_node!(self, oid, n_off, p_off, sub_num);
+        if index >= sub_num {
+            return Ok(None);
+        }
+
+        let mut off = index * self.oz;
+        if self._try_slice(n_off, 1)?[0] == ST::SMDT_MAP {
+            off = off * 2 + self.oz;
+        }
+        self._try_slice(p_off + self.oz + off, self.oz)?;
+        return Ok(Some(self.get_int(p_off + self.oz + off)));
+    }
+
+    // value offset at the end of a path, 0 when any step is missing
+    #[allow(dead_code)]
+    pub fn get_path_voff(&self, oid: usize, segs: &[SmDtonPathSeg]) -> usize {
+        return self
+            .try_get_path_voff(oid, segs)
+            .unwrap_or(None)
+            .unwrap_or(0);
+    }
+
+    #[allow(dead_code)]
+    pub fn try_get_path_voff(
+        &self,
+        oid: usize,
+        segs: &[SmDtonPathSeg],
+    ) -> Result<Option<usize>, SmDtonError> {
+        smd_try_node_id!(self, oid);
+        let mut node = oid;
+        let mut value_off = 0;
+        for (i, seg) in segs.iter().enumerate() {
+            if i > 0 {
+                node = match self._try_path_node(value_off)? {
+                    Some(node) => node,
+                    None => return Ok(None),
+                };
+            }
+            let found = match seg {
+                SmDtonPathSeg::Key(_) if self.node_type(node) == ST::SMDT_ARR => None,
+                SmDtonPathSeg::Key(key) => self.try_get_field_voff(node, key)?,
+                SmDtonPathSeg::Index(index) => self.try_get_sub_voff(node, *index)?,
+            };
+            match found {
+                Some(off) => value_off = off,
+                None => return Ok(None),
+            }
+        }
+        if value_off == 0 {
+            // an empty path has no value offset of its own
+            return Ok(None);
+        }
+        return Ok(Some(value_off));
+    }
+
+    // value offset behind a json pointer, 0 when any token is missing
+    #[allow(dead_code)]
+    pub fn get_pointer_voff(&self, oid: usize, pointer: &str) -> usize {
+        return self
+            .try_get_pointer_voff(oid, pointer)
+            .unwrap_or(None)
+            .unwrap_or(0);
+    }
+
+    #[allow(dead_code)]
+    pub fn try_get_pointer_voff(
+        &self,
+        oid: usize,
+        pointer: &str,
+    ) -> Result<Option<usize>, SmDtonError> {
+        let tokens = sd_path::pointer_tokens(pointer)?;
+        smd_try_node_id!(self, oid);
+        let mut node = oid;
+        let mut value_off = 0;
+        for (i, token) in tokens.iter().enumerate() {
+            if i > 0 {
+                node = match self._try_path_node(value_off)? {
+                    Some(node) => node,
+                    None => return Ok(None),
+                };
+            }
+            let found = match self.node_type(node) {
+                ST::SMDT_ARR => match sd_path::pointer_index(token) {
+                    Some(index) => self.try_get_sub_voff(node, index)?,
+                    None => None,
+                },
+                _ => self.try_get_field_voff(node, token)?,
+            };
+            match found {
+                Some(off) => value_off = off,
+                None => return Ok(None),
+            }
+        }
+        if value_off == 0 {
+            return Ok(None);
+        }
+        return Ok(Some(value_off));
+    }
+
+    // a path stepping past a scalar finds nothing, that is not an error
+    fn _try_path_node(&self, value_off: usize) -> Result<Option<usize>, SmDtonError> {
+        match self.try_get_node_id_by_voff(value_off) {
+            Ok(node) => return Ok(Some(node)),
+            Err(SmDtonError::NotANode { .. }) => return Ok(None),
+            Err(e) => return Err(e),
+        }
+    }
+
+    // json pointer to a child of a node, found through the parents of the root tree
+    #[allow(dead_code)]
+    pub fn pointer_of(&self, oid: usize, index: usize) -> Option<String> {
+        if index >= self.node_sub_num(oid) {
+            return None;
+        }
+        let parent = self.parents.get_or_init(|| self._parents());
+        let mut steps = vec![(oid, index)];
+        let mut node = oid;
+        while node != 1 {
+            let (up, i) = parent[node];
+            if up == 0 || steps.len() > self.nnum {
+                return None;
+            }
+            steps.push((up, i));
+            node = up;
+        }
+
+        let mut pointer = String::new();
+        for (node, i) in steps.iter().rev() {
+            pointer.push('/');
+            if self.node_type(*node) == ST::SMDT_MAP {
+                let key = self.get_sub_key(*node, *i)?;
+                pointer.push_str(&sd_path::escape_pointer_token(&key));
+            } else {
+                pointer.push_str(&i.to_string());
+            }
+        }
+        return Some(pointer);
+    }
+
+    fn _parents(&self) -> Vec<(usize, usize)> {
+        let mut parent = vec![(0, 0); self.nnum + 1];
+        for up in 1..self.nnum + 1 {
+            for i in 0..self.node_sub_num(up) {
+                match self.get_node_id_by_voff(self.get_sub_voff(up, i)) {
+                    Some(child) if child > 1 && child <= self.nnum && parent[child].0 == 0 => {
+                        parent[child] = (up, i);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        return parent;
+    }
+
+    // key bytes of a map entry, empty when they run past the buffer
+    #[inline]
+    fn _entry_key(&self, p_off: usize, index: usize) -> &'a [u8] {
+        let key_off = self.get_int(p_off + self.oz + 2 * self.oz * index);
+        let kw = self.get_int(key_off);
+        if kw == 0 {
+            return &[];
+        }
+        let start = key_off + self.oz;
+        return self.u8a.get(start..start + kw - 1).unwrap_or(&[]);
+    }
+
+    #[inline]
+    fn _try_entry_key(&self, p_off: usize, index: usize) -> Result<&'a str, SmDtonError> {
+        let p_i_off = p_off + self.oz + 2 * self.oz * index;
+        self._try_slice(p_i_off, 2 * self.oz)?;
+        return self._try_key(self.get_int(p_i_off));
+    }
+
+    // value offset of the last entry with this key, entries ordered by key bytes
+    fn _find_sorted(&self, p_off: usize, sub_num: usize, kbs: &[u8]) -> Option<usize> {
+        let mut lo = 0;
+        let mut hi = sub_num;
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self._entry_key(p_off, mid) <= kbs {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        if lo == 0 || self._entry_key(p_off, lo - 1) != kbs {
+            return None;
+        }
+        return Some(self.get_int(p_off + 2 * self.oz * lo));
+    }
+
+    // later entries overwrite earlier ones, as the reverse scan finds them first
+    fn _index_node(&self, p_off: usize, sub_num: usize) -> HashMap<&'a [u8], usize> {
+        let mut table = HashMap::with_capacity(sub_num);
+        for i in 0..sub_num {
+            let value_off = self.get_int(p_off + 2 * self.oz * (i + 1));
+            table.insert(self._entry_key(p_off, i), value_off);
+        }
+        return table;
+    }
+
+    // entry count of a node, zero when its pieces run past the buffer
+    #[inline]
+    fn _sub_num(&self, n_off: usize, p_off: usize) -> usize {
+        let sub_num = self.get_int(p_off);
+        let mut width = self.oz;
+        if self.u8a.get(n_off) == Some(&ST::SMDT_MAP) {
+            width *= 2;
+        }
+        if width == 0 || p_off + self.oz > self.u8a.len() {
+            return 0;
+        }
+        if sub_num > (self.u8a.len() - p_off - self.oz) / width {
+            return 0;
+        }
+        return sub_num;
+    }
+
+    #[inline]
+    fn _try_slice(&self, off: usize, len: usize) -> Result<&'a [u8], SmDtonError> {
+        match self.u8a.get(off..off.saturating_add(len)) {
+            Some(piece) => return Ok(piece),
+            None => return Err(SmDtonError::Truncated { offset: off }),
+        }
+    }
+
+    #[inline]
+    fn _try_type(&self, off: usize, smdt: u8) -> Result<(), SmDtonError> {
+        let found = self._try_slice(off, 1)?[0];
+        if found != smdt {
+            return Err(SmDtonError::TypeMismatch {
+                expected: smdt,
+                found: found,
+            });
+        }
+        return Ok(());
+    }
+
+    #[inline]
+    fn _try_key(&self, key_off: usize) -> Result<&'a str, SmDtonError> {
+        self._try_slice(key_off, self.oz)?;
+        let kw = self.get_int(key_off);
+        if kw == 0 {
+            return Err(SmDtonError::InvalidOffset { offset: key_off });
+        }
+        let piece = self._try_slice(key_off + self.oz, kw - 1)?;
+        match std::str::from_utf8(piece) {
+            Ok(txt) => return Ok(txt),
+            Err(_) => {
+                return Err(SmDtonError::InvalidUtf8 {
+                    offset: key_off + self.oz,
+                })
+            }
+        }
+    }
+
     // for outside
 
     #[allow(dead_code)]
     pub fn new(u8a: &'a [u8]) -> Self {
-        let oz = u8a[1] as usize;
+        let oz = match u8a.get(1) {
+            Some(b) => *b as usize,
+            None => 0,
+        };
+        let node_off = 3 + 3 * oz;
+
+        // a broken head leaves the reader without nodes
+        let head = u8a.first().copied().unwrap_or(0);
+        let mut nnum = su::get_int(u8a, 2, oz);
+        if head & !ST::SMTY_FLAG_SORTED != ST::SMTY_DTR
+            || nnum > u8a.len().saturating_sub(node_off) / (1 + oz)
+        {
+            nnum = 0;
+        }
 
         SmDtonReader {
             u8a: u8a,
             oz: oz,
-            nnum: su::get_int(u8a, 2, oz),
+            nnum: nnum,
 
-            node_off: 3 + 3 * oz,
+            node_off: node_off,
+            sorted: head & ST::SMTY_FLAG_SORTED != 0,
+            key_index: None,
+            parents: OnceLock::new(),
         }
     }
 
+    // look keys up through a hash table per map node, built on first use
     #[allow(dead_code)]
-    pub fn clone(&self) -> Self {
-        SmDtonReader {
-            u8a: self.u8a,
-            oz: self.oz,
-            nnum: self.nnum,
+    pub fn with_key_index(mut self) -> Self {
+        if !self.sorted {
+            let index = (0..self.nnum + 1).map(|_| OnceLock::new()).collect();
+            self.key_index = Some(Arc::new(index));
+        }
+        return self;
+    }
 
-            node_off: self.node_off,
+    pub fn try_new(u8a: &'a [u8]) -> Result<Self, SmDtonError> {
+        if u8a.len() < 2 {
+            return Err(SmDtonError::Truncated { offset: u8a.len() });
+        }
+        if u8a[0] & !ST::SMTY_FLAG_SORTED != ST::SMTY_DTR {
+            return Err(SmDtonError::InvalidHeader(u8a[0]));
         }
+        let oz = u8a[1] as usize;
+        if oz != 1 && oz != 2 && oz != 4 {
+            return Err(SmDtonError::InvalidOffsetSize(u8a[1]));
+        }
+
+        let reader = SmDtonReader::new(u8a);
+        reader.validate()?;
+        return Ok(reader);
+    }
+
+    pub fn validate(&self) -> Result<(), SmDtonError> {
+        let u8a = self.u8a;
+        let oz = self.oz;
+        if u8a.len() < 2 {
+            return Err(SmDtonError::Truncated { offset: u8a.len() });
+        }
+        if u8a[0] & !ST::SMTY_FLAG_SORTED != ST::SMTY_DTR {
+            return Err(SmDtonError::InvalidHeader(u8a[0]));
+        }
+        if oz != 1 && oz != 2 && oz != 4 {
+            return Err(SmDtonError::InvalidOffsetSize(u8a[1]));
+        }
+
+        // head
+        let mut off = 2 + 3 * oz;
+        self._check_sentinel(off)?;
+        let nnum = self.get_int(2);
+        let knum = self.get_int(2 + oz);
+        let vnum = self.get_int(2 + 2 * oz);
+        off += 1;
+
+        // node head
+        if nnum > (u8a.len() - off) / (1 + oz) {
+            return Err(SmDtonError::Truncated { offset: u8a.len() });
+        }
+        let mut p_off = off + nnum * (1 + oz);
+        for oid in 1..=nnum {
+            let node_type = u8a[off];
+            if node_type != ST::SMDT_MAP && node_type != ST::SMDT_ARR {
+                return Err(SmDtonError::InvalidNodeType {
+                    oid: oid,
+                    found: node_type,
+                });
+            }
+            if self.get_int(off + 1) != p_off {
+                return Err(SmDtonError::InvalidOffset { offset: off + 1 });
+            }
+            self._check_room(p_off, oz)?;
+            let sub_num = self.get_int(p_off);
+            let width = if node_type == ST::SMDT_MAP {
+                2 * oz
+            } else {
+                oz
+            };
+            if sub_num > (u8a.len() - p_off - oz) / width {
+                return Err(SmDtonError::Truncated { offset: u8a.len() });
+            }
+            p_off += oz + sub_num * width;
+            off += 1 + oz;
+        }
+        self._check_sentinel(p_off)?;
+        off = p_off + 1;
+
+        // key segment
+        if knum > (u8a.len() - off) / (oz + 1) {
+            return Err(SmDtonError::Truncated { offset: u8a.len() });
+        }
+        let mut key_offs: Vec<usize> = Vec::with_capacity(knum);
+        for _ in 0..knum {
+            self._check_room(off, oz)?;
+            let kw = self.get_int(off);
+            if kw == 0 {
+                return Err(SmDtonError::InvalidOffset { offset: off });
+            }
+            self._check_room(off + oz, kw)?;
+            self._check_text(off + oz, kw)?;
+            key_offs.push(off);
+            off += oz + kw;
+        }
+        self._check_sentinel(off)?;
+        off += 1;
+
+        // value segment
+        if vnum > u8a.len() - off {
+            return Err(SmDtonError::Truncated { offset: u8a.len() });
+        }
+        let mut value_offs: Vec<usize> = Vec::with_capacity(vnum);
+        for _ in 0..vnum {
+            let next = self._check_value(off, nnum)?;
+            value_offs.push(off);
+            off = next;
+        }
+        self._check_sentinel(off)?;
+        off += 1;
+        if off != u8a.len() {
+            return Err(SmDtonError::TrailingBytes { offset: off });
+        }
+
+        // node pieces must point at segment entries
+        let mut parent = vec![0; nnum + 1];
+        for oid in 1..=nnum {
+            smd_handle_node!(self, oid, n_off, p_off, sub_num);
+            let is_map = u8a[n_off] == ST::SMDT_MAP;
+            let mut i_off = p_off + oz;
+            let mut last_key = "";
+            for _ in 0..sub_num {
+                if is_map {
+                    let key_off = self.get_int(i_off);
+                    if key_offs.binary_search(&key_off).is_err() {
+                        return Err(SmDtonError::InvalidOffset { offset: i_off });
+                    }
+                    if self.sorted {
+                        let key = self._try_key(key_off)?;
+                        if key < last_key {
+                            return Err(SmDtonError::UnsortedKeys(oid));
+                        }
+                        last_key = key;
+                    }
+                    i_off += oz;
+                }
+                let voff = self.get_int(i_off);
+                if value_offs.binary_search(&voff).is_err() {
+                    return Err(SmDtonError::InvalidOffset { offset: i_off });
+                }
+                // tombstones only stand for keys
+                if !is_map && u8a[voff] == ST::SMDT_DEL {
+                    return Err(SmDtonError::InvalidValueType {
+                        offset: voff,
+                        found: ST::SMDT_DEL,
+                    });
+                }
+                i_off += oz;
+
+                // every node hangs off exactly one parent, the root off none
+                let smdt = u8a[voff];
+                if smdt == ST::SMDT_MAP || smdt == ST::SMDT_ARR {
+                    let child = self.get_int(voff + 1);
+                    if child == 1 || parent[child] != 0 {
+                        return Err(SmDtonError::RepeatedNode(child));
+                    }
+                    parent[child] = oid;
+                }
+            }
+        }
+
+        // no cycles, bounded depth
+        let mut depth = vec![0; nnum + 1];
+        let mut chain: Vec<usize> = Vec::new();
+        for oid in 1..=nnum {
+            let mut cur = oid;
+            while cur != 0 && depth[cur] == 0 {
+                if chain.len() > nnum {
+                    return Err(SmDtonError::RepeatedNode(oid));
+                }
+                chain.push(cur);
+                cur = parent[cur];
+            }
+            let mut d = if cur == 0 { 0 } else { depth[cur] };
+            while let Some(node) = chain.pop() {
+                d += 1;
+                if d > MAX_DEPTH + 1 {
+                    return Err(SmDtonError::TooDeep { depth: d - 1 });
+                }
+                depth[node] = d;
+            }
+        }
+
+        return Ok(());
+    }
+
+    #[inline]
+    fn _check_room(&self, off: usize, len: usize) -> Result<(), SmDtonError> {
+        if off > self.u8a.len() || len > self.u8a.len() - off {
+            return Err(SmDtonError::Truncated { offset: off });
+        }
+        return Ok(());
+    }
+
+    #[inline]
+    fn _check_sentinel(&self, off: usize) -> Result<(), SmDtonError> {
+        match self.u8a.get(off) {
+            Some(0x77) => return Ok(()),
+            Some(_) => return Err(SmDtonError::InvalidSentinel { offset: off }),
+            None => return Err(SmDtonError::Truncated { offset: off }),
+        }
+    }
+
+    // zero terminated utf-8 text of tw bytes (terminator included)
+    #[inline]
+    fn _check_text(&self, off: usize, tw: usize) -> Result<(), SmDtonError> {
+        if self.u8a[off + tw - 1] != 0 {
+            return Err(SmDtonError::InvalidOffset {
+                offset: off + tw - 1,
+            });
+        }
+        if std::str::from_utf8(&self.u8a[off..off + tw - 1]).is_err() {
+            return Err(SmDtonError::InvalidUtf8 { offset: off });
+        }
+        return Ok(());
+    }
+
+    fn _check_value(&self, off: usize, nnum: usize) -> Result<usize, SmDtonError> {
+        let oz = self.oz;
+        self._check_room(off, 1)?;
+        let smdt = self.u8a[off];
+        let body = off + 1;
+        let size = match smdt {
+            ST::SMDT_NUL | ST::SMDT_DEL => 0,
+            ST::SMDT_BOO | ST::SMDT_UI8 => 1,
+            ST::SMDT_I16 | ST::SMDT_U16 => 2,
+            ST::SMDT_I32 | ST::SMDT_U32 | ST::SMDT_F32 => 4,
+            ST::SMDT_I64 | ST::SMDT_U64 | ST::SMDT_F64 => 8,
+            ST::SMDT_STR => {
+                self._check_room(body, oz)?;
+                let tw = self.get_int(body);
+                if tw == 0 {
+                    return Err(SmDtonError::InvalidOffset { offset: body });
+                }
+                self._check_room(body + oz, tw)?;
+                self._check_text(body + oz, tw)?;
+                oz + tw
+            }
+            ST::SMDT_BIN => {
+                self._check_room(body, oz)?;
+                oz + self.get_int(body)
+            }
+            ST::SMDT_MAP | ST::SMDT_ARR => {
+                self._check_room(body, oz)?;
+                let oid = self.get_int(body);
+                if oid == 0 || oid > nnum {
+                    return Err(SmDtonError::InvalidNodeId(oid));
+                }
+                if self.node_type(oid) != smdt {
+                    return Err(SmDtonError::InvalidNodeType {
+                        oid: oid,
+                        found: smdt,
+                    });
+                }
+                oz
+            }
+            _ => {
+                return Err(SmDtonError::InvalidValueType {
+                    offset: off,
+                    found: smdt,
+                });
+            }
+        };
+        self._check_room(body, size)?;
+        return Ok(body + size);
     }
 
     #[allow(dead_code)]
     pub fn node_type(&self, oid: usize) -> u8 {
         smd_check_node_id!(self, oid, 0);
         let n_off = self.node_off + (oid - 1) * (1 + self.oz);
-        let node_type = self.u8a[n_off];
-        return node_type;
+        match self.u8a.get(n_off) {
+            Some(node_type) => return *node_type,
+            None => return 0,
+        }
     }
 
     #[allow(dead_code)]
@@ -200,204 +901,353 @@ impl<'a> SmDtonReader<'a> {
 
     #[allow(dead_code)]
     pub fn get_sub_key(&self, oid: usize, index: usize) -> Option<String> {
-        smd_check_node_id!(self, oid, None);
+        return self.try_get_sub_key(oid, index).unwrap_or(None);
+    }
+
+    #[allow(dead_code)]
+    pub fn try_get_sub_key(&self, oid: usize, index: usize) -> Result<Option<String>, SmDtonError> {
+        smd_try_node_id!(self, oid);
         smd_handle_node!(self, oid, n_off, p_off, sub_num);
-        if self.u8a[n_off] != ST::SMDT_MAP || index >= sub_num {
-            return None;
+        self._try_type(n_off, ST::SMDT_MAP)?;
+        if index >= sub_num {
+            return Ok(None);
+        }
+
+        smd_get_key!(self, p_off, index, key);
+        return Ok(Some(key.to_string()));
+    }
+
+    #[allow(dead_code)]
+    pub fn try_get_sub_entry(
+        &self,
+        oid: usize,
+        index: usize,
+    ) -> Result<Option<(&'a str, usize)>, SmDtonError> {
+        smd_try_node_id!(self, oid);
+        smd_handle_node!(self, oid, n_off, p_off, sub_num);
+        self._try_type(n_off, ST::SMDT_MAP)?;
+        if index >= sub_num {
+            return Ok(None);
         }
 
+        self._try_slice(p_off + (1 + 2 * index) * self.oz, 2 * self.oz)?;
         smd_get_key!(self, p_off, index, key);
-        return Some(key);
+        let value_off = self.get_int(p_off + self.oz * 2 * (1 + index));
+        return Ok(Some((key, value_off)));
     }
 
     #[allow(dead_code)]
     pub fn get_sub_map(&self, oid: usize) -> HashMap<String, usize> {
+        return self.try_get_sub_map(oid).unwrap_or_default();
+    }
+
+    #[allow(dead_code)]
+    pub fn try_get_sub_map(&self, oid: usize) -> Result<HashMap<String, usize>, SmDtonError> {
         let mut mp: HashMap<String, usize> = HashMap::default();
-        smd_check_node_id!(self, oid, mp);
+        smd_try_node_id!(self, oid);
         smd_handle_node!(self, oid, n_off, p_off, sub_num);
-        if self.u8a[n_off] != ST::SMDT_MAP {
-            return mp;
-        }
+        self._try_type(n_off, ST::SMDT_MAP)?;
 
         for i in 0..sub_num {
             let off = p_off + (1 + 2 * i) * self.oz;
-            let k_off = self.get_int(off);
+            self._try_slice(off, 2 * self.oz)?;
+            let txt = self._try_key(self.get_int(off))?;
             let v_off = self.get_int(off + self.oz);
-            let kw = self.get_int(k_off);
-
-            let piece = &self.u8a[k_off + self.oz..k_off + self.oz + kw - 1];
-            let txt = std::str::from_utf8(piece).unwrap();
 
             mp.insert(txt.to_string(), v_off);
         }
 
-        return mp;
+        return Ok(mp);
     }
 
+    // key and value of each map entry, in stored order
+    #[allow(dead_code)]
+    pub fn iter_entries(&self, oid: usize) -> SmDtonEntries<'a> {
+        let mut len = 0;
+        if self.node_type(oid) == ST::SMDT_MAP {
+            len = self.node_sub_num(oid);
+        }
+        return SmDtonEntries::new(self.clone(), oid, len);
+    }
+
+    // values of an array, or of a map without its keys
+    #[allow(dead_code)]
+    pub fn iter_items(&self, oid: usize) -> SmDtonItems<'a> {
+        let len = self.node_sub_num(oid);
+        return SmDtonItems::new(self.clone(), oid, len);
+    }
+
+    #[cfg(feature = "json")]
     #[allow(dead_code)]
     pub fn to_json(&self, oid: usize) -> Option<JsonValue> {
-        smd_check_node_id!(self, oid, None);
+        return self.try_to_json_value(oid).ok();
+    }
+
+    #[cfg(feature = "json")]
+    #[allow(dead_code)]
+    pub fn try_to_json(&self, oid: usize) -> Result<JsonValue, SmDtonError> {
+        return self.try_to_json_value(oid);
+    }
+
+    // any json backend, e.g. r.to_json_value::<serde_json::Value>(1)
+    #[allow(dead_code)]
+    pub fn to_json_value<J: SmDtonJsonValue>(&self, oid: usize) -> Option<J> {
+        return self.try_to_json_value(oid).ok();
+    }
+
+    #[allow(dead_code)]
+    pub fn try_to_json_value<J: SmDtonJsonValue>(&self, oid: usize) -> Result<J, SmDtonError> {
+        let mut seen = vec![false; self.nnum + 1];
+        return self._try_to_json(oid, &mut seen, 0);
+    }
+
+    fn _try_to_json<J: SmDtonJsonValue>(
+        &self,
+        oid: usize,
+        seen: &mut Vec<bool>,
+        depth: usize,
+    ) -> Result<J, SmDtonError> {
+        smd_try_node_id!(self, oid);
+        if depth > MAX_DEPTH {
+            return Err(SmDtonError::TooDeep { depth: depth });
+        }
+        if seen[oid] {
+            return Err(SmDtonError::RepeatedNode(oid));
+        }
+        seen[oid] = true;
         smd_handle_node!(self, oid, n_off, p_off, sub_num);
 
-        match self.u8a[n_off] {
+        let node_type = self._try_slice(n_off, 1)?[0];
+        match node_type {
             ST::SMDT_MAP => {
-                let mut obj = JsonValue::new_object();
+                let mut obj = J::json_object();
                 for index in 0..sub_num {
                     smd_get_key!(self, p_off, index, key);
                     let voff = self.get_int(p_off + self.oz * 2 * (1 + index));
 
-                    match self.u8a[voff] {
+                    match self.try_get_type_by_voff(voff)? {
                         ST::SMDT_I16 => {
-                            smd_add_number!(self, 2, voff, i16, obj, key);
+                            smd_add_number!(self, 2, voff, i16, J::json_i64, obj, key);
                         }
                         ST::SMDT_U16 => {
-                            smd_add_number!(self, 2, voff, u16, obj, key);
+                            smd_add_number!(self, 2, voff, u16, J::json_u64, obj, key);
                         }
                         ST::SMDT_I32 => {
-                            smd_add_number!(self, 4, voff, i32, obj, key);
+                            smd_add_number!(self, 4, voff, i32, J::json_i64, obj, key);
                         }
                         ST::SMDT_U32 => {
-                            smd_add_number!(self, 4, voff, u32, obj, key);
+                            smd_add_number!(self, 4, voff, u32, J::json_u64, obj, key);
                         }
                         ST::SMDT_F32 => {
-                            smd_add_number!(self, 4, voff, f32, obj, key);
+                            smd_add_number!(self, 4, voff, f32, J::json_f64, obj, key);
                         }
                         ST::SMDT_I64 => {
-                            smd_add_number!(self, 8, voff, i64, obj, key);
+                            smd_add_number!(self, 8, voff, i64, J::json_i64, obj, key);
                         }
                         ST::SMDT_U64 => {
-                            smd_add_number!(self, 8, voff, u64, obj, key);
+                            smd_add_number!(self, 8, voff, u64, J::json_u64, obj, key);
                         }
                         ST::SMDT_F64 => {
-                            smd_add_number!(self, 8, voff, f64, obj, key);
+                            smd_add_number!(self, 8, voff, f64, J::json_f64, obj, key);
                         }
                         ST::SMDT_BIN => {
-                            let bytes = self.get_bin_by_voff(voff).unwrap();
-                            let data = general_purpose::STANDARD.encode(&bytes);
-                            obj[key] = JsonValue::from("$B64$".to_string() + &data);
+                            let bytes = self.try_get_bin_by_voff(voff)?;
+                            let data = general_purpose::STANDARD.encode(bytes);
+                            obj.json_insert(key, J::json_str(&("$B64$".to_string() + &data)));
+                        }
+                        ST::SMDT_NUL => {
+                            obj.json_insert(key, J::json_null());
                         }
                         ST::SMDT_BOO => {
-                            let data = self.u8a[voff + 1] == 1;
-                            obj[key] = JsonValue::from(data);
+                            let data = self.try_get_bool_by_voff(voff)?;
+                            obj.json_insert(key, J::json_bool(data));
                         }
                         ST::SMDT_UI8 => {
-                            obj[key] = JsonValue::from(self.u8a[voff + 1]);
+                            obj.json_insert(
+                                key,
+                                J::json_u64(self.try_get_u8_by_voff(voff)?.into()),
+                            );
                         }
                         ST::SMDT_STR => {
-                            let data = self.get_string_by_voff(voff).unwrap();
-                            obj[key] = JsonValue::from(data);
+                            let data = self.try_get_string_by_voff(voff)?;
+                            obj.json_insert(key, J::json_str(data));
                         }
                         ST::SMDT_MAP | ST::SMDT_ARR => {
-                            let next_oid = self.get_int(voff + 1);
-                            let data = self.to_json(next_oid).unwrap();
-                            obj[key] = data;
+                            let next_oid = self.try_get_node_id_by_voff(voff)?;
+                            let data = self._try_to_json(next_oid, seen, depth + 1)?;
+                            obj.json_insert(key, data);
                         }
                         _ => {}
                     }
                 }
-                return Some(obj);
+                return Ok(obj);
             }
             ST::SMDT_ARR => {
-                let mut obj = JsonValue::new_array();
+                let mut obj = J::json_array();
 
                 for index in 0..sub_num {
                     let voff = self.get_int(p_off + (index + 1) * self.oz);
 
-                    match self.u8a[voff] {
+                    match self.try_get_type_by_voff(voff)? {
                         ST::SMDT_I16 => {
-                            smd_push_number!(self, 2, voff, i16, obj);
+                            smd_push_number!(self, 2, voff, i16, J::json_i64, obj);
                         }
                         ST::SMDT_U16 => {
-                            smd_push_number!(self, 2, voff, u16, obj);
+                            smd_push_number!(self, 2, voff, u16, J::json_u64, obj);
                         }
                         ST::SMDT_I32 => {
-                            smd_push_number!(self, 4, voff, i32, obj);
+                            smd_push_number!(self, 4, voff, i32, J::json_i64, obj);
                         }
                         ST::SMDT_U32 => {
-                            smd_push_number!(self, 4, voff, u32, obj);
+                            smd_push_number!(self, 4, voff, u32, J::json_u64, obj);
                         }
                         ST::SMDT_F32 => {
-                            smd_push_number!(self, 4, voff, f32, obj);
+                            smd_push_number!(self, 4, voff, f32, J::json_f64, obj);
                         }
                         ST::SMDT_I64 => {
-                            smd_push_number!(self, 8, voff, i64, obj);
+                            smd_push_number!(self, 8, voff, i64, J::json_i64, obj);
                         }
                         ST::SMDT_U64 => {
-                            smd_push_number!(self, 8, voff, u64, obj);
+                            smd_push_number!(self, 8, voff, u64, J::json_u64, obj);
                         }
                         ST::SMDT_F64 => {
-                            smd_push_number!(self, 8, voff, f64, obj);
+                            smd_push_number!(self, 8, voff, f64, J::json_f64, obj);
+                        }
+                        ST::SMDT_NUL => {
+                            obj.json_push(J::json_null());
                         }
                         ST::SMDT_BOO => {
-                            let data = self.u8a[voff + 1] == 1;
-                            obj.push(data).unwrap();
+                            let data = self.try_get_bool_by_voff(voff)?;
+                            obj.json_push(J::json_bool(data));
+                        }
+                        ST::SMDT_BIN => {
+                            let bytes = self.try_get_bin_by_voff(voff)?;
+                            let data = general_purpose::STANDARD.encode(bytes);
+                            obj.json_push(J::json_str(&("$B64$".to_string() + &data)));
                         }
                         ST::SMDT_UI8 => {
-                            obj.push(self.u8a[voff + 1]).unwrap();
+                            obj.json_push(J::json_u64(self.try_get_u8_by_voff(voff)?.into()));
                         }
                         ST::SMDT_STR => {
-                            let data = self.get_string_by_voff(voff).unwrap();
-                            obj.push(data).unwrap();
+                            let data = self.try_get_string_by_voff(voff)?;
+                            obj.json_push(J::json_str(data));
                         }
                         ST::SMDT_MAP | ST::SMDT_ARR => {
-                            let next_oid = self.get_int(voff + 1);
-                            let data = self.to_json(next_oid).unwrap();
-                            obj.push(data).unwrap();
+                            let next_oid = self.try_get_node_id_by_voff(voff)?;
+                            let data = self._try_to_json(next_oid, seen, depth + 1)?;
+                            obj.json_push(data);
                         }
+                        // a tombstone in an array is invalid, it is skipped as unknown types are
                         _ => {}
                     }
                 }
-                return Some(obj);
+                return Ok(obj);
             }
             _ => {}
         }
 
-        return None;
+        return Err(SmDtonError::InvalidNodeType {
+            oid: oid,
+            found: node_type,
+        });
     }
 
     // get value from value offset
 
+    #[allow(dead_code)]
+    pub fn get_value_by_voff(&self, value_off: usize) -> Option<SmDtonValue<'a>> {
+        return self.try_get_value_by_voff(value_off).ok();
+    }
+
+    #[allow(dead_code)]
+    pub fn try_get_value_by_voff(&self, value_off: usize) -> Result<SmDtonValue<'a>, SmDtonError> {
+        let found = self.try_get_type_by_voff(value_off)?;
+        let value = match found {
+            ST::SMDT_NUL => SmDtonValue::Null,
+            ST::SMDT_DEL => SmDtonValue::Deleted,
+            ST::SMDT_BOO => SmDtonValue::Bool(self.try_get_bool_by_voff(value_off)?),
+            ST::SMDT_UI8 => SmDtonValue::U8(self.try_get_u8_by_voff(value_off)?),
+            ST::SMDT_I16 => SmDtonValue::I16(self.try_get_i16_by_voff(value_off)?),
+            ST::SMDT_U16 => SmDtonValue::U16(self.try_get_u16_by_voff(value_off)?),
+            ST::SMDT_I32 => SmDtonValue::I32(self.try_get_i32_by_voff(value_off)?),
+            ST::SMDT_U32 => SmDtonValue::U32(self.try_get_u32_by_voff(value_off)?),
+            ST::SMDT_F32 => SmDtonValue::F32(self.try_get_f32_by_voff(value_off)?),
+            ST::SMDT_I64 => SmDtonValue::I64(self.try_get_i64_by_voff(value_off)?),
+            ST::SMDT_U64 => SmDtonValue::U64(self.try_get_u64_by_voff(value_off)?),
+            ST::SMDT_F64 => SmDtonValue::F64(self.try_get_f64_by_voff(value_off)?),
+            ST::SMDT_STR => SmDtonValue::Str(self.try_get_string_by_voff(value_off)?),
+            ST::SMDT_BIN => SmDtonValue::Bin(self.try_get_bin_by_voff(value_off)?),
+            ST::SMDT_MAP => SmDtonValue::Map(SmDtonNodeRef {
+                oid: self.try_get_node_id_by_voff(value_off)?,
+            }),
+            ST::SMDT_ARR => SmDtonValue::Arr(SmDtonNodeRef {
+                oid: self.try_get_node_id_by_voff(value_off)?,
+            }),
+            _ => {
+                return Err(SmDtonError::InvalidValueType {
+                    offset: value_off,
+                    found: found,
+                });
+            }
+        };
+        return Ok(value);
+    }
+
     #[inline]
     pub fn get_type_by_voff(&self, value_off: usize) -> Option<u8> {
-        return Some(self.u8a[value_off]);
+        return self.u8a.get(value_off).copied();
+    }
+
+    #[inline]
+    pub fn get_null_by_voff(&self, value_off: usize) -> Option<()> {
+        smd_check_type!(value_off, self, ST::SMDT_NUL);
+        return Some(());
+    }
+
+    #[inline]
+    pub fn is_null_by_voff(&self, value_off: usize) -> bool {
+        return self.get_null_by_voff(value_off).is_some();
     }
 
     #[inline]
     pub fn get_bool_by_voff(&self, value_off: usize) -> Option<bool> {
         smd_check_type!(value_off, self, ST::SMDT_BOO);
-        return Some(self.u8a[value_off + 1] == 1);
+        return Some(*self.u8a.get(value_off + 1)? == 1);
     }
 
     #[inline]
     pub fn get_u8_by_voff(&self, value_off: usize) -> Option<u8> {
         smd_check_type!(value_off, self, ST::SMDT_UI8);
-        return Some(self.u8a[value_off + 1]);
+        return self.u8a.get(value_off + 1).copied();
     }
 
     #[inline]
-    pub fn get_string_by_voff(&self, value_off: usize) -> Option<&str> {
+    pub fn get_string_by_voff(&self, value_off: usize) -> Option<&'a str> {
         smd_check_type!(value_off, self, ST::SMDT_STR);
         let tw = self.get_int(value_off + 1);
-        let piece = &self.u8a[value_off + 1 + self.oz..value_off + self.oz + tw];
+        let piece = self
+            .u8a
+            .get(value_off + 1 + self.oz..value_off + self.oz + tw)?;
         let rtxt = std::str::from_utf8(piece);
-        match rtxt {
-            Ok(txt) => return Some(txt),
-            _ => {}
+        if let Ok(txt) = rtxt {
+            return Some(txt);
         }
         return None;
     }
 
     #[allow(dead_code)]
-    pub fn get_bin_by_voff(&self, value_off: usize) -> Option<&[u8]> {
+    pub fn get_bin_by_voff(&self, value_off: usize) -> Option<&'a [u8]> {
         smd_check_type!(value_off, self, ST::SMDT_BIN);
         let len = self.get_int(value_off + 1);
-        let piece = &self.u8a[value_off + 1 + self.oz..value_off + 1 + self.oz + len];
+        let piece = self
+            .u8a
+            .get(value_off + 1 + self.oz..value_off + 1 + self.oz + len)?;
         return Some(piece);
     }
 
     #[allow(dead_code)]
     pub fn get_node_id_by_voff(&self, value_off: usize) -> Option<usize> {
-        let body_type = self.u8a[value_off];
+        let body_type = *self.u8a.get(value_off)?;
         if body_type != ST::SMDT_MAP && body_type != ST::SMDT_ARR {
             return None;
         }
@@ -406,6 +1256,173 @@ impl<'a> SmDtonReader<'a> {
         return Some(oid);
     }
 
+    #[inline]
+    pub fn try_get_type_by_voff(&self, value_off: usize) -> Result<u8, SmDtonError> {
+        return Ok(self._try_slice(value_off, 1)?[0]);
+    }
+
+    #[inline]
+    pub fn try_get_null_by_voff(&self, value_off: usize) -> Result<(), SmDtonError> {
+        return self._try_type(value_off, ST::SMDT_NUL);
+    }
+
+    #[inline]
+    pub fn try_get_bool_by_voff(&self, value_off: usize) -> Result<bool, SmDtonError> {
+        self._try_type(value_off, ST::SMDT_BOO)?;
+        return Ok(self._try_slice(value_off + 1, 1)?[0] == 1);
+    }
+
+    #[inline]
+    pub fn try_get_u8_by_voff(&self, value_off: usize) -> Result<u8, SmDtonError> {
+        self._try_type(value_off, ST::SMDT_UI8)?;
+        return Ok(self._try_slice(value_off + 1, 1)?[0]);
+    }
+
+    #[inline]
+    pub fn try_get_string_by_voff(&self, value_off: usize) -> Result<&'a str, SmDtonError> {
+        self._try_type(value_off, ST::SMDT_STR)?;
+        self._try_slice(value_off + 1, self.oz)?;
+        let tw = self.get_int(value_off + 1);
+        if tw == 0 {
+            return Err(SmDtonError::InvalidOffset {
+                offset: value_off + 1,
+            });
+        }
+        let piece = self._try_slice(value_off + 1 + self.oz, tw - 1)?;
+        match std::str::from_utf8(piece) {
+            Ok(txt) => return Ok(txt),
+            Err(_) => {
+                return Err(SmDtonError::InvalidUtf8 {
+                    offset: value_off + 1 + self.oz,
+                });
+            }
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn try_get_bin_by_voff(&self, value_off: usize) -> Result<&'a [u8], SmDtonError> {
+        self._try_type(value_off, ST::SMDT_BIN)?;
+        self._try_slice(value_off + 1, self.oz)?;
+        let len = self.get_int(value_off + 1);
+        return self._try_slice(value_off + 1 + self.oz, len);
+    }
+
+    #[allow(dead_code)]
+    pub fn try_get_node_id_by_voff(&self, value_off: usize) -> Result<usize, SmDtonError> {
+        let body_type = self._try_slice(value_off, 1)?[0];
+        if body_type != ST::SMDT_MAP && body_type != ST::SMDT_ARR {
+            return Err(SmDtonError::NotANode { found: body_type });
+        }
+        self._try_slice(value_off + 1, self.oz)?;
+        return Ok(self.get_int(value_off + 1));
+    }
+
+    def_func_try_get_by_voff!(try_get_i16_by_voff, 2, i16, ST::SMDT_I16);
+    def_func_try_get_by_voff!(try_get_u16_by_voff, 2, u16, ST::SMDT_U16);
+
+    def_func_try_get_by_voff!(try_get_i32_by_voff, 4, i32, ST::SMDT_I32);
+    def_func_try_get_by_voff!(try_get_u32_by_voff, 4, u32, ST::SMDT_U32);
+    def_func_try_get_by_voff!(try_get_f32_by_voff, 4, f32, ST::SMDT_F32);
+
+    def_func_try_get_by_voff!(try_get_f64_by_voff, 8, f64, ST::SMDT_F64);
+
+    // any integer type whose value fits
+    def_func_get_int_by_voff!(get_i64_by_voff, try_get_i64_by_voff, i64, ST::SMDT_I64);
+    def_func_get_int_by_voff!(get_u64_by_voff, try_get_u64_by_voff, u64, ST::SMDT_U64);
+
+    #[inline]
+    fn _try_int_by_voff(&self, value_off: usize, expected: u8) -> Result<(u8, i128), SmDtonError> {
+        let found = self.try_get_type_by_voff(value_off)?;
+        let v = match found {
+            ST::SMDT_UI8 => self.try_get_u8_by_voff(value_off)? as i128,
+            ST::SMDT_I16 => self.try_get_i16_by_voff(value_off)? as i128,
+            ST::SMDT_U16 => self.try_get_u16_by_voff(value_off)? as i128,
+            ST::SMDT_I32 => self.try_get_i32_by_voff(value_off)? as i128,
+            ST::SMDT_U32 => self.try_get_u32_by_voff(value_off)? as i128,
+            ST::SMDT_I64 => {
+                let bytes = self._try_slice(value_off + 1, 8)?;
+                i64::from_le_bytes(bytes.try_into().unwrap()) as i128
+            }
+            ST::SMDT_U64 => {
+                let bytes = self._try_slice(value_off + 1, 8)?;
+                u64::from_le_bytes(bytes.try_into().unwrap()) as i128
+            }
+            _ => {
+                return Err(SmDtonError::TypeMismatch {
+                    expected: expected,
+                    found: found,
+                });
+            }
+        };
+        return Ok((found, v));
+    }
+
+    // coerce any numeric type, failing when the value would change
+
+    #[allow(dead_code)]
+    pub fn as_i64_by_voff(&self, value_off: usize) -> Result<i64, SmDtonError> {
+        let (found, v) = self._try_coerce_int(value_off, ST::SMDT_I64)?;
+        match i64::try_from(v) {
+            Ok(d) => return Ok(d),
+            Err(_) => return Err(SmDtonError::OutOfRange { found: found }),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn as_u64_by_voff(&self, value_off: usize) -> Result<u64, SmDtonError> {
+        let (found, v) = self._try_coerce_int(value_off, ST::SMDT_U64)?;
+        match u64::try_from(v) {
+            Ok(d) => return Ok(d),
+            Err(_) => return Err(SmDtonError::OutOfRange { found: found }),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn as_f64_by_voff(&self, value_off: usize) -> Result<f64, SmDtonError> {
+        let found = self.try_get_type_by_voff(value_off)?;
+        match found {
+            ST::SMDT_F32 => return Ok(self.try_get_f32_by_voff(value_off)? as f64),
+            ST::SMDT_F64 => return self.try_get_f64_by_voff(value_off),
+            _ => {}
+        }
+        let (_, v) = self._try_int_by_voff(value_off, ST::SMDT_F64)?;
+        let d = v as f64;
+        if d as i128 != v {
+            return Err(SmDtonError::InexactNumber);
+        }
+        return Ok(d);
+    }
+
+    #[allow(dead_code)]
+    pub fn get_int_lossless_by_voff(&self, value_off: usize) -> Option<i128> {
+        match self._try_coerce_int(value_off, ST::SMDT_I64) {
+            Ok((_, v)) => return Some(v),
+            Err(_) => return None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn get_float_by_voff(&self, value_off: usize) -> Option<f64> {
+        return self.as_f64_by_voff(value_off).ok();
+    }
+
+    fn _try_coerce_int(&self, value_off: usize, expected: u8) -> Result<(u8, i128), SmDtonError> {
+        let found = self.try_get_type_by_voff(value_off)?;
+        let d = match found {
+            ST::SMDT_F32 => self.try_get_f32_by_voff(value_off)? as f64,
+            ST::SMDT_F64 => self.try_get_f64_by_voff(value_off)?,
+            _ => return self._try_int_by_voff(value_off, expected),
+        };
+        if !d.is_finite() || d.fract() != 0.0 {
+            return Err(SmDtonError::InexactNumber);
+        }
+        // 2^127, the first integral f64 beyond i128
+        if d.abs() >= 170141183460469231731687303715884105728.0 {
+            return Err(SmDtonError::OutOfRange { found: found });
+        }
+        return Ok((found, d as i128));
+    }
+
     def_func_get_by_voff!(get_i16_by_voff, 2, i16, ST::SMDT_I16);
     def_func_get_by_voff!(get_u16_by_voff, 2, u16, ST::SMDT_U16);
 
@@ -413,12 +1430,11 @@ impl<'a> SmDtonReader<'a> {
     def_func_get_by_voff!(get_u32_by_voff, 4, u32, ST::SMDT_U32);
     def_func_get_by_voff!(get_f32_by_voff, 4, f32, ST::SMDT_F32);
 
-    def_func_get_by_voff!(get_i64_by_voff, 8, i64, ST::SMDT_I64);
-    def_func_get_by_voff!(get_u64_by_voff, 8, u64, ST::SMDT_U64);
     def_func_get_by_voff!(get_f64_by_voff, 8, f64, ST::SMDT_F64);
 
     // get value from key
 
+    def_func_get_by_key!(get_null, get_null_by_voff, ());
     def_func_get_by_key!(get_bool, get_bool_by_voff, bool);
     def_func_get_by_key!(get_u8, get_u8_by_voff, u8);
 
@@ -433,12 +1449,157 @@ impl<'a> SmDtonReader<'a> {
     def_func_get_by_key!(get_u64, get_u64_by_voff, u64);
     def_func_get_by_key!(get_f64, get_f64_by_voff, f64);
 
-    def_func_get_by_key!(get_string, get_string_by_voff, &str);
-    def_func_get_by_key!(get_bin, get_bin_by_voff, &[u8]);
+    def_func_get_by_key!(get_int_lossless, get_int_lossless_by_voff, i128);
+    def_func_get_by_key!(get_float, get_float_by_voff, f64);
+
+    def_func_get_by_key!(get_string, get_string_by_voff, &'a str);
+    def_func_get_by_key!(get_bin, get_bin_by_voff, &'a [u8]);
     def_func_get_by_key!(get_node_id, get_node_id_by_voff, usize);
+    def_func_get_by_key!(get_value, get_value_by_voff, SmDtonValue<'a>);
+
+    def_func_try_get_by_key!(try_get_null, try_get_null_by_voff, ());
+    def_func_try_get_by_key!(try_get_bool, try_get_bool_by_voff, bool);
+    def_func_try_get_by_key!(try_get_u8, try_get_u8_by_voff, u8);
+
+    def_func_try_get_by_key!(try_get_i16, try_get_i16_by_voff, i16);
+    def_func_try_get_by_key!(try_get_u16, try_get_u16_by_voff, u16);
+
+    def_func_try_get_by_key!(try_get_i32, try_get_i32_by_voff, i32);
+    def_func_try_get_by_key!(try_get_u32, try_get_u32_by_voff, u32);
+    def_func_try_get_by_key!(try_get_f32, try_get_f32_by_voff, f32);
+
+    def_func_try_get_by_key!(try_get_i64, try_get_i64_by_voff, i64);
+    def_func_try_get_by_key!(try_get_u64, try_get_u64_by_voff, u64);
+    def_func_try_get_by_key!(try_get_f64, try_get_f64_by_voff, f64);
+
+    def_func_try_get_by_key!(as_i64, as_i64_by_voff, i64);
+    def_func_try_get_by_key!(as_u64, as_u64_by_voff, u64);
+    def_func_try_get_by_key!(as_f64, as_f64_by_voff, f64);
+
+    def_func_try_get_by_key!(try_get_string, try_get_string_by_voff, &'a str);
+    def_func_try_get_by_key!(try_get_bin, try_get_bin_by_voff, &'a [u8]);
+    def_func_try_get_by_key!(try_get_node_id, try_get_node_id_by_voff, usize);
+    def_func_try_get_by_key!(try_get_value, try_get_value_by_voff, SmDtonValue<'a>);
+
+    #[allow(dead_code)]
+    pub fn is_null(&self, oid: usize, key: &str) -> bool {
+        return self.get_null(oid, key).is_some();
+    }
+
+    #[allow(dead_code)]
+    pub fn is_null_by_id(&self, oid: usize, index: usize) -> bool {
+        return self.get_null_by_id(oid, index).is_some();
+    }
+
+    // get value from path
+
+    #[allow(dead_code)]
+    pub fn get_path(&self, oid: usize, segs: &[SmDtonPathSeg]) -> Option<SmDtonValue<'a>> {
+        let value_off = self.get_path_voff(oid, segs);
+        if value_off == 0 {
+            return None;
+        }
+        return self.get_value_by_voff(value_off);
+    }
+
+    #[allow(dead_code)]
+    pub fn try_get_path(
+        &self,
+        oid: usize,
+        segs: &[SmDtonPathSeg],
+    ) -> Result<Option<SmDtonValue<'a>>, SmDtonError> {
+        match self.try_get_path_voff(oid, segs)? {
+            Some(value_off) => return Ok(Some(self.try_get_value_by_voff(value_off)?)),
+            None => return Ok(None),
+        }
+    }
+
+    def_func_get_by_path!(get_null_path, get_null_by_voff, ());
+    def_func_get_by_path!(get_bool_path, get_bool_by_voff, bool);
+    def_func_get_by_path!(get_u8_path, get_u8_by_voff, u8);
+
+    def_func_get_by_path!(get_i16_path, get_i16_by_voff, i16);
+    def_func_get_by_path!(get_u16_path, get_u16_by_voff, u16);
+
+    def_func_get_by_path!(get_i32_path, get_i32_by_voff, i32);
+    def_func_get_by_path!(get_u32_path, get_u32_by_voff, u32);
+    def_func_get_by_path!(get_f32_path, get_f32_by_voff, f32);
+
+    def_func_get_by_path!(get_i64_path, get_i64_by_voff, i64);
+    def_func_get_by_path!(get_u64_path, get_u64_by_voff, u64);
+    def_func_get_by_path!(get_f64_path, get_f64_by_voff, f64);
+
+    def_func_get_by_path!(get_string_path, get_string_by_voff, &'a str);
+    def_func_get_by_path!(get_bin_path, get_bin_by_voff, &'a [u8]);
+    def_func_get_by_path!(get_node_id_path, get_node_id_by_voff, usize);
+    def_func_get_by_path!(get_value_path, get_value_by_voff, SmDtonValue<'a>);
+
+    def_func_try_get_by_path!(try_get_null_path, try_get_null_by_voff, ());
+    def_func_try_get_by_path!(try_get_bool_path, try_get_bool_by_voff, bool);
+    def_func_try_get_by_path!(try_get_u8_path, try_get_u8_by_voff, u8);
+
+    def_func_try_get_by_path!(try_get_i16_path, try_get_i16_by_voff, i16);
+    def_func_try_get_by_path!(try_get_u16_path, try_get_u16_by_voff, u16);
+
+    def_func_try_get_by_path!(try_get_i32_path, try_get_i32_by_voff, i32);
+    def_func_try_get_by_path!(try_get_u32_path, try_get_u32_by_voff, u32);
+    def_func_try_get_by_path!(try_get_f32_path, try_get_f32_by_voff, f32);
+
+    def_func_try_get_by_path!(try_get_i64_path, try_get_i64_by_voff, i64);
+    def_func_try_get_by_path!(try_get_u64_path, try_get_u64_by_voff, u64);
+    def_func_try_get_by_path!(try_get_f64_path, try_get_f64_by_voff, f64);
+
+    def_func_try_get_by_path!(try_get_string_path, try_get_string_by_voff, &'a str);
+    def_func_try_get_by_path!(try_get_bin_path, try_get_bin_by_voff, &'a [u8]);
+    def_func_try_get_by_path!(try_get_node_id_path, try_get_node_id_by_voff, usize);
+    def_func_try_get_by_path!(try_get_value_path, try_get_value_by_voff, SmDtonValue<'a>);
+
+    // get value from json pointer
+
+    def_func_get_by_pointer!(get_pointer, get_value_by_voff, SmDtonValue<'a>);
+    def_func_try_get_by_pointer!(try_get_pointer, try_get_value_by_voff, SmDtonValue<'a>);
+
+    def_func_get_by_pointer!(get_null_pointer, get_null_by_voff, ());
+    def_func_get_by_pointer!(get_bool_pointer, get_bool_by_voff, bool);
+    def_func_get_by_pointer!(get_u8_pointer, get_u8_by_voff, u8);
+
+    def_func_get_by_pointer!(get_i16_pointer, get_i16_by_voff, i16);
+    def_func_get_by_pointer!(get_u16_pointer, get_u16_by_voff, u16);
+
+    def_func_get_by_pointer!(get_i32_pointer, get_i32_by_voff, i32);
+    def_func_get_by_pointer!(get_u32_pointer, get_u32_by_voff, u32);
+    def_func_get_by_pointer!(get_f32_pointer, get_f32_by_voff, f32);
+
+    def_func_get_by_pointer!(get_i64_pointer, get_i64_by_voff, i64);
+    def_func_get_by_pointer!(get_u64_pointer, get_u64_by_voff, u64);
+    def_func_get_by_pointer!(get_f64_pointer, get_f64_by_voff, f64);
+
+    def_func_get_by_pointer!(get_string_pointer, get_string_by_voff, &'a str);
+    def_func_get_by_pointer!(get_bin_pointer, get_bin_by_voff, &'a [u8]);
+    def_func_get_by_pointer!(get_node_id_pointer, get_node_id_by_voff, usize);
+
+    def_func_try_get_by_pointer!(try_get_null_pointer, try_get_null_by_voff, ());
+    def_func_try_get_by_pointer!(try_get_bool_pointer, try_get_bool_by_voff, bool);
+    def_func_try_get_by_pointer!(try_get_u8_pointer, try_get_u8_by_voff, u8);
+
+    def_func_try_get_by_pointer!(try_get_i16_pointer, try_get_i16_by_voff, i16);
+    def_func_try_get_by_pointer!(try_get_u16_pointer, try_get_u16_by_voff, u16);
+
+    def_func_try_get_by_pointer!(try_get_i32_pointer, try_get_i32_by_voff, i32);
+    def_func_try_get_by_pointer!(try_get_u32_pointer, try_get_u32_by_voff, u32);
+    def_func_try_get_by_pointer!(try_get_f32_pointer, try_get_f32_by_voff, f32);
+
+    def_func_try_get_by_pointer!(try_get_i64_pointer, try_get_i64_by_voff, i64);
+    def_func_try_get_by_pointer!(try_get_u64_pointer, try_get_u64_by_voff, u64);
+    def_func_try_get_by_pointer!(try_get_f64_pointer, try_get_f64_by_voff, f64);
+
+    def_func_try_get_by_pointer!(try_get_string_pointer, try_get_string_by_voff, &'a str);
+    def_func_try_get_by_pointer!(try_get_bin_pointer, try_get_bin_by_voff, &'a [u8]);
+    def_func_try_get_by_pointer!(try_get_node_id_pointer, try_get_node_id_by_voff, usize);
 
     // get value from index
 
+    def_func_get_by_id!(get_null_by_id, get_null_by_voff, ());
     def_func_get_by_id!(get_bool_by_id, get_bool_by_voff, bool);
     def_func_get_by_id!(get_u8_by_id, get_u8_by_voff, u8);
 
@@ -453,7 +1614,50 @@ impl<'a> SmDtonReader<'a> {
     def_func_get_by_id!(get_u64_by_id, get_u64_by_voff, u64);
     def_func_get_by_id!(get_f64_by_id, get_f64_by_voff, f64);
 
-    def_func_get_by_id!(get_string_by_id, get_string_by_voff, &str);
-    def_func_get_by_id!(get_bin_by_id, get_bin_by_voff, &[u8]);
+    def_func_get_by_id!(get_int_lossless_by_id, get_int_lossless_by_voff, i128);
+    def_func_get_by_id!(get_float_by_id, get_float_by_voff, f64);
+
+    def_func_get_by_id!(get_string_by_id, get_string_by_voff, &'a str);
+    def_func_get_by_id!(get_bin_by_id, get_bin_by_voff, &'a [u8]);
     def_func_get_by_id!(get_node_id_by_id, get_node_id_by_voff, usize);
+    def_func_get_by_id!(get_value_by_id, get_value_by_voff, SmDtonValue<'a>);
+
+    def_func_try_get_by_id!(try_get_null_by_id, try_get_null_by_voff, ());
+    def_func_try_get_by_id!(try_get_bool_by_id, try_get_bool_by_voff, bool);
+    def_func_try_get_by_id!(try_get_u8_by_id, try_get_u8_by_voff, u8);
+
+    def_func_try_get_by_id!(try_get_i16_by_id, try_get_i16_by_voff, i16);
+    def_func_try_get_by_id!(try_get_u16_by_id, try_get_u16_by_voff, u16);
+
+    def_func_try_get_by_id!(try_get_i32_by_id, try_get_i32_by_voff, i32);
+    def_func_try_get_by_id!(try_get_u32_by_id, try_get_u32_by_voff, u32);
+    def_func_try_get_by_id!(try_get_f32_by_id, try_get_f32_by_voff, f32);
+
+    def_func_try_get_by_id!(try_get_i64_by_id, try_get_i64_by_voff, i64);
+    def_func_try_get_by_id!(try_get_u64_by_id, try_get_u64_by_voff, u64);
+    def_func_try_get_by_id!(try_get_f64_by_id, try_get_f64_by_voff, f64);
+
+    def_func_try_get_by_id!(as_i64_by_id, as_i64_by_voff, i64);
+    def_func_try_get_by_id!(as_u64_by_id, as_u64_by_voff, u64);
+    def_func_try_get_by_id!(as_f64_by_id, as_f64_by_voff, f64);
+
+    def_func_try_get_by_id!(try_get_string_by_id, try_get_string_by_voff, &'a str);
+    def_func_try_get_by_id!(try_get_bin_by_id, try_get_bin_by_voff, &'a [u8]);
+    def_func_try_get_by_id!(try_get_node_id_by_id, try_get_node_id_by_voff, usize);
+    def_func_try_get_by_id!(try_get_value_by_id, try_get_value_by_voff, SmDtonValue<'a>);
+}
+
+impl<'a> Clone for SmDtonReader<'a> {
+    fn clone(&self) -> Self {
+        SmDtonReader {
+            u8a: self.u8a,
+            oz: self.oz,
+            nnum: self.nnum,
+
+            node_off: self.node_off,
+            sorted: self.sorted,
+            key_index: self.key_index.clone(),
+            parents: self.parents.clone(),
+        }
+    }
 }
@@ -0,0 +1,573 @@
+// serde serializer straight into a SmDtonBuilder
+//
+// encoding:
+//   bool, u8, i16, u16, i32, u32, f32, i64, u64, f64 -> the matching ST tag
+//   i8 -> SMDT_I16, i128 / u128 -> SMDT_I64 or SMDT_U64 when they fit
+//   char, str -> SMDT_STR, bytes -> SMDT_BIN
+//   a Vec<u8> is a seq of SMDT_UI8 unless marked #[serde(with = "serde_bytes")]
+//   None, (), unit struct -> SMDT_NUL, Some(v) and newtype structs -> v
+//   seq, tuple, tuple struct -> array node, map and struct -> map node
+//   unit variant -> its name as SMDT_STR
+//   newtype, tuple and struct variants -> map node { name: value }
+//   map keys must be strings, chars or integers (written as text)
+//   the root must serialize to a map or an array node
+
+use serde::ser::{self, Serialize};
+use std::borrow::Cow;
+
+use crate::sd_data::{SmDtonData, ST};
+use crate::{SmDtonBuffer, SmDtonBuilder, SmDtonError, SmDtonOptions};
+
+impl ser::Error for SmDtonError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        return SmDtonError::Custom(msg.to_string());
+    }
+}
+
+pub fn to_buffer<T: Serialize + ?Sized>(value: &T) -> Result<SmDtonBuffer, SmDtonError> {
+    return to_buffer_with(value, SmDtonOptions::default());
+}
+
+pub fn to_buffer_with<T: Serialize + ?Sized>(
+    value: &T,
+    options: SmDtonOptions,
+) -> Result<SmDtonBuffer, SmDtonError> {
+    let mut builder = SmDtonBuilder::with_options(options);
+    value.serialize(SmDtonSerializer {
+        builder: &mut builder,
+        upoid: 0,
+        key: None,
+    })?;
+    return Ok(builder.build());
+}
+
+// writes one value into map upoid under key, or pushes it when key is None
+pub struct SmDtonSerializer<'s> {
+    builder: &'s mut SmDtonBuilder<'static>,
+    upoid: usize,
+    key: Option<Cow<'static, str>>,
+}
+
+impl<'s> SmDtonSerializer<'s> {
+    fn _put(self, da: SmDtonData<'static>) -> Result<(), SmDtonError> {
+        if self.upoid == 0 {
+            return Err(SmDtonError::NotANode { found: da.smdt });
+        }
+        return _attach(self.builder, self.upoid, self.key, da);
+    }
+
+    fn _node(self, smdt: u8) -> Result<SmDtonCompound<'s>, SmDtonError> {
+        let oid = self.builder.create_node(smdt);
+        if self.upoid > 0 {
+            let da = self.builder._node_data(oid);
+            _attach(&mut *self.builder, self.upoid, self.key, da)?;
+        }
+        return Ok(SmDtonCompound {
+            builder: self.builder,
+            oid: oid,
+            key: None,
+        });
+    }
+
+    // { variant: inner node }
+    fn _variant(self, variant: &'static str, smdt: u8) -> Result<SmDtonCompound<'s>, SmDtonError> {
+        let outer = self._node(ST::SMDT_MAP)?;
+        let inner = SmDtonSerializer {
+            builder: outer.builder,
+            upoid: outer.oid,
+            key: Some(Cow::Borrowed(variant)),
+        };
+        return inner._node(smdt);
+    }
+}
+
+fn _attach(
+    builder: &mut SmDtonBuilder<'static>,
+    upoid: usize,
+    key: Option<Cow<'static, str>>,
+    da: SmDtonData<'static>,
+) -> Result<(), SmDtonError> {
+    match key {
+        Some(key) => {
+            builder._check_node(upoid, ST::SMDT_MAP)?;
+            builder._do_add(upoid, key, da);
+        }
+        None => {
+            builder._check_node(upoid, ST::SMDT_ARR)?;
+            builder._do_push(upoid, da);
+        }
+    }
+    return Ok(());
+}
+
+impl<'s> ser::Serializer for SmDtonSerializer<'s> {
+    type Ok = ();
+    type Error = SmDtonError;
+
+    type SerializeSeq = SmDtonCompound<'s>;
+    type SerializeTuple = SmDtonCompound<'s>;
+    type SerializeTupleStruct = SmDtonCompound<'s>;
+    type SerializeTupleVariant = SmDtonCompound<'s>;
+    type SerializeMap = SmDtonCompound<'s>;
+    type SerializeStruct = SmDtonCompound<'s>;
+    type SerializeStructVariant = SmDtonCompound<'s>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), SmDtonError> {
+        return self._put(SmDtonData::new_bool(v));
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), SmDtonError> {
+        return self._put(SmDtonData::new_i16(v as i16));
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), SmDtonError> {
+        return self._put(SmDtonData::new_i16(v));
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), SmDtonError> {
+        return self._put(SmDtonData::new_i32(v));
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), SmDtonError> {
+        return self._put(SmDtonData::new_i64(v));
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), SmDtonError> {
+        if let Ok(n) = i64::try_from(v) {
+            return self._put(SmDtonData::new_i64(n));
+        }
+        if let Ok(n) = u64::try_from(v) {
+            return self._put(SmDtonData::new_u64(n));
+        }
+        return Err(SmDtonError::Custom(format!(
+            "{} does not fit in 64 bits",
+            v
+        )));
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), SmDtonError> {
+        return self._put(SmDtonData::new_u8(v));
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), SmDtonError> {
+        return self._put(SmDtonData::new_u16(v));
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), SmDtonError> {
+        return self._put(SmDtonData::new_u32(v));
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), SmDtonError> {
+        return self._put(SmDtonData::new_u64(v));
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), SmDtonError> {
+        match u64::try_from(v) {
+            Ok(n) => return self._put(SmDtonData::new_u64(n)),
+            Err(_) => {
+                return Err(SmDtonError::Custom(format!(
+                    "{} does not fit in 64 bits",
+                    v
+                )))
+            }
+        }
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), SmDtonError> {
+        return self._put(SmDtonData::new_f32(v));
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), SmDtonError> {
+        return self._put(SmDtonData::new_f64(v));
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), SmDtonError> {
+        return self._put(SmDtonData::new_string_owned(v.to_string()));
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), SmDtonError> {
+        return self._put(SmDtonData::new_string_owned(v.to_string()));
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), SmDtonError> {
+        return self._put(SmDtonData::new_bin_owned(v.to_vec()));
+    }
+
+    fn serialize_none(self) -> Result<(), SmDtonError> {
+        return self._put(SmDtonData::new_null());
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), SmDtonError> {
+        return value.serialize(self);
+    }
+
+    fn serialize_unit(self) -> Result<(), SmDtonError> {
+        return self._put(SmDtonData::new_null());
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), SmDtonError> {
+        return self._put(SmDtonData::new_null());
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), SmDtonError> {
+        return self._put(SmDtonData::new_string(variant));
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), SmDtonError> {
+        return value.serialize(self);
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), SmDtonError> {
+        let outer = self._node(ST::SMDT_MAP)?;
+        return value.serialize(SmDtonSerializer {
+            builder: outer.builder,
+            upoid: outer.oid,
+            key: Some(Cow::Borrowed(variant)),
+        });
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<SmDtonCompound<'s>, SmDtonError> {
+        return self._node(ST::SMDT_ARR);
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<SmDtonCompound<'s>, SmDtonError> {
+        return self._node(ST::SMDT_ARR);
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<SmDtonCompound<'s>, SmDtonError> {
+        return self._node(ST::SMDT_ARR);
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SmDtonCompound<'s>, SmDtonError> {
+        return self._variant(variant, ST::SMDT_ARR);
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SmDtonCompound<'s>, SmDtonError> {
+        return self._node(ST::SMDT_MAP);
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<SmDtonCompound<'s>, SmDtonError> {
+        return self._node(ST::SMDT_MAP);
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SmDtonCompound<'s>, SmDtonError> {
+        return self._variant(variant, ST::SMDT_MAP);
+    }
+}
+
+// an open map or array node
+pub struct SmDtonCompound<'s> {
+    builder: &'s mut SmDtonBuilder<'static>,
+    oid: usize,
+    key: Option<Cow<'static, str>>,
+}
+
+impl<'s> SmDtonCompound<'s> {
+    fn _element<T: Serialize + ?Sized>(
+        &mut self,
+        key: Option<Cow<'static, str>>,
+        value: &T,
+    ) -> Result<(), SmDtonError> {
+        return value.serialize(SmDtonSerializer {
+            builder: &mut *self.builder,
+            upoid: self.oid,
+            key: key,
+        });
+    }
+}
+
+impl<'s> ser::SerializeSeq for SmDtonCompound<'s> {
+    type Ok = ();
+    type Error = SmDtonError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SmDtonError> {
+        return self._element(None, value);
+    }
+
+    fn end(self) -> Result<(), SmDtonError> {
+        return Ok(());
+    }
+}
+
+impl<'s> ser::SerializeTuple for SmDtonCompound<'s> {
+    type Ok = ();
+    type Error = SmDtonError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SmDtonError> {
+        return self._element(None, value);
+    }
+
+    fn end(self) -> Result<(), SmDtonError> {
+        return Ok(());
+    }
+}
+
+impl<'s> ser::SerializeTupleStruct for SmDtonCompound<'s> {
+    type Ok = ();
+    type Error = SmDtonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SmDtonError> {
+        return self._element(None, value);
+    }
+
+    fn end(self) -> Result<(), SmDtonError> {
+        return Ok(());
+    }
+}
+
+impl<'s> ser::SerializeTupleVariant for SmDtonCompound<'s> {
+    type Ok = ();
+    type Error = SmDtonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SmDtonError> {
+        return self._element(None, value);
+    }
+
+    fn end(self) -> Result<(), SmDtonError> {
+        return Ok(());
+    }
+}
+
+impl<'s> ser::SerializeMap for SmDtonCompound<'s> {
+    type Ok = ();
+    type Error = SmDtonError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SmDtonError> {
+        self.key = Some(Cow::Owned(key.serialize(SmDtonKeySerializer)?));
+        return Ok(());
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SmDtonError> {
+        match self.key.take() {
+            Some(key) => return self._element(Some(key), value),
+            None => return Err(SmDtonError::Custom("map value without a key".to_string())),
+        }
+    }
+
+    fn end(self) -> Result<(), SmDtonError> {
+        return Ok(());
+    }
+}
+
+impl<'s> ser::SerializeStruct for SmDtonCompound<'s> {
+    type Ok = ();
+    type Error = SmDtonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SmDtonError> {
+        return self._element(Some(Cow::Borrowed(key)), value);
+    }
+
+    fn end(self) -> Result<(), SmDtonError> {
+        return Ok(());
+    }
+}
+
+impl<'s> ser::SerializeStructVariant for SmDtonCompound<'s> {
+    type Ok = ();
+    type Error = SmDtonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SmDtonError> {
+        return self._element(Some(Cow::Borrowed(key)), value);
+    }
+
+    fn end(self) -> Result<(), SmDtonError> {
+        return Ok(());
+    }
+}
+
+// map keys become text
+struct SmDtonKeySerializer;
+
+macro_rules! def_key_int {
+    ($func_name:ident, $dty: ty) => {
+        fn $func_name(self, v: $dty) -> Result<String, SmDtonError> {
+            return Ok(v.to_string());
+        }
+    };
+}
+
+impl ser::Serializer for SmDtonKeySerializer {
+    type Ok = String;
+    type Error = SmDtonError;
+
+    type SerializeSeq = ser::Impossible<String, SmDtonError>;
+    type SerializeTuple = ser::Impossible<String, SmDtonError>;
+    type SerializeTupleStruct = ser::Impossible<String, SmDtonError>;
+    type SerializeTupleVariant = ser::Impossible<String, SmDtonError>;
+    type SerializeMap = ser::Impossible<String, SmDtonError>;
+    type SerializeStruct = ser::Impossible<String, SmDtonError>;
+    type SerializeStructVariant = ser::Impossible<String, SmDtonError>;
+
+    def_key_int!(serialize_i8, i8);
+    def_key_int!(serialize_i16, i16);
+    def_key_int!(serialize_i32, i32);
+    def_key_int!(serialize_i64, i64);
+    def_key_int!(serialize_i128, i128);
+    def_key_int!(serialize_u8, u8);
+    def_key_int!(serialize_u16, u16);
+    def_key_int!(serialize_u32, u32);
+    def_key_int!(serialize_u64, u64);
+    def_key_int!(serialize_u128, u128);
+
+    fn serialize_char(self, v: char) -> Result<String, SmDtonError> {
+        return Ok(v.to_string());
+    }
+
+    fn serialize_str(self, v: &str) -> Result<String, SmDtonError> {
+        return Ok(v.to_string());
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<String, SmDtonError> {
+        return Ok(variant.to_string());
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String, SmDtonError> {
+        return value.serialize(self);
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<String, SmDtonError> {
+        return Err(_key_error());
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<String, SmDtonError> {
+        return Err(_key_error());
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<String, SmDtonError> {
+        return Err(_key_error());
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String, SmDtonError> {
+        return Err(_key_error());
+    }
+
+    fn serialize_none(self) -> Result<String, SmDtonError> {
+        return Err(_key_error());
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<String, SmDtonError> {
+        return Err(_key_error());
+    }
+
+    fn serialize_unit(self) -> Result<String, SmDtonError> {
+        return Err(_key_error());
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String, SmDtonError> {
+        return Err(_key_error());
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String, SmDtonError> {
+        return Err(_key_error());
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, SmDtonError> {
+        return Err(_key_error());
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, SmDtonError> {
+        return Err(_key_error());
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, SmDtonError> {
+        return Err(_key_error());
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, SmDtonError> {
+        return Err(_key_error());
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, SmDtonError> {
+        return Err(_key_error());
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, SmDtonError> {
+        return Err(_key_error());
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, SmDtonError> {
+        return Err(_key_error());
+    }
+}
+
+fn _key_error() -> SmDtonError {
+    return SmDtonError::Custom("map key must be a string".to_string());
+}
@@ -0,0 +1,522 @@
+// json text straight into a SmDtonBuilder, read in chunks with no value tree
+//
+// keys and strings are owned by the builder, so the text can be dropped as
+// it is consumed. parsing is strict, as with try_new_from_json: "$B64$"
+// strings must decode and exact_numbers is honoured. repeated keys are all
+// kept, lookups take the last one as with any buffer.
+
+use std::borrow::Cow;
+use std::io::{ErrorKind, Read};
+
+use super::sd_builder::SmDtonBuilder;
+use super::sd_data::{SmDtonData, ST};
+use super::sd_error::SmDtonError;
+use super::sd_option::SmDtonOptions;
+use super::sd_reader::MAX_DEPTH;
+use super::sd_text;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Clone, Copy)]
+struct SmDtonFrame {
+    oid: usize,
+    map: bool,
+    count: usize,
+}
+
+struct SmDtonJsonStream<R: Read> {
+    reader: R,
+    buf: Vec<u8>,
+    pos: usize,
+    len: usize,
+    // text offset of buf[0]
+    base: usize,
+    stack: Vec<SmDtonFrame>,
+    options: SmDtonOptions,
+    // typed text notation instead of plain json, see sd_text
+    typed: bool,
+}
+
+impl<'a> SmDtonBuilder<'a> {
+    #[allow(dead_code)]
+    pub fn from_json_str(text: &str) -> Result<Self, SmDtonError> {
+        return SmDtonBuilder::from_json_reader_with(text.as_bytes(), SmDtonOptions::default());
+    }
+
+    #[allow(dead_code)]
+    pub fn from_json_str_with(text: &str, options: SmDtonOptions) -> Result<Self, SmDtonError> {
+        return SmDtonBuilder::from_json_reader_with(text.as_bytes(), options);
+    }
+
+    #[allow(dead_code)]
+    pub fn from_json_reader<R: Read>(reader: R) -> Result<Self, SmDtonError> {
+        return SmDtonBuilder::from_json_reader_with(reader, SmDtonOptions::default());
+    }
+
+    #[allow(dead_code)]
+    pub fn from_json_reader_with<R: Read>(
+        reader: R,
+        options: SmDtonOptions,
+    ) -> Result<Self, SmDtonError> {
+        return SmDtonBuilder::_from_stream(reader, options, false);
+    }
+
+    pub(crate) fn _from_stream<R: Read>(
+        reader: R,
+        options: SmDtonOptions,
+        typed: bool,
+    ) -> Result<Self, SmDtonError> {
+        let mut builder = SmDtonBuilder::with_options(options);
+        let mut stream = SmDtonJsonStream {
+            reader: reader,
+            buf: vec![0; CHUNK_SIZE],
+            pos: 0,
+            len: 0,
+            base: 0,
+            stack: Vec::new(),
+            options: options,
+            typed: typed,
+        };
+        stream.parse(&mut builder)?;
+        return Ok(builder);
+    }
+}
+
+impl<R: Read> SmDtonJsonStream<R> {
+    #[inline]
+    fn offset(&self) -> usize {
+        return self.base + self.pos;
+    }
+
+    fn _error(&self) -> SmDtonError {
+        let offset = self.offset();
+        if self.typed {
+            return SmDtonError::InvalidText { offset: offset };
+        }
+        return SmDtonError::InvalidJson { offset: offset };
+    }
+
+    fn _fill(&mut self) -> Result<(), SmDtonError> {
+        self.base += self.len;
+        self.pos = 0;
+        self.len = 0;
+        loop {
+            match self.reader.read(&mut self.buf) {
+                Ok(n) => {
+                    self.len = n;
+                    return Ok(());
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(SmDtonError::Io(e.kind())),
+            }
+        }
+    }
+
+    #[inline]
+    fn peek(&mut self) -> Result<Option<u8>, SmDtonError> {
+        if self.pos == self.len {
+            self._fill()?;
+            if self.len == 0 {
+                return Ok(None);
+            }
+        }
+        return Ok(Some(self.buf[self.pos]));
+    }
+
+    #[inline]
+    fn next(&mut self) -> Result<u8, SmDtonError> {
+        match self.peek()? {
+            Some(c) => {
+                self.pos += 1;
+                return Ok(c);
+            }
+            None => return Err(self._error()),
+        }
+    }
+
+    fn expect(&mut self, want: u8) -> Result<(), SmDtonError> {
+        if self.peek()? != Some(want) {
+            return Err(self._error());
+        }
+        self.pos += 1;
+        return Ok(());
+    }
+
+    fn skip_ws(&mut self) -> Result<(), SmDtonError> {
+        while let Some(c) = self.peek()? {
+            match c {
+                b' ' | b'\t' | b'\n' | b'\r' => self.pos += 1,
+                _ => break,
+            }
+        }
+        return Ok(());
+    }
+
+    fn parse(&mut self, builder: &mut SmDtonBuilder<'_>) -> Result<(), SmDtonError> {
+        self.skip_ws()?;
+        match self.peek()? {
+            Some(b'{') | Some(b'[') => self.parse_value(builder, 0, None)?,
+            Some(b'n') => {
+                return Err(SmDtonError::NotANode {
+                    found: ST::SMDT_NUL,
+                })
+            }
+            Some(b't') | Some(b'f') => {
+                return Err(SmDtonError::NotANode {
+                    found: ST::SMDT_BOO,
+                })
+            }
+            Some(b'"') => {
+                return Err(SmDtonError::NotANode {
+                    found: ST::SMDT_STR,
+                })
+            }
+            Some(b'-') | Some(b'0'..=b'9') => {
+                return Err(SmDtonError::NotANode {
+                    found: ST::SMDT_F64,
+                })
+            }
+            _ => return Err(self._error()),
+        }
+
+        while let Some(top) = self.stack.last().copied() {
+            self.skip_ws()?;
+            let close = if top.map { b'}' } else { b']' };
+            if self.peek()? == Some(close) {
+                self.pos += 1;
+                self.stack.pop();
+                continue;
+            }
+            if top.count > 0 {
+                self.expect(b',')?;
+                self.skip_ws()?;
+            }
+            self.stack.last_mut().unwrap().count += 1;
+
+            let mut key = None;
+            if top.map {
+                key = Some(self.parse_string()?);
+                self.skip_ws()?;
+                self.expect(b':')?;
+                self.skip_ws()?;
+            }
+            self.parse_value(builder, top.oid, key)?;
+        }
+
+        self.skip_ws()?;
+        if self.peek()?.is_some() {
+            return Err(SmDtonError::TrailingBytes {
+                offset: self.offset(),
+            });
+        }
+        return Ok(());
+    }
+
+    // scalars are added at once, a map or array is attached and left open on the stack
+    fn parse_value(
+        &mut self,
+        builder: &mut SmDtonBuilder<'_>,
+        upoid: usize,
+        key: Option<String>,
+    ) -> Result<(), SmDtonError> {
+        let da = match self.peek()? {
+            Some(c @ b'{') | Some(c @ b'[') => {
+                if self.stack.len() > MAX_DEPTH {
+                    return Err(SmDtonError::TooDeep {
+                        depth: self.stack.len(),
+                    });
+                }
+                self.pos += 1;
+                let map = c == b'{';
+                let oid = builder.create_node(if map { ST::SMDT_MAP } else { ST::SMDT_ARR });
+                self.stack.push(SmDtonFrame {
+                    oid: oid,
+                    map: map,
+                    count: 0,
+                });
+                if upoid == 0 {
+                    return Ok(());
+                }
+                builder._node_data(oid)
+            }
+            Some(b'"') => {
+                let s = self.parse_string()?;
+                if !self.typed && s.starts_with("$B64$") {
+                    SmDtonData::try_new_b64(&s)?
+                } else {
+                    SmDtonData::new_string_owned(s)
+                }
+            }
+            Some(b'b') if self.typed => {
+                self.parse_literal(b"b64")?;
+                let s = self.parse_string()?;
+                let bytes = sd_text::decode_base64(&s)?;
+                SmDtonData::new_bin_owned(bytes)
+            }
+            Some(b't') => {
+                self.parse_literal(b"true")?;
+                SmDtonData::new_bool(true)
+            }
+            Some(b'f') => {
+                self.parse_literal(b"false")?;
+                SmDtonData::new_bool(false)
+            }
+            Some(b'n') => {
+                self.pos += 1;
+                if self.typed && self.peek()? == Some(b'a') {
+                    self.parse_literal(b"an")?;
+                    self.parse_float_word(f64::NAN)?
+                } else {
+                    self.parse_literal(b"ull")?;
+                    SmDtonData::new_null()
+                }
+            }
+            Some(b'i') if self.typed => self.parse_number()?,
+            Some(b'd') if self.typed && key.is_some() => {
+                self.parse_literal(b"del")?;
+                SmDtonData::new_delete()
+            }
+            Some(b'-') | Some(b'0'..=b'9') => self.parse_number()?,
+            _ => return Err(self._error()),
+        };
+        match key {
+            Some(key) => builder._do_add(upoid, Cow::Owned(key), da),
+            None => builder._do_push(upoid, da),
+        }
+        return Ok(());
+    }
+
+    fn parse_literal(&mut self, word: &[u8]) -> Result<(), SmDtonError> {
+        for want in word {
+            if self.peek()? != Some(*want) {
+                return Err(self._error());
+            }
+            self.pos += 1;
+        }
+        return Ok(());
+    }
+
+    fn parse_string(&mut self) -> Result<String, SmDtonError> {
+        let start = self.offset();
+        self.expect(b'"')?;
+        let mut bytes = Vec::new();
+        loop {
+            if self.peek()?.is_none() {
+                return Err(self._error());
+            }
+            // copy the plain run in one go
+            let piece = &self.buf[self.pos..self.len];
+            let run = piece
+                .iter()
+                .position(|c| *c == b'"' || *c == b'\\' || *c < 0x20)
+                .unwrap_or(piece.len());
+            bytes.extend_from_slice(&piece[..run]);
+            self.pos += run;
+            if self.pos == self.len {
+                continue;
+            }
+            match self.buf[self.pos] {
+                b'"' => {
+                    self.pos += 1;
+                    break;
+                }
+                b'\\' => {
+                    self.pos += 1;
+                    self.parse_escape(&mut bytes)?;
+                }
+                _ => return Err(self._error()),
+            }
+        }
+        match String::from_utf8(bytes) {
+            Ok(s) => return Ok(s),
+            Err(e) => {
+                return Err(SmDtonError::InvalidUtf8 {
+                    offset: start + 1 + e.utf8_error().valid_up_to(),
+                })
+            }
+        }
+    }
+
+    fn parse_escape(&mut self, bytes: &mut Vec<u8>) -> Result<(), SmDtonError> {
+        let c = match self.next()? {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                let hi = self.parse_hex4()?;
+                let code = match hi {
+                    0xD800..=0xDBFF => {
+                        self.expect(b'\\')?;
+                        self.expect(b'u')?;
+                        let lo = self.parse_hex4()?;
+                        if !(0xDC00..=0xDFFF).contains(&lo) {
+                            return Err(self._error());
+                        }
+                        0x10000 + ((hi - 0xD800) << 10) + (lo - 0xDC00)
+                    }
+                    0xDC00..=0xDFFF => return Err(self._error()),
+                    _ => hi,
+                };
+                char::from_u32(code).ok_or_else(|| self._error())?
+            }
+            _ => return Err(self._error()),
+        };
+        let mut utf8 = [0u8; 4];
+        bytes.extend_from_slice(c.encode_utf8(&mut utf8).as_bytes());
+        return Ok(());
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, SmDtonError> {
+        let mut v = 0;
+        for _ in 0..4 {
+            let c = self.next()?;
+            let d = (c as char).to_digit(16).ok_or_else(|| self._error())?;
+            v = v * 16 + d;
+        }
+        return Ok(v);
+    }
+
+    // decimal parts for try_new_number, digits past u64 only move the exponent
+    fn parse_number(&mut self) -> Result<SmDtonData<'static>, SmDtonError> {
+        let mut positive = true;
+        if self.peek()? == Some(b'-') {
+            positive = false;
+            self.pos += 1;
+        }
+        if self.typed && self.peek()? == Some(b'i') {
+            self.parse_literal(b"inf")?;
+            match positive {
+                true => return self.parse_float_word(f64::INFINITY),
+                false => return self.parse_float_word(f64::NEG_INFINITY),
+            }
+        }
+        let mut mantissa: u64 = 0;
+        let mut exponent: i64 = 0;
+        // no digit is added once the mantissa is full
+        let mut full = false;
+        // a nonzero digit was dropped
+        let mut truncated = false;
+
+        match self.peek()? {
+            Some(b'0') => self.pos += 1,
+            Some(b'1'..=b'9') => {
+                while let Some(c @ b'0'..=b'9') = self.peek()? {
+                    self.pos += 1;
+                    if !full {
+                        match mantissa
+                            .checked_mul(10)
+                            .and_then(|m| m.checked_add((c - b'0') as u64))
+                        {
+                            Some(m) => {
+                                mantissa = m;
+                                continue;
+                            }
+                            None => full = true,
+                        }
+                    }
+                    exponent += 1;
+                    truncated |= c != b'0';
+                }
+            }
+            _ => return Err(self._error()),
+        }
+
+        if self.peek()? == Some(b'.') {
+            self.pos += 1;
+            if !matches!(self.peek()?, Some(b'0'..=b'9')) {
+                return Err(self._error());
+            }
+            while let Some(c @ b'0'..=b'9') = self.peek()? {
+                self.pos += 1;
+                if !full {
+                    match mantissa
+                        .checked_mul(10)
+                        .and_then(|m| m.checked_add((c - b'0') as u64))
+                    {
+                        Some(m) => {
+                            mantissa = m;
+                            exponent -= 1;
+                            continue;
+                        }
+                        None => full = true,
+                    }
+                }
+                truncated |= c != b'0';
+            }
+        }
+
+        if let Some(b'e') | Some(b'E') = self.peek()? {
+            self.pos += 1;
+            let mut sign = 1;
+            match self.peek()? {
+                Some(b'+') => self.pos += 1,
+                Some(b'-') => {
+                    sign = -1;
+                    self.pos += 1;
+                }
+                _ => {}
+            }
+            if !matches!(self.peek()?, Some(b'0'..=b'9')) {
+                return Err(self._error());
+            }
+            let mut e: i64 = 0;
+            while let Some(c @ b'0'..=b'9') = self.peek()? {
+                self.pos += 1;
+                e = (e * 10 + (c - b'0') as i64).min(1 << 20);
+            }
+            exponent += sign * e;
+        }
+
+        let exponent = exponent.clamp(i16::MIN as i64, i16::MAX as i64) as i16;
+        let suffix = if self.typed {
+            self.parse_suffix()?
+        } else {
+            None
+        };
+        if let Some(smdt) = suffix {
+            // lost digits fit no integer type
+            let float = smdt == ST::SMDT_F32 || smdt == ST::SMDT_F64;
+            if truncated && !float {
+                return Err(self._error());
+            }
+            return sd_text::typed_number(smdt, positive, mantissa, exponent)
+                .ok_or_else(|| self._error());
+        }
+        let exact = self.options.exact_numbers;
+        let narrow = self.options.narrow_ints;
+        return SmDtonData::try_new_number(positive, mantissa, exponent, truncated, exact, narrow);
+    }
+
+    // type suffix after a number in typed text, e.g. the u8 of 12u8
+    fn parse_suffix(&mut self) -> Result<Option<u8>, SmDtonError> {
+        if !matches!(self.peek()?, Some(b'a'..=b'z')) {
+            return Ok(None);
+        }
+        let mut word = String::new();
+        while let Some(c @ (b'a'..=b'z' | b'0'..=b'9')) = self.peek()? {
+            if word.len() == 3 {
+                return Err(self._error());
+            }
+            word.push(c as char);
+            self.pos += 1;
+        }
+        match sd_text::suffix_type(&word) {
+            Some(smdt) => return Ok(Some(smdt)),
+            None => return Err(self._error()),
+        }
+    }
+
+    // nan and inf carry no digits, only a float suffix is allowed
+    fn parse_float_word(&mut self, v: f64) -> Result<SmDtonData<'static>, SmDtonError> {
+        match self.parse_suffix()? {
+            Some(ST::SMDT_F32) => return Ok(SmDtonData::new_f32(v as f32)),
+            Some(ST::SMDT_F64) => return Ok(SmDtonData::new_f64(v)),
+            _ => return Err(self._error()),
+        }
+    }
+}
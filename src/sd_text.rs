@@ -0,0 +1,156 @@
+// typed text notation, a json superset that keeps every value type
+//
+// numbers carry their type as a suffix: 200u8, -3i16, 7u16, -5i32, 9u32,
+// -1i64, 5u64, 0.25f32, 1.5e300f64. floats also take nanf64, inff32 and
+// -inff64. binary is b64"AAEC". strings are never decoded, so "$B64$.."
+// stays a string. numbers without a suffix follow the json rules. a map
+// entry whose value is del is a tombstone for overlays.
+//
+// the encoder writes every map entry in stored order, repeated keys
+// included, so text read back holds the same entries with the same types.
+
+use base64::{engine::general_purpose, Engine as _};
+use std::io::{Read, Write};
+
+use super::sd_builder::SmDtonBuilder;
+use super::sd_data::{SmDtonData, ST};
+use super::sd_error::SmDtonError;
+use super::sd_option::{SmDtonJsonFormat, SmDtonOptions};
+use super::sd_reader::SmDtonReader;
+use super::sd_value::SmDtonValue;
+use super::sd_write::{float_text, SmDtonJsonWriter};
+
+const SUFFIXES: [(&str, u8); 9] = [
+    ("u8", ST::SMDT_UI8),
+    ("i16", ST::SMDT_I16),
+    ("u16", ST::SMDT_U16),
+    ("i32", ST::SMDT_I32),
+    ("u32", ST::SMDT_U32),
+    ("f32", ST::SMDT_F32),
+    ("i64", ST::SMDT_I64),
+    ("u64", ST::SMDT_U64),
+    ("f64", ST::SMDT_F64),
+];
+
+impl<'a> SmDtonReader<'a> {
+    #[allow(dead_code)]
+    pub fn to_text(&self, oid: usize) -> Option<String> {
+        return self.try_to_text(oid).ok();
+    }
+
+    #[allow(dead_code)]
+    pub fn try_to_text(&self, oid: usize) -> Result<String, SmDtonError> {
+        let mut out = Vec::new();
+        self.write_text(oid, &mut out, SmDtonJsonFormat::default())?;
+        return String::from_utf8(out).map_err(|e| SmDtonError::InvalidUtf8 {
+            offset: e.utf8_error().valid_up_to(),
+        });
+    }
+
+    // b64_prefix of the format is not used, binary is always b64"..."
+    #[allow(dead_code)]
+    pub fn write_text<W: Write>(
+        &self,
+        oid: usize,
+        w: &mut W,
+        format: SmDtonJsonFormat,
+    ) -> Result<(), SmDtonError> {
+        let mut writer = SmDtonJsonWriter::new(w, format, true, vec![self]);
+        return writer.write_node(0, oid, 0);
+    }
+}
+
+impl<'a> SmDtonBuilder<'a> {
+    #[allow(dead_code)]
+    pub fn from_text_str(text: &str) -> Result<Self, SmDtonError> {
+        return SmDtonBuilder::from_text_reader(text.as_bytes());
+    }
+
+    #[allow(dead_code)]
+    pub fn from_text_reader<R: Read>(reader: R) -> Result<Self, SmDtonError> {
+        return SmDtonBuilder::_from_stream(reader, SmDtonOptions::default(), true);
+    }
+}
+
+pub(crate) fn suffix_type(word: &str) -> Option<u8> {
+    return SUFFIXES.iter().find(|(s, _)| *s == word).map(|(_, t)| *t);
+}
+
+fn _type_suffix(smdt: u8) -> &'static str {
+    return SUFFIXES
+        .iter()
+        .find(|(_, t)| *t == smdt)
+        .map_or("", |(s, _)| *s);
+}
+
+// number values with their suffix, None for anything else
+pub(crate) fn number_text(value: &SmDtonValue) -> Option<String> {
+    let (text, smdt) = match *value {
+        SmDtonValue::U8(v) => (v.to_string(), ST::SMDT_UI8),
+        SmDtonValue::I16(v) => (v.to_string(), ST::SMDT_I16),
+        SmDtonValue::U16(v) => (v.to_string(), ST::SMDT_U16),
+        SmDtonValue::I32(v) => (v.to_string(), ST::SMDT_I32),
+        SmDtonValue::U32(v) => (v.to_string(), ST::SMDT_U32),
+        SmDtonValue::I64(v) => (v.to_string(), ST::SMDT_I64),
+        SmDtonValue::U64(v) => (v.to_string(), ST::SMDT_U64),
+        SmDtonValue::F32(v) if v.is_finite() => (float_text(v, v.abs() as f64), ST::SMDT_F32),
+        SmDtonValue::F64(v) if v.is_finite() => (float_text(v, v.abs()), ST::SMDT_F64),
+        SmDtonValue::F32(v) => (_float_word(v as f64), ST::SMDT_F32),
+        SmDtonValue::F64(v) => (_float_word(v), ST::SMDT_F64),
+        _ => return None,
+    };
+    return Some(text + _type_suffix(smdt));
+}
+
+// nan and infinity, which have no digits
+fn _float_word(v: f64) -> String {
+    if v.is_nan() {
+        return "nan".to_string();
+    }
+    if v > 0.0 {
+        return "inf".to_string();
+    }
+    return "-inf".to_string();
+}
+
+pub(crate) fn typed_number(
+    smdt: u8,
+    positive: bool,
+    mantissa: u64,
+    exponent: i16,
+) -> Option<SmDtonData<'static>> {
+    if smdt == ST::SMDT_F32 || smdt == ST::SMDT_F64 {
+        // parsed from the decimal text so f32 is rounded once
+        let sign = if positive { "" } else { "-" };
+        let text = format!("{}{}e{}", sign, mantissa, exponent);
+        if smdt == ST::SMDT_F32 {
+            return Some(SmDtonData::new_f32(text.parse().ok()?));
+        }
+        return Some(SmDtonData::new_f64(text.parse().ok()?));
+    }
+
+    if exponent < 0 && mantissa != 0 {
+        return None;
+    }
+    let pw = 10i128.checked_pow(exponent.max(0) as u32)?;
+    let mut v = (mantissa as i128).checked_mul(pw)?;
+    if !positive {
+        v = -v;
+    }
+    match smdt {
+        ST::SMDT_UI8 => return Some(SmDtonData::new_u8(u8::try_from(v).ok()?)),
+        ST::SMDT_I16 => return Some(SmDtonData::new_i16(i16::try_from(v).ok()?)),
+        ST::SMDT_U16 => return Some(SmDtonData::new_u16(u16::try_from(v).ok()?)),
+        ST::SMDT_I32 => return Some(SmDtonData::new_i32(i32::try_from(v).ok()?)),
+        ST::SMDT_U32 => return Some(SmDtonData::new_u32(u32::try_from(v).ok()?)),
+        ST::SMDT_I64 => return Some(SmDtonData::new_i64(i64::try_from(v).ok()?)),
+        ST::SMDT_U64 => return Some(SmDtonData::new_u64(u64::try_from(v).ok()?)),
+        _ => return None,
+    }
+}
+
+pub(crate) fn decode_base64(text: &str) -> Result<Vec<u8>, SmDtonError> {
+    return general_purpose::STANDARD
+        .decode(text)
+        .map_err(|_| SmDtonError::InvalidBase64);
+}
@@ -0,0 +1,116 @@
+use crate::SmDtonReader;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SmDtonNodeRef {
+    pub oid: usize,
+}
+
+// more value kinds may come, matches outside the crate need a wildcard arm
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum SmDtonValue<'a> {
+    Null,
+    Bool(bool),
+    U8(u8),
+    I16(i16),
+    U16(u16),
+    I32(i32),
+    U32(u32),
+    F32(f32),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Str(&'a str),
+    Bin(&'a [u8]),
+    Map(SmDtonNodeRef),
+    Arr(SmDtonNodeRef),
+    Deleted,
+}
+
+impl<'a> SmDtonValue<'a> {
+    #[allow(dead_code)]
+    pub fn node_id(&self) -> Option<usize> {
+        match self {
+            SmDtonValue::Map(node) | SmDtonValue::Arr(node) => return Some(node.oid),
+            _ => return None,
+        }
+    }
+}
+
+// both iterators stop at the first entry that does not decode
+
+pub struct SmDtonEntries<'a> {
+    reader: SmDtonReader<'a>,
+    oid: usize,
+    index: usize,
+    len: usize,
+}
+
+impl<'a> SmDtonEntries<'a> {
+    pub(crate) fn new(reader: SmDtonReader<'a>, oid: usize, len: usize) -> Self {
+        SmDtonEntries {
+            reader: reader,
+            oid: oid,
+            index: 0,
+            len: len,
+        }
+    }
+}
+
+impl<'a> Iterator for SmDtonEntries<'a> {
+    type Item = (&'a str, SmDtonValue<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.len {
+            return None;
+        }
+        let entry = self.reader.try_get_sub_entry(self.oid, self.index);
+        self.index += 1;
+        if let Ok(Some((key, value_off))) = entry {
+            let value = self.reader.try_get_value_by_voff(value_off);
+            if let Ok(value) = value {
+                return Some((key, value));
+            }
+        }
+        self.index = self.len;
+        return None;
+    }
+}
+
+pub struct SmDtonItems<'a> {
+    reader: SmDtonReader<'a>,
+    oid: usize,
+    index: usize,
+    len: usize,
+}
+
+impl<'a> SmDtonItems<'a> {
+    pub(crate) fn new(reader: SmDtonReader<'a>, oid: usize, len: usize) -> Self {
+        SmDtonItems {
+            reader: reader,
+            oid: oid,
+            index: 0,
+            len: len,
+        }
+    }
+}
+
+impl<'a> Iterator for SmDtonItems<'a> {
+    type Item = SmDtonValue<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.len {
+            return None;
+        }
+        let value_off = self.reader.try_get_sub_voff(self.oid, self.index);
+        self.index += 1;
+        if let Ok(Some(value_off)) = value_off {
+            let value = self.reader.try_get_value_by_voff(value_off);
+            if let Ok(value) = value {
+                return Some(value);
+            }
+        }
+        self.index = self.len;
+        return None;
+    }
+}
@@ -0,0 +1,323 @@
+use base64::{engine::general_purpose, Engine as _};
+use std::collections::HashSet;
+use std::io::Write;
+
+use super::sd_error::SmDtonError;
+use super::sd_layer::{self, SmDtonChain, SmDtonChains};
+use super::sd_option::{SmDtonJsonFormat, SmDtonMergeOptions};
+use super::sd_reader::{SmDtonReader, MAX_DEPTH};
+use super::sd_text;
+use super::sd_value::SmDtonValue;
+use crate::{SmDton, ST};
+
+// json text written straight from the buffers, no json tree is built
+//
+// maps repeating a key keep the position of the first entry and the value of
+// the last, as to_json does. values of unknown type are skipped. with layers,
+// the values of a key are joined as sd_layer says. a key whose last value is
+// a tombstone is left out, typed text writes it as del.
+
+pub(crate) struct SmDtonJsonWriter<'r, 'a, W: Write> {
+    w: &'r mut W,
+    format: SmDtonJsonFormat,
+    // typed text notation, every entry is written with its exact type
+    typed: bool,
+    merge: SmDtonMergeOptions,
+    // lowest layer first
+    layers: Vec<&'r SmDtonReader<'a>>,
+    // nodes written so far, per layer
+    seen: Vec<HashSet<usize>>,
+}
+
+impl<'a> SmDtonReader<'a> {
+    #[allow(dead_code)]
+    pub fn write_json<W: Write>(
+        &self,
+        oid: usize,
+        w: &mut W,
+        format: SmDtonJsonFormat,
+    ) -> Result<(), SmDtonError> {
+        let mut writer = SmDtonJsonWriter::new(w, format, false, vec![self]);
+        return writer.write_node(0, oid, 0);
+    }
+}
+
+impl<'a> SmDton<'a> {
+    // root maps of all layers are merged, entries of the top layer win
+    #[allow(dead_code)]
+    pub fn write_json<W: Write>(
+        &self,
+        w: &mut W,
+        format: SmDtonJsonFormat,
+    ) -> Result<(), SmDtonError> {
+        let mut layers = self._layers();
+        layers.reverse();
+        if layers.is_empty() {
+            return Err(SmDtonError::InvalidNodeId(1));
+        }
+
+        let merge = layers.len() > 1 && layers.iter().all(|q| q.node_type(1) == ST::SMDT_MAP);
+        if !merge {
+            let top = layers.len() - 1;
+            let mut writer = SmDtonJsonWriter::new(w, format, false, vec![layers[top]]);
+            return writer.write_node(0, 1, 0);
+        }
+
+        let roots = (0..layers.len()).map(|layer| (layer, 1)).collect();
+        let mut writer = SmDtonJsonWriter::new(w, format, false, layers);
+        writer.merge = self.merge_options();
+        return writer.write_nodes(roots, 0);
+    }
+}
+
+impl<'r, 'a, W: Write> SmDtonJsonWriter<'r, 'a, W> {
+    pub(crate) fn new(
+        w: &'r mut W,
+        format: SmDtonJsonFormat,
+        typed: bool,
+        layers: Vec<&'r SmDtonReader<'a>>,
+    ) -> Self {
+        let seen = vec![HashSet::new(); layers.len()];
+        return SmDtonJsonWriter {
+            w: w,
+            format: format,
+            typed: typed,
+            merge: SmDtonMergeOptions::default(),
+            layers: layers,
+            seen: seen,
+        };
+    }
+
+    fn mark(&mut self, layer: usize, oid: usize) -> Result<(), SmDtonError> {
+        if !self.seen[layer].insert(oid) {
+            return Err(SmDtonError::RepeatedNode(oid));
+        }
+        return Ok(());
+    }
+
+    pub(crate) fn write_node(
+        &mut self,
+        layer: usize,
+        oid: usize,
+        depth: usize,
+    ) -> Result<(), SmDtonError> {
+        match self.layers[layer].node_type(oid) {
+            ST::SMDT_MAP | ST::SMDT_ARR => return self.write_nodes(vec![(layer, oid)], depth),
+            found => {
+                if oid == 0 || found == 0 {
+                    return Err(SmDtonError::InvalidNodeId(oid));
+                }
+                return Err(SmDtonError::InvalidNodeType {
+                    oid: oid,
+                    found: found,
+                });
+            }
+        }
+    }
+
+    // nodes of one type written as one, lowest layer first
+    fn write_nodes(&mut self, nodes: Vec<(usize, usize)>, depth: usize) -> Result<(), SmDtonError> {
+        if depth > MAX_DEPTH {
+            return Err(SmDtonError::TooDeep { depth: depth });
+        }
+        let mut map = false;
+        let mut entries = SmDtonChains::new(!self.typed);
+        let mut items = Vec::new();
+        for (layer, oid) in nodes {
+            self.mark(layer, oid)?;
+            let reader = self.layers[layer];
+            if reader.node_type(oid) == ST::SMDT_MAP {
+                map = true;
+                entries.add(layer, reader, oid)?;
+                continue;
+            }
+            for value in sd_layer::items(reader, oid)? {
+                items.push((layer, value));
+            }
+        }
+        if map {
+            return self.write_map(entries, depth);
+        }
+        return self.write_array(items, depth);
+    }
+
+    fn write_map(
+        &mut self,
+        entries: SmDtonChains<'a, usize>,
+        depth: usize,
+    ) -> Result<(), SmDtonError> {
+        let mut kept = match self.typed {
+            true => entries.all(),
+            false => entries.live(),
+        };
+        if self.format.sort_keys {
+            kept.sort_by(|a, b| a.0.cmp(b.0));
+        }
+
+        if kept.is_empty() {
+            return self.put(b"{}");
+        }
+        self.put(b"{")?;
+        for (index, (key, chain)) in kept.into_iter().enumerate() {
+            if index > 0 {
+                self.put(b",")?;
+            }
+            self.newline(depth + 1)?;
+            self.write_str(key)?;
+            match self.format.pretty {
+                true => self.put(b": ")?,
+                false => self.put(b":")?,
+            }
+            self.write_chain(&chain, depth)?;
+        }
+        self.newline(depth)?;
+        return self.put(b"}");
+    }
+
+    fn write_array(
+        &mut self,
+        items: SmDtonChain<'a, usize>,
+        depth: usize,
+    ) -> Result<(), SmDtonError> {
+        if items.is_empty() {
+            return self.put(b"[]");
+        }
+        self.put(b"[")?;
+        for (index, (layer, value)) in items.into_iter().enumerate() {
+            if index > 0 {
+                self.put(b",")?;
+            }
+            self.newline(depth + 1)?;
+            self.write_value(layer, value, depth)?;
+        }
+        self.newline(depth)?;
+        return self.put(b"]");
+    }
+
+    fn write_chain(
+        &mut self,
+        chain: &SmDtonChain<'a, usize>,
+        depth: usize,
+    ) -> Result<(), SmDtonError> {
+        let joined = sd_layer::joined(chain, self.merge);
+        match joined {
+            [] => return Ok(()),
+            [(layer, top)] => return self.write_value(*layer, *top, depth),
+            _ => {
+                let nodes = joined
+                    .iter()
+                    .map(|(layer, value)| (*layer, value.node_id().unwrap_or(0)))
+                    .collect();
+                return self.write_nodes(nodes, depth + 1);
+            }
+        }
+    }
+
+    fn write_value(
+        &mut self,
+        layer: usize,
+        value: SmDtonValue<'a>,
+        depth: usize,
+    ) -> Result<(), SmDtonError> {
+        let typed = if self.typed {
+            sd_text::number_text(&value)
+        } else {
+            None
+        };
+        if let Some(text) = typed {
+            return self.put_text(&text);
+        }
+        match value {
+            SmDtonValue::Null => return self.put(b"null"),
+            SmDtonValue::Bool(true) => return self.put(b"true"),
+            SmDtonValue::Bool(false) => return self.put(b"false"),
+            SmDtonValue::U8(v) => return self.put_display(v),
+            SmDtonValue::I16(v) => return self.put_display(v),
+            SmDtonValue::U16(v) => return self.put_display(v),
+            SmDtonValue::I32(v) => return self.put_display(v),
+            SmDtonValue::U32(v) => return self.put_display(v),
+            SmDtonValue::I64(v) => return self.put_display(v),
+            SmDtonValue::U64(v) => return self.put_display(v),
+            SmDtonValue::F32(v) => return self.write_float(v as f64),
+            SmDtonValue::F64(v) => return self.write_float(v),
+            SmDtonValue::Str(s) => return self.write_str(s),
+            SmDtonValue::Bin(bytes) => {
+                let data = general_purpose::STANDARD.encode(bytes);
+                if self.typed {
+                    self.put(b"b64")?;
+                    return self.write_str(&data);
+                }
+                return self.write_str(&(self.format.b64_prefix.to_string() + &data));
+            }
+            SmDtonValue::Map(node) | SmDtonValue::Arr(node) => {
+                return self.write_node(layer, node.oid, depth + 1);
+            }
+            SmDtonValue::Deleted if self.typed => return self.put(b"del"),
+            SmDtonValue::Deleted => return self.put(b"null"),
+        }
+    }
+
+    fn write_float(&mut self, v: f64) -> Result<(), SmDtonError> {
+        if !v.is_finite() {
+            return self.put(b"null");
+        }
+        return self.put_text(&float_text(v, v.abs()));
+    }
+
+    fn write_str(&mut self, s: &str) -> Result<(), SmDtonError> {
+        self.put(b"\"")?;
+        let bytes = s.as_bytes();
+        let mut start = 0;
+        for (index, &b) in bytes.iter().enumerate() {
+            let escaped: &[u8] = match b {
+                b'"' => b"\\\"",
+                b'\\' => b"\\\\",
+                b'\n' => b"\\n",
+                b'\r' => b"\\r",
+                b'\t' => b"\\t",
+                0x08 => b"\\b",
+                0x0c => b"\\f",
+                0x00..=0x1f => b"",
+                _ => continue,
+            };
+            self.put(&bytes[start..index])?;
+            start = index + 1;
+            match escaped.is_empty() {
+                true => self.put_text(&format!("\\u{:04x}", b))?,
+                false => self.put(escaped)?,
+            }
+        }
+        self.put(&bytes[start..])?;
+        return self.put(b"\"");
+    }
+
+    fn newline(&mut self, depth: usize) -> Result<(), SmDtonError> {
+        if !self.format.pretty {
+            return Ok(());
+        }
+        return self.put_text(&("\n".to_string() + &" ".repeat(depth * self.format.indent)));
+    }
+
+    fn put_display<T: std::fmt::Display>(&mut self, v: T) -> Result<(), SmDtonError> {
+        return self.put_text(&v.to_string());
+    }
+
+    fn put_text(&mut self, s: &str) -> Result<(), SmDtonError> {
+        return self.put(s.as_bytes());
+    }
+
+    fn put(&mut self, bytes: &[u8]) -> Result<(), SmDtonError> {
+        match self.w.write_all(bytes) {
+            Ok(()) => return Ok(()),
+            Err(e) => return Err(SmDtonError::Io(e.kind())),
+        }
+    }
+}
+
+// shortest text that reads back the same, exponent form outside 1e-6..1e21 as in javascript
+pub(crate) fn float_text<T: std::fmt::Display + std::fmt::LowerExp>(v: T, abs: f64) -> String {
+    if abs != 0.0 && !(1e-6..1e21).contains(&abs) {
+        return format!("{:e}", v);
+    }
+    return v.to_string();
+}
@@ -1,7 +1,12 @@
 macro_rules! def_num_copy {
     ($src: expr, $off: expr, $tgt: expr, $len: expr, $dty: ty) => {
-        let bytes: [u8; $len] = $src[$off..$off + $len].try_into().unwrap();
-        return <$dty>::from_le_bytes(bytes) as usize;
+        match $src.get($off..$off.saturating_add($len)) {
+            Some(piece) => {
+                let bytes: [u8; $len] = piece.try_into().unwrap();
+                return <$dty>::from_le_bytes(bytes) as usize;
+            }
+            None => return 0,
+        }
     };
 }
 
@@ -19,9 +24,10 @@ pub fn getblkz(bv: usize, num: usize) -> u8 {
 #[inline]
 pub fn get_int(u8a: &[u8], offset: usize, oz: usize) -> usize {
     match oz {
-        1 => {
-            return u8a[offset] as usize;
-        }
+        1 => match u8a.get(offset) {
+            Some(b) => return *b as usize,
+            None => return 0,
+        },
         2 => {
             def_num_copy!(u8a, offset, bytes, 2, u16);
         }
@@ -0,0 +1,100 @@
+#![cfg(feature = "json")]
+
+mod common;
+
+use common::build;
+use smdton::{SmDton, SmDtonBuilder, SmDtonError, SmDtonJsonFormat, SmDtonReader, ST};
+
+// buffer whose entry "a" points back at the root node
+fn cycle() -> Vec<u8> {
+    let smb = build(r#"{"a":{"b":1u8}}"#);
+    let reader = SmDtonReader::new(smb.get_buffer());
+    let voff = reader.get_field_voff(1, "a");
+    let mut buf = smb.get_buffer().to_vec();
+    assert_eq!(buf[voff], ST::SMDT_MAP);
+    buf[voff + 1] = 1;
+    return buf;
+}
+
+#[test]
+fn node_cycles_are_reported() {
+    let buf = cycle();
+    assert_eq!(
+        SmDtonReader::try_new(&buf).err(),
+        Some(SmDtonError::RepeatedNode(1))
+    );
+
+    let reader = SmDtonReader::new(&buf);
+    assert_eq!(
+        reader.try_to_json(1).err(),
+        Some(SmDtonError::RepeatedNode(1))
+    );
+    assert_eq!(reader.to_json(1), None);
+    assert!(reader.try_to_text(1).is_err());
+    let mut out = Vec::new();
+    assert!(reader
+        .write_json(1, &mut out, SmDtonJsonFormat::default())
+        .is_err());
+
+    // lookups follow the cycle only as far as the path goes
+    assert_eq!(reader.get_node_id_path(1, "a.a.a"), Some(1));
+    assert_eq!(reader.get_u8_path(1, "a.a.b"), None);
+}
+
+#[test]
+fn layered_cycles_are_reported() {
+    let base = build(r#"{"x":1u8}"#);
+    let buf = cycle();
+    let mut dton = SmDton::new_from_buffer(&base);
+    dton.push_layer(&buf);
+    assert!(dton.try_stringify().is_err());
+    assert_eq!(dton.stringify(), None);
+    assert!(dton.try_flatten().is_err());
+}
+
+#[test]
+fn nesting_is_bounded() {
+    let deep = "[".repeat(300) + &"]".repeat(300);
+    assert!(matches!(
+        SmDtonBuilder::from_json_str(&deep),
+        Err(SmDtonError::TooDeep { .. })
+    ));
+
+    let fine = "[".repeat(200) + &"]".repeat(200);
+    let smb = SmDtonBuilder::from_json_str(&fine).unwrap().build();
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    assert_eq!(reader.try_to_json(1).unwrap().dump(), fine);
+}
+
+#[test]
+fn offsets_past_the_end_read_as_absent() {
+    let smb = build(r#"{"s":"text","n":[1u8,2u8]}"#);
+    let reader = SmDtonReader::new(smb.get_buffer());
+    let len = smb.get_buffer().len();
+    assert_eq!(reader.get_string_by_voff(len), None);
+    assert_eq!(reader.get_u8_by_voff(len + 100), None);
+    assert_eq!(reader.get_value_by_voff(usize::MAX), None);
+    assert!(reader.try_get_value_by_voff(len).is_err());
+    assert_eq!(reader.get_sub_voff(7, 0), 0);
+    assert_eq!(reader.node_sub_num(7), 0);
+}
+
+#[test]
+fn fuzz_seeds_read_without_panic() {
+    let corpus = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus");
+    for target in std::fs::read_dir(corpus).unwrap() {
+        for seed in std::fs::read_dir(target.unwrap().path()).unwrap() {
+            let data = std::fs::read(seed.unwrap().path()).unwrap();
+            let reader = SmDtonReader::new(&data);
+            let _ = reader.to_json(1);
+            let _ = reader.get_sub_map(1);
+            for oid in 0..4 {
+                let _ = reader.iter_entries(oid).count();
+                let _ = reader.iter_items(oid).count();
+            }
+            for voff in 0..data.len() + 2 {
+                let _ = reader.get_value_by_voff(voff);
+            }
+        }
+    }
+}
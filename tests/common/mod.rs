@@ -0,0 +1,27 @@
+// fixtures shared by the integration tests, each test crate uses a part
+#![allow(dead_code)]
+
+#[cfg(feature = "json")]
+use smdton::{SmDton, SmDtonJsonFormat};
+use smdton::{SmDtonBuffer, SmDtonBuilder, SmDtonReader};
+
+// a buffer from typed text, e.g. {"port":5432u16}
+pub fn build(text: &str) -> SmDtonBuffer {
+    return SmDtonBuilder::from_text_str(text).unwrap().build();
+}
+
+// typed text of a buffer that must validate
+pub fn text(smb: &SmDtonBuffer) -> String {
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    reader.validate().unwrap();
+    return reader.try_to_text(1).unwrap();
+}
+
+// json of every layer as write_json merges them
+#[cfg(feature = "json")]
+pub fn written(dton: &SmDton) -> json::JsonValue {
+    let mut out = Vec::new();
+    dton.write_json(&mut out, SmDtonJsonFormat::default())
+        .unwrap();
+    return json::parse(std::str::from_utf8(&out).unwrap()).unwrap();
+}
@@ -0,0 +1,164 @@
+#![cfg(all(feature = "json", feature = "derive"))]
+
+mod common;
+
+use common::{build, text};
+use smdton::{DtonBuild, DtonView, SmDtonBuild, SmDtonBuilder, SmDtonReader, SmDtonViewField, ST};
+
+#[derive(DtonBuild, DtonView)]
+struct Limits {
+    conns: u32,
+}
+
+#[derive(DtonBuild, DtonView)]
+struct Server {
+    #[dton(rename = "host-name")]
+    host: String,
+    port: u16,
+    #[dton(skip)]
+    #[allow(dead_code)]
+    cache: u32,
+    #[dton(bin)]
+    key: Vec<u8>,
+    backup: Option<String>,
+    note: Option<String>,
+    limits: Limits,
+    tags: Vec<String>,
+}
+
+#[test]
+fn build_and_view_round_trip() {
+    let server = Server {
+        host: "h".to_string(),
+        port: 5432,
+        cache: 9,
+        key: vec![0, 1],
+        backup: None,
+        note: Some("n".to_string()),
+        limits: Limits { conns: 8 },
+        tags: vec!["x".to_string()],
+    };
+    let smb = server.to_buffer();
+    assert_eq!(
+        text(&smb),
+        r#"{"host-name":"h","port":5432u16,"key":b64"AAE=","backup":null,"note":"n","limits":{"conns":8u32},"tags":["x"]}"#
+    );
+
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    let view = ServerView::from_node(&reader, 1);
+    assert_eq!(view.host(), Some("h"));
+    assert_eq!(view.port(), Some(5432));
+    assert_eq!(view.key(), Some(&[0u8, 1][..]));
+    assert_eq!(view.backup(), Some(None));
+    assert_eq!(view.note(), Some(Some("n")));
+    assert_eq!(view.limits().unwrap().conns(), Some(8));
+    let tags = view.tags().unwrap();
+    assert_eq!(reader.get_string_by_id(tags.oid, 0), Some("x"));
+    assert_eq!(reader.get_field_voff(1, "cache"), 0);
+    assert_eq!(reader.get_field_voff(1, "host"), 0);
+}
+
+#[test]
+fn view_tells_null_from_missing() {
+    let smb = build(r#"{"host-name":"h","port":70000i64,"note":null,"limits":[]}"#);
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    let view = ServerView::from_node(&reader, 1);
+    assert_eq!(view.note(), Some(None));
+    assert_eq!(view.backup(), None);
+    // out of range or of another type reads as absent
+    assert_eq!(view.port(), None);
+    assert!(view.limits().is_none());
+
+    let view =
+        <Server as SmDtonViewField>::view_by_voff(&reader, reader.get_field_voff(1, "limits"));
+    assert!(view.is_none());
+}
+
+#[test]
+fn derive_rejects_unsupported_shapes() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
+
+#[derive(DtonBuild, DtonView)]
+struct Sample {
+    flag: bool,
+    small: u8,
+    temp: i16,
+    port: u16,
+    delta: i32,
+    count: u32,
+    id: i64,
+    big: u64,
+    ratio: f32,
+    mean: f64,
+    name: String,
+    children: Vec<Limits>,
+}
+
+#[test]
+fn every_field_type_builds_and_views() {
+    let sample = Sample {
+        flag: true,
+        small: 1,
+        temp: -2,
+        port: 3,
+        delta: -4,
+        count: 5,
+        id: -6,
+        big: u64::MAX,
+        ratio: 0.5,
+        mean: 1.5,
+        name: "s".to_string(),
+        children: vec![Limits { conns: 1 }, Limits { conns: 2 }],
+    };
+    let smb = sample.to_buffer();
+    assert_eq!(
+        text(&smb),
+        r#"{"flag":true,"small":1u8,"temp":-2i16,"port":3u16,"delta":-4i32,"count":5u32,"id":-6i64,"big":18446744073709551615u64,"ratio":0.5f32,"mean":1.5f64,"name":"s","children":[{"conns":1u32},{"conns":2u32}]}"#
+    );
+
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    let view = SampleView::from_node(&reader, 1);
+    assert_eq!(view.node_id(), 1);
+    assert_eq!(view.flag(), Some(true));
+    assert_eq!(view.small(), Some(1));
+    assert_eq!(view.temp(), Some(-2));
+    assert_eq!(view.port(), Some(3));
+    assert_eq!(view.delta(), Some(-4));
+    assert_eq!(view.count(), Some(5));
+    assert_eq!(view.id(), Some(-6));
+    assert_eq!(view.big(), Some(u64::MAX));
+    assert_eq!(view.ratio(), Some(0.5));
+    assert_eq!(view.mean(), Some(1.5));
+    assert_eq!(view.name(), Some("s"));
+    let children = view.children().unwrap();
+    let second = reader.get_node_id_by_id(children.oid, 1).unwrap();
+    assert_eq!(LimitsView::from_node(&reader, second).conns(), Some(2));
+}
+
+#[test]
+fn views_read_json_integers_of_any_width() {
+    let smb = build(r#"{"conns":7i64}"#);
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    assert_eq!(LimitsView::from_node(&reader, 1).conns(), Some(7));
+
+    let smb = build(r#"{"conns":-7i64}"#);
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    assert_eq!(LimitsView::from_node(&reader, 1).conns(), None);
+}
+
+#[test]
+fn build_into_fills_a_node_of_a_larger_document() {
+    let limits = Limits { conns: 3 };
+    let mut builder = SmDtonBuilder::new();
+    let root = builder.create_node(ST::SMDT_MAP);
+    builder.add_string(root, "name", "outer");
+    let sub = builder.create_node(ST::SMDT_MAP);
+    limits.build_into(&mut builder, sub);
+    builder.add_node(root, "limits", sub);
+    assert_eq!(
+        text(&builder.build()),
+        r#"{"name":"outer","limits":{"conns":3u32}}"#
+    );
+}
@@ -0,0 +1,126 @@
+#![cfg(feature = "json")]
+
+mod common;
+
+use common::{build, text, written};
+use smdton::{
+    diff, diff_with, try_diff, try_diff_with, SmDton, SmDtonArrayMerge, SmDtonBuffer,
+    SmDtonMergeOptions, SmDtonPair, SmDtonReader,
+};
+
+const OLD: &str = r#"{"db":{"host":"h","port":5432u16,"opts":{"a":1i64,"b":2i64}},"tags":["x","y"],"name":"n","id":1u8}"#;
+const NEW: &str = r#"{"db":{"port":5433u16,"opts":{"a":1i64,"b":2i64}},"tags":["x"],"id":1i64,"extra":b64"AAE="}"#;
+
+// text of the pair as written, every layer merged
+fn merged(old: SmDtonBuffer, update: SmDtonBuffer, merge: SmDtonMergeOptions) -> json::JsonValue {
+    let pair = SmDtonPair::new(old, update);
+    let mut dton = SmDton::try_new_from_pair(&pair).unwrap();
+    dton.set_merge(merge);
+    return written(&dton);
+}
+
+fn deep() -> SmDtonMergeOptions {
+    return SmDtonMergeOptions {
+        deep: true,
+        ..Default::default()
+    };
+}
+
+#[test]
+fn deep_diff_keeps_changed_entries_only() {
+    let update = diff_with(&build(OLD), &build(NEW), deep());
+    assert_eq!(
+        text(&update),
+        r#"{"db":{"port":5433u16,"host":del},"tags":["x"],"id":1i64,"extra":b64"AAE=","name":del}"#
+    );
+
+    let new = SmDtonReader::try_new(build(NEW).get_buffer())
+        .unwrap()
+        .try_to_json(1)
+        .unwrap();
+    assert_eq!(merged(build(OLD), update, deep()), new);
+}
+
+#[test]
+fn diff_reads_back_through_dton() {
+    let pair = SmDtonPair::new(build(OLD), diff_with(&build(OLD), &build(NEW), deep()));
+    let mut dton = SmDton::new_from_pair(&pair);
+    dton.set_merge(deep());
+    assert_eq!(dton.get_string_path("db.host"), None);
+    assert_eq!(dton.get_u16_path("db.port"), Some(5433));
+    assert_eq!(dton.get_i64_path("db.opts.b"), Some(2));
+    assert_eq!(dton.get_i64("id"), Some(1));
+    assert_eq!(dton.get_bin("extra"), Some(&[0u8, 1][..]));
+    assert_eq!(dton.get_string("name"), None);
+    assert_eq!(dton.get_string_path("tags[1]"), None);
+}
+
+#[test]
+fn equal_documents_give_an_empty_update() {
+    let update = try_diff(&build(OLD), &build(OLD)).unwrap();
+    assert_eq!(text(&update), "{}");
+}
+
+#[test]
+fn diff_replaces_root_entries() {
+    let update = diff(&build(OLD), &build(NEW));
+    assert_eq!(
+        text(&update),
+        r#"{"db":{"port":5433u16,"opts":{"a":1i64,"b":2i64}},"tags":["x"],"id":1i64,"extra":b64"AAE=","name":del}"#
+    );
+    let want = json::parse(
+        r#"{"db":{"port":5433,"opts":{"a":1,"b":2}},"tags":["x"],"id":1,"extra":"$B64$AAE="}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        merged(build(OLD), update, SmDtonMergeOptions::default()),
+        want
+    );
+}
+
+#[test]
+fn diff_reads_back_through_a_plain_pair() {
+    let old = build(r#"{"db":{"host":"h","port":1u8},"name":"n"}"#);
+    let new = build(r#"{"db":{"host":"h","port":2u8},"name":"n"}"#);
+    let update = diff(&old, &new);
+    assert_eq!(text(&update), r#"{"db":{"host":"h","port":2u8}}"#);
+
+    let pair = SmDtonPair::new(old, update);
+    let dton = SmDton::new_from_pair(&pair);
+    assert_eq!(dton.get_string_path("db.host"), Some("h"));
+    assert_eq!(dton.get_u8_path("db.port"), Some(2));
+    assert_eq!(
+        json::parse(&dton.stringify().unwrap()).unwrap(),
+        json::parse(r#"{"db":{"host":"h","port":2},"name":"n"}"#).unwrap()
+    );
+    assert_eq!(text(&pair.compact()), text(&new));
+}
+
+#[test]
+fn concat_diff_appends_array_tail() {
+    let merge = SmDtonMergeOptions {
+        deep: true,
+        arrays: SmDtonArrayMerge::Concat,
+    };
+    let old = build(r#"{"tags":["x","y"]}"#);
+    let new = build(r#"{"tags":["x","y","z"]}"#);
+    let update = try_diff_with(&old, &new, merge).unwrap();
+    assert_eq!(text(&update), r#"{"tags":["z"]}"#);
+    assert_eq!(
+        merged(old, update, merge),
+        json::parse(r#"{"tags":["x","y","z"]}"#).unwrap()
+    );
+
+    let shorter = build(r#"{"tags":["x"]}"#);
+    assert!(try_diff_with(&new, &shorter, merge).is_err());
+    assert!(diff_with(&new, &shorter, merge).get_buffer().is_empty());
+}
+
+#[test]
+fn root_arrays_are_copied_whole() {
+    let old = build(r#"[1,2]"#);
+    let new = build(r#"{"a":[3u8]}"#);
+    let update = try_diff(&old, &new).unwrap();
+    assert_eq!(text(&update), r#"{"a":[3u8]}"#);
+    assert!(try_diff(&SmDtonBuffer::new(), &new).is_err());
+}
@@ -0,0 +1,163 @@
+#![cfg(feature = "json")]
+
+mod common;
+
+use common::{build, text};
+use smdton::{SmDton, SmDtonBuffer, SmDtonBuilder, SmDtonError, SmDtonReader, ST};
+
+#[test]
+fn empty_key_is_a_map_key() {
+    let jsn = json::parse(r#"{"a":1,"":2,"b":{"":[3,{"":4}]}}"#).unwrap();
+    let smb = SmDtonBuilder::try_new_from_json(&jsn).unwrap().build();
+    assert_eq!(
+        text(&smb),
+        r#"{"a":1i64,"":2i64,"b":{"":[3i64,{"":4i64}]}}"#
+    );
+
+    let lenient = SmDtonBuilder::new_from_json(&jsn).build();
+    assert_eq!(text(&lenient), text(&smb));
+}
+
+#[test]
+fn add_from_json_with_empty_key() {
+    let jsn = json::parse(r#"{"x":true}"#).unwrap();
+    let mut bd = SmDtonBuilder::new();
+    let root = bd.create_node(ST::SMDT_MAP);
+    let arr = bd.create_node(ST::SMDT_ARR);
+    bd.try_add_node(root, "arr", arr).unwrap();
+    bd.try_add_from_json(root, "", &jsn).unwrap();
+    bd.try_add_from_json(arr, "", &jsn).unwrap();
+    assert_eq!(text(&bd.build()), r#"{"arr":[{"x":true}],"":{"x":true}}"#);
+}
+
+#[test]
+fn lenient_build_keeps_undecodable_base64_as_text() {
+    let jsn = json::parse(r#"{"a":1,"bin":"$B64$!!","b":[2,"$B64$AAE="],"c":3}"#).unwrap();
+    let smb = SmDtonBuilder::new_from_json(&jsn).build();
+    assert_eq!(
+        text(&smb),
+        r#"{"a":1i64,"bin":"$B64$!!","b":[2i64,b64"AAE="],"c":3i64}"#
+    );
+    assert!(SmDtonBuilder::try_new_from_json(&jsn).is_err());
+}
+
+#[test]
+fn builder_reports_bad_nodes() {
+    let mut bd = SmDtonBuilder::new();
+    let root = bd.create_node(ST::SMDT_MAP);
+    let arr = bd.create_node(ST::SMDT_ARR);
+    assert_eq!(
+        bd.try_add_u8(99, "a", 1),
+        Err(SmDtonError::InvalidNodeId(99))
+    );
+    assert_eq!(
+        bd.try_add_u8(arr, "a", 1),
+        Err(SmDtonError::TypeMismatch {
+            expected: ST::SMDT_MAP,
+            found: ST::SMDT_ARR
+        })
+    );
+    assert_eq!(
+        bd.try_push_u8(root, 1),
+        Err(SmDtonError::TypeMismatch {
+            expected: ST::SMDT_ARR,
+            found: ST::SMDT_MAP
+        })
+    );
+    assert_eq!(
+        bd.try_add_base64(root, "b", "$B64$!!"),
+        Err(SmDtonError::InvalidBase64)
+    );
+    assert_eq!(
+        bd.try_add_base64(root, "c", "$B6"),
+        Err(SmDtonError::InvalidBase64)
+    );
+
+    // the lenient calls leave the entry out
+    bd.add_u8(99, "a", 1);
+    bd.push_u8(root, 1);
+    bd.add_base64(root, "b", "$B64$!!");
+    bd.add_node(root, "arr", arr);
+    assert_eq!(text(&bd.build()), r#"{"arr":[]}"#);
+}
+
+#[test]
+fn reader_tells_absent_from_broken() {
+    let smb = build(r#"{"s":"v","n":5u16,"l":[1u8]}"#);
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    assert_eq!(reader.try_get_u16(1, "n"), Ok(Some(5)));
+    assert_eq!(reader.try_get_u16(1, "x"), Ok(None));
+    assert_eq!(
+        reader.try_get_u16(1, "s"),
+        Err(SmDtonError::TypeMismatch {
+            expected: ST::SMDT_U16,
+            found: ST::SMDT_STR
+        })
+    );
+    assert_eq!(reader.get_u16(1, "s"), None);
+    assert_eq!(
+        reader.try_get_u16(9, "n"),
+        Err(SmDtonError::InvalidNodeId(9))
+    );
+
+    let list = reader.get_node_id(1, "l").unwrap();
+    assert!(matches!(
+        reader.try_get_sub_key(list, 0),
+        Err(SmDtonError::TypeMismatch { .. })
+    ));
+    assert_eq!(reader.get_sub_key(list, 0), None);
+    assert_eq!(reader.try_get_sub_key(1, 3), Ok(None));
+}
+
+#[test]
+fn reader_reports_broken_keys() {
+    let smb = build(r#"{"key":1u8}"#);
+    let mut buf = smb.get_buffer().to_vec();
+    let at = buf.windows(4).position(|w| w == b"key\0").unwrap();
+    buf[at] = 0xff;
+
+    let reader = SmDtonReader::new(&buf);
+    assert!(matches!(
+        reader.try_get_sub_key(1, 0),
+        Err(SmDtonError::InvalidUtf8 { .. })
+    ));
+    assert!(reader.try_get_sub_map(1).is_err());
+    assert_eq!(reader.get_sub_key(1, 0), None);
+    assert!(reader.get_sub_map(1).is_empty());
+    assert!(reader.try_to_json(1).is_err());
+    assert_eq!(reader.to_json(1), None);
+}
+
+#[test]
+fn dton_tells_absent_from_mistyped() {
+    let empty = SmDtonBuffer::new();
+    let dton = SmDton::try_new_from_buffer(&empty).unwrap();
+    assert_eq!(dton.layer_count(), 0);
+    assert_eq!(dton.try_get_u16("x"), Ok(None));
+
+    let smb = build(r#"{"s":"v"}"#);
+    let dton = SmDton::try_new_from_buffer(&smb).unwrap();
+    assert_eq!(dton.try_get_u16("x"), Ok(None));
+    assert!(matches!(
+        dton.try_get_u16("s"),
+        Err(SmDtonError::TypeMismatch { .. })
+    ));
+    assert_eq!(dton.get_u16("s"), None);
+}
+
+#[test]
+fn errors_display_their_place() {
+    let error = SmDtonError::TypeMismatch {
+        expected: ST::SMDT_U16,
+        found: ST::SMDT_STR,
+    };
+    assert_eq!(
+        error.to_string(),
+        "type mismatch: expected 0x14, found 0x21"
+    );
+    let field = SmDtonError::Field {
+        path: "a[1]".to_string(),
+        error: Box::new(SmDtonError::InvalidNodeId(3)),
+    };
+    assert_eq!(field.to_string(), "a[1]: invalid node id 3");
+}
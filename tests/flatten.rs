@@ -0,0 +1,104 @@
+#![cfg(feature = "json")]
+
+mod common;
+
+use common::{build, text, written};
+use smdton::{
+    diff, diff_with, SmDton, SmDtonArrayMerge, SmDtonBuffer, SmDtonMergeOptions, SmDtonPair,
+    SmDtonReader,
+};
+
+const BASE: &str =
+    r#"{"db":{"host":"h","port":5432u16,"tags":["x"]},"ratio":0.5f32,"key":b64"AAE=","name":"n"}"#;
+const UPDATE: &str = r#"{"db":{"port":1u16,"tags":["y"]},"name":del,"extra":-3i16}"#;
+
+#[test]
+fn compact_keeps_types() {
+    let pair = SmDtonPair::new(build(BASE), build(UPDATE));
+    assert_eq!(
+        text(&pair.compact()),
+        r#"{"db":{"port":1u16,"tags":["y"]},"ratio":0.5f32,"key":b64"AAE=","extra":-3i16}"#
+    );
+
+    let deep = SmDtonMergeOptions {
+        deep: true,
+        arrays: SmDtonArrayMerge::Concat,
+    };
+    assert_eq!(
+        text(&pair.try_compact_with(deep).unwrap()),
+        r#"{"db":{"host":"h","port":1u16,"tags":["x","y"]},"ratio":0.5f32,"key":b64"AAE=","extra":-3i16}"#
+    );
+}
+
+#[test]
+fn flatten_matches_write_json() {
+    let mut stack = SmDtonPair::default();
+    stack.push(build(BASE));
+    stack.push(build(UPDATE));
+    stack.push(build(r#"{"db":{"host":del},"name":"back"}"#));
+    let mut dton = SmDton::new_from_pair(&stack);
+    for merge in [
+        SmDtonMergeOptions::default(),
+        SmDtonMergeOptions {
+            deep: true,
+            ..Default::default()
+        },
+        SmDtonMergeOptions {
+            deep: true,
+            arrays: SmDtonArrayMerge::Concat,
+        },
+    ] {
+        dton.set_merge(merge);
+        let flat = dton.try_flatten().unwrap();
+        let reader = SmDtonReader::try_new(flat.get_buffer()).unwrap();
+        assert_eq!(reader.try_to_json(1).unwrap(), written(&dton));
+
+        let single = SmDton::new_from_buffer(&flat);
+        assert_eq!(
+            single.stringify().map(|s| json::parse(&s).unwrap()),
+            Some(written(&dton))
+        );
+    }
+}
+
+#[test]
+fn flatten_applies_tombstones_and_repeats() {
+    let smb = build(r#"{"a":1u8,"a":2u8,"b":del,"c":{"d":del}}"#);
+    let dton = SmDton::new_from_buffer(&smb);
+    assert_eq!(text(&dton.flatten()), r#"{"a":2u8,"c":{}}"#);
+}
+
+#[test]
+fn compact_of_diff_gives_new() {
+    let old = build(BASE);
+    let new = build(r#"{"db":{"host":"h2","port":5432u16,"tags":[]},"ratio":0.5f32,"more":b64""}"#);
+    let pair = SmDtonPair::new(build(BASE), diff(&old, &new));
+    assert_eq!(text(&pair.compact()), text(&new));
+
+    let deep = SmDtonMergeOptions {
+        deep: true,
+        ..Default::default()
+    };
+    let pair = SmDtonPair::new(build(BASE), diff_with(&old, &new, deep));
+    assert_eq!(text(&pair.compact_with(deep)), text(&new));
+}
+
+#[test]
+fn combine_keeps_types() {
+    let base = build(BASE);
+    let update = build(UPDATE);
+    let lower = SmDton::new_from_buffer(&base);
+    let upper = SmDton::new_from_buffer(&update);
+    let jsn = lower.combine(&upper).unwrap();
+    assert_eq!(jsn["db"]["port"], 1);
+    assert_eq!(jsn["ratio"], 0.5);
+    assert_eq!(jsn["key"], "$B64$AAE=");
+    assert!(jsn["name"].is_null());
+
+    let empty = SmDtonBuffer::new();
+    assert!(SmDton::new_from_buffer(&empty).try_flatten().is_err());
+    assert!(SmDton::new_from_buffer(&empty)
+        .flatten()
+        .get_buffer()
+        .is_empty());
+}
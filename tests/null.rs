@@ -0,0 +1,73 @@
+#![cfg(feature = "json")]
+
+mod common;
+
+use common::text;
+use smdton::{SmDton, SmDtonBuilder, SmDtonMap, SmDtonReader, ST};
+
+fn round_trip(doc: &str) -> json::JsonValue {
+    let jsn = json::parse(doc).unwrap();
+    let smb = SmDtonBuilder::try_new_from_json(&jsn).unwrap().build();
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    return reader.try_to_json(1).unwrap();
+}
+
+#[test]
+fn null_survives_a_round_trip() {
+    for doc in [
+        r#"{"a":null}"#,
+        r#"[1,null,2]"#,
+        r#"{"a":[null,{"b":null}],"c":null}"#,
+        r#"[null]"#,
+    ] {
+        assert_eq!(round_trip(doc), json::parse(doc).unwrap(), "{}", doc);
+    }
+}
+
+#[test]
+fn null_keeps_array_positions() {
+    let jsn = json::parse(r#"{"l":[1,null,2]}"#).unwrap();
+    let smb = SmDtonBuilder::new_from_json(&jsn).build();
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    let list = reader.get_node_id(1, "l").unwrap();
+    assert_eq!(reader.node_sub_num(list), 3);
+    assert!(reader.is_null_by_id(list, 1));
+    assert_eq!(reader.get_i64_by_id(list, 2), Some(2));
+    assert_eq!(reader.get_null_path(1, "l[1]"), Some(()));
+}
+
+#[test]
+fn null_is_told_from_absent() {
+    let mut bd = SmDtonBuilder::new();
+    let root = bd.create_node(ST::SMDT_MAP);
+    let arr = bd.create_node(ST::SMDT_ARR);
+    bd.add_null(root, "a");
+    bd.push_null(arr);
+    bd.add_node(root, "l", arr);
+    let smb = bd.build();
+    assert_eq!(text(&smb), r#"{"a":null,"l":[null]}"#);
+
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    assert!(reader.is_null(1, "a"));
+    assert!(!reader.is_null(1, "b"));
+    assert_eq!(reader.try_get_null(1, "a"), Ok(Some(())));
+    assert_eq!(reader.try_get_null(1, "b"), Ok(None));
+    assert!(reader.try_get_null(1, "l").is_err());
+
+    let dton = SmDton::new_from_buffer(&smb);
+    assert!(dton.is_null("a"));
+    assert!(!dton.is_null("b"));
+    assert_eq!(
+        dton.stringify(),
+        Some("{\n    \"a\": null,\n    \"l\": [\n        null\n    ]\n}".to_string())
+    );
+}
+
+#[test]
+fn map_builder_keeps_null() {
+    let jsn = json::parse(r#"{"a":null,"b":1}"#).unwrap();
+    let mut map = SmDtonMap::new();
+    map.add_from_json(&jsn);
+    map.add_null("c");
+    assert_eq!(text(&map.build()), r#"{"a":null,"b":1i64,"c":null}"#);
+}
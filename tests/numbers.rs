@@ -0,0 +1,282 @@
+#![cfg(feature = "json")]
+
+mod common;
+
+use common::{build, text};
+use smdton::{SmDton, SmDtonBuilder, SmDtonError, SmDtonMap, SmDtonOptions, SmDtonReader};
+
+fn exact() -> SmDtonOptions {
+    return SmDtonOptions {
+        exact_numbers: true,
+        ..Default::default()
+    };
+}
+
+#[test]
+fn lenient_exact_build_leaves_inexact_numbers_out() {
+    let jsn =
+        json::parse(r#"{"a":1,"big":123456789012345678901,"list":[2,1e30,3],"f":0.5}"#).unwrap();
+    assert!(matches!(
+        SmDtonBuilder::try_new_from_json_with(&jsn, exact()),
+        Err(SmDtonError::InexactNumber)
+    ));
+    let smb = SmDtonBuilder::new_from_json_with(&jsn, exact()).build();
+    assert_eq!(text(&smb), r#"{"a":1i64,"list":[2i64,3i64],"f":0.5f64}"#);
+
+    // without the option the integer is rounded into f64
+    let rounded = SmDtonBuilder::new_from_json(&jsn).build();
+    assert_eq!(
+        text(&rounded),
+        r#"{"a":1i64,"big":123456789012345680000f64,"list":[2i64,1e30f64,3i64],"f":0.5f64}"#
+    );
+}
+
+#[test]
+fn lenient_exact_map_leaves_inexact_numbers_out() {
+    let jsn = json::parse(r#"{"a":1,"big":123456789012345678901}"#).unwrap();
+    let mut map = SmDtonMap::with_options(exact());
+    map.add_from_json(&jsn);
+    assert_eq!(text(&map.build()), r#"{"a":1i64}"#);
+
+    let mut map = SmDtonMap::with_options(exact());
+    assert!(matches!(
+        map.try_add_from_json(&jsn),
+        Err(SmDtonError::InexactNumber)
+    ));
+}
+
+#[test]
+fn integers_past_u64_are_not_truncated() {
+    let doc = r#"{"a":18446744073709551616,"b":-18446744073709551616,"c":18446744073709551610}"#;
+    let want =
+        r#"{"a":18446744073709552000f64,"b":-18446744073709552000f64,"c":18446744073709551610u64}"#;
+    let jsn = json::parse(doc).unwrap();
+    assert_eq!(text(&SmDtonBuilder::new_from_json(&jsn).build()), want);
+    assert_eq!(
+        text(&SmDtonBuilder::from_json_str(doc).unwrap().build()),
+        want
+    );
+    assert!(matches!(
+        SmDtonBuilder::from_json_str_with(doc, exact()),
+        Err(SmDtonError::InexactNumber)
+    ));
+}
+
+#[test]
+fn extreme_integers_keep_their_value() {
+    let doc = r#"{"min":-9223372036854775808,"max":9223372036854775807,"umax":18446744073709551615,"neg":-9223372036854775809}"#;
+    let want = r#"{"min":-9223372036854775808i64,"max":9223372036854775807i64,"umax":18446744073709551615u64,"neg":-9223372036854776000f64}"#;
+    let jsn = json::parse(doc).unwrap();
+    let built = SmDtonBuilder::new_from_json(&jsn).build();
+    assert_eq!(text(&built), want);
+    let mut map = SmDtonMap::new();
+    map.add_from_json(&jsn);
+    assert_eq!(text(&map.build()), want);
+    let streamed = SmDtonBuilder::from_json_str(doc).unwrap().build();
+    assert_eq!(text(&streamed), want);
+
+    let reader = SmDtonReader::try_new(built.get_buffer()).unwrap();
+    assert_eq!(reader.get_i64(1, "min"), Some(i64::MIN));
+    assert_eq!(reader.get_u64(1, "umax"), Some(u64::MAX));
+    assert_eq!(reader.get_i64(1, "umax"), None);
+    assert_eq!(
+        reader.try_to_json(1).unwrap().dump(),
+        doc.replace("-9223372036854775809", "-9223372036854776000")
+    );
+}
+
+#[test]
+fn exponents_and_negative_zero() {
+    let doc = r#"{"z":-0,"e":1e2,"m":1.5e3,"f":12e-1}"#;
+    let want = r#"{"z":-0f64,"e":100i64,"m":1500i64,"f":1.2f64}"#;
+    let jsn = json::parse(doc).unwrap();
+    let smb = SmDtonBuilder::new_from_json(&jsn).build();
+    assert_eq!(text(&smb), want);
+    assert_eq!(
+        text(&SmDtonBuilder::from_json_str(doc).unwrap().build()),
+        want
+    );
+
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    assert!(reader.get_f64(1, "z").unwrap().is_sign_negative());
+    assert_eq!(reader.get_i64(1, "m"), Some(1500));
+}
+
+#[test]
+fn exact_numbers_fail_instead_of_rounding() {
+    let doc = r#"{"a":123456789012345678901}"#;
+    let jsn = json::parse(doc).unwrap();
+    assert!(matches!(
+        SmDtonBuilder::try_new_from_json_with(&jsn, exact()),
+        Err(SmDtonError::InexactNumber)
+    ));
+    assert!(matches!(
+        SmDtonBuilder::from_json_str_with(doc, exact()),
+        Err(SmDtonError::InexactNumber)
+    ));
+
+    // exact values pass the option untouched
+    let doc = r#"{"a":18446744073709551615,"b":-0,"c":0.5,"d":1e3}"#;
+    let smb = SmDtonBuilder::from_json_str_with(doc, exact())
+        .unwrap()
+        .build();
+    assert_eq!(
+        text(&smb),
+        r#"{"a":18446744073709551615u64,"b":-0f64,"c":0.5f64,"d":1000i64}"#
+    );
+}
+
+fn narrow() -> SmDtonOptions {
+    return SmDtonOptions {
+        narrow_ints: true,
+        ..Default::default()
+    };
+}
+
+#[test]
+fn narrow_ints_pick_the_smallest_type() {
+    let doc = r#"{"a":5,"b":-129,"c":40000,"d":70000,"e":3000000000,"f":-3000000000,"g":18446744073709551615,"h":1500,"i":0.5}"#;
+    let want = r#"{"a":5u8,"b":-129i16,"c":40000u16,"d":70000i32,"e":3000000000u32,"f":-3000000000i64,"g":18446744073709551615u64,"h":1500i16,"i":0.5f64}"#;
+    let jsn = json::parse(doc).unwrap();
+    assert_eq!(
+        text(&SmDtonBuilder::new_from_json_with(&jsn, narrow()).build()),
+        want
+    );
+    let mut map = SmDtonMap::with_options(narrow());
+    map.add_from_json(&jsn);
+    assert_eq!(text(&map.build()), want);
+    assert_eq!(
+        text(
+            &SmDtonBuilder::from_json_str_with(doc, narrow())
+                .unwrap()
+                .build()
+        ),
+        want
+    );
+}
+
+#[test]
+fn narrow_ints_shrink_the_buffer() {
+    let jsn = json::parse(r#"{"list":[1,2,3,4,5,6,7,8],"n":300}"#).unwrap();
+    let wide = SmDtonBuilder::new_from_json(&jsn).build();
+    let small = SmDtonBuilder::new_from_json_with(&jsn, narrow()).build();
+    assert!(small.get_buffer().len() < wide.get_buffer().len());
+    assert_eq!(
+        SmDtonReader::try_new(small.get_buffer())
+            .unwrap()
+            .try_to_json(1)
+            .unwrap(),
+        jsn
+    );
+}
+
+#[test]
+fn integer_getters_widen_narrow_values() {
+    let jsn = json::parse(r#"{"a":5,"b":-129,"c":40000,"d":-70000,"e":3000000000}"#).unwrap();
+    let smb = SmDtonBuilder::new_from_json_with(&jsn, narrow()).build();
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    assert_eq!(reader.get_i64(1, "a"), Some(5));
+    assert_eq!(reader.get_u64(1, "a"), Some(5));
+    assert_eq!(reader.get_i64(1, "b"), Some(-129));
+    assert_eq!(reader.get_u64(1, "b"), None);
+    assert_eq!(reader.get_u64(1, "c"), Some(40000));
+    assert_eq!(reader.get_i64(1, "d"), Some(-70000));
+    assert_eq!(reader.get_i64_by_id(1, 4), Some(3000000000));
+    assert!(matches!(
+        reader.try_get_u64(1, "b"),
+        Err(SmDtonError::OutOfRange { .. })
+    ));
+    assert_eq!(reader.get_i64(1, "missing"), None);
+
+    let dton = SmDton::new_from_buffer(&smb);
+    assert_eq!(dton.get_i64("b"), Some(-129));
+    assert_eq!(dton.get_u64("e"), Some(3000000000));
+    assert_eq!(dton.try_get_i64("c").unwrap(), Some(40000));
+}
+
+#[test]
+fn coercing_getters_convert_exact_values() {
+    let smb = build(r#"{"b":200u8,"s":-5i16,"f":3f64,"h":0.5f32,"big":18446744073709551615u64}"#);
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    assert_eq!(reader.as_i64(1, "b").unwrap(), Some(200));
+    assert_eq!(reader.as_u64(1, "f").unwrap(), Some(3));
+    assert_eq!(reader.as_f64(1, "s").unwrap(), Some(-5.0));
+    assert_eq!(reader.as_f64(1, "h").unwrap(), Some(0.5));
+    assert_eq!(reader.as_i64(1, "missing").unwrap(), None);
+
+    // by index and by value offset reach the same values
+    assert_eq!(reader.as_i64_by_id(1, 1).unwrap(), Some(-5));
+    let voff = reader.get_field_voff(1, "big");
+    assert_eq!(reader.as_u64_by_voff(voff).unwrap(), u64::MAX);
+    assert_eq!(
+        reader.get_int_lossless_by_voff(voff),
+        Some(u64::MAX as i128)
+    );
+
+    assert_eq!(reader.get_int_lossless(1, "s"), Some(-5));
+    assert_eq!(reader.get_int_lossless(1, "f"), Some(3));
+    assert_eq!(reader.get_int_lossless(1, "h"), None);
+    assert_eq!(reader.get_float(1, "b"), Some(200.0));
+    assert_eq!(reader.get_float_by_id(1, 3), Some(0.5));
+}
+
+#[test]
+fn coercing_getters_fail_on_lossy_values() {
+    let smb = build(
+        r#"{"h":0.5f32,"s":-5i16,"big":18446744073709551615u64,"odd":9007199254740993i64,"huge":1e300f64,"name":"x"}"#,
+    );
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    assert!(matches!(
+        reader.as_i64(1, "h"),
+        Err(SmDtonError::InexactNumber)
+    ));
+    assert!(matches!(
+        reader.as_u64(1, "s"),
+        Err(SmDtonError::OutOfRange { .. })
+    ));
+    assert!(matches!(
+        reader.as_i64(1, "big"),
+        Err(SmDtonError::OutOfRange { .. })
+    ));
+    // 2^53 + 1 has no f64
+    assert!(matches!(
+        reader.as_f64(1, "odd"),
+        Err(SmDtonError::InexactNumber)
+    ));
+    assert_eq!(reader.get_float(1, "odd"), None);
+    assert!(matches!(
+        reader.as_i64(1, "huge"),
+        Err(SmDtonError::OutOfRange { .. })
+    ));
+    assert!(matches!(
+        reader.as_f64(1, "name"),
+        Err(SmDtonError::TypeMismatch { .. })
+    ));
+    assert_eq!(reader.get_int_lossless(1, "name"), None);
+}
+
+#[test]
+fn coercing_getters_on_layers() {
+    let base = build(r#"{"a":1u8,"b":0.5f64,"c":7i32}"#);
+    let update = build(r#"{"a":2.0f32,"c":del}"#);
+    let mut dton = SmDton::new_from_buffer(&base);
+    dton.push_layer(update.get_buffer());
+    assert_eq!(dton.as_i64("a").unwrap(), Some(2));
+    assert_eq!(dton.as_u64("c").unwrap(), None);
+    assert!(matches!(dton.as_i64("b"), Err(SmDtonError::InexactNumber)));
+    assert_eq!(dton.as_f64("b").unwrap(), Some(0.5));
+    assert_eq!(dton.get_int_lossless("a"), Some(2));
+    assert_eq!(dton.get_float("b"), Some(0.5));
+}
+
+#[test]
+fn written_exponents_fitting_u64_stay_exact() {
+    let doc = r#"{"a":1844674407370955161e1,"b":-922337203685477580e1}"#;
+    let smb = SmDtonBuilder::from_json_str_with(doc, exact())
+        .unwrap()
+        .build();
+    assert_eq!(
+        text(&smb),
+        r#"{"a":18446744073709551610u64,"b":-9223372036854775800i64}"#
+    );
+}
@@ -0,0 +1,342 @@
+#![cfg(feature = "json")]
+
+use smdton::{
+    SmDton, SmDtonArrayMerge, SmDtonBuffer, SmDtonBuilder, SmDtonError, SmDtonJsonFormat,
+    SmDtonMergeOptions, SmDtonPair, SmDtonReader, SmDtonValue, ST,
+};
+
+const BASE: &str =
+    r#"{"db":{"host":"h","port":5432,"opts":{"a":1,"b":2},"tags":["x","y"]},"name":"n"}"#;
+const UPDATE: &str = r#"{"db":{"port":1,"opts":{"b":3},"tags":["z"]},"extra":true}"#;
+
+fn pair() -> SmDtonPair {
+    let base = SmDtonBuilder::from_json_str(BASE).unwrap().build();
+    let update = SmDtonBuilder::from_json_str(UPDATE).unwrap().build();
+    return SmDtonPair::new(base, update);
+}
+
+fn written(dton: &SmDton) -> json::JsonValue {
+    let mut out = Vec::new();
+    dton.write_json(&mut out, SmDtonJsonFormat::default())
+        .unwrap();
+    return json::parse(std::str::from_utf8(&out).unwrap()).unwrap();
+}
+
+#[test]
+fn shallow_update_replaces_root_entries() {
+    let pair = pair();
+    let dton = SmDton::new_from_pair(&pair);
+    assert_eq!(dton.get_string_path("db.host"), None);
+    assert_eq!(dton.get_i64_path("db.port"), Some(1));
+    assert_eq!(dton.get_string_path("name"), Some("n"));
+
+    let want =
+        json::parse(r#"{"db":{"port":1,"opts":{"b":3},"tags":["z"]},"name":"n","extra":true}"#)
+            .unwrap();
+    assert_eq!(json::parse(&dton.stringify().unwrap()).unwrap(), want);
+    assert_eq!(written(&dton), want);
+}
+
+#[test]
+fn deep_merge_joins_nested_maps() {
+    let pair = pair();
+    let mut dton = SmDton::new_from_pair(&pair);
+    dton.set_merge(SmDtonMergeOptions {
+        deep: true,
+        ..Default::default()
+    });
+    assert_eq!(dton.get_string_path("db.host"), Some("h"));
+    assert_eq!(dton.get_i64_path("db.port"), Some(1));
+    assert_eq!(dton.get_i64_path("db.opts.a"), Some(1));
+    assert_eq!(dton.get_i64_path("db.opts.b"), Some(3));
+    assert_eq!(dton.get_string_pointer("/db/host"), Some("h"));
+    assert_eq!(dton.get_string_path("db.tags[1]"), None);
+
+    let want = json::parse(
+        r#"{"db":{"host":"h","port":1,"opts":{"a":1,"b":3},"tags":["z"]},"name":"n","extra":true}"#,
+    )
+    .unwrap();
+    assert_eq!(json::parse(&dton.stringify().unwrap()).unwrap(), want);
+    assert_eq!(written(&dton), want);
+}
+
+#[test]
+fn deep_merge_concats_arrays() {
+    let pair = pair();
+    let mut dton = SmDton::new_from_pair(&pair);
+    dton.set_merge(SmDtonMergeOptions {
+        deep: true,
+        arrays: SmDtonArrayMerge::Concat,
+    });
+    assert_eq!(dton.get_string_path("db.tags[0]"), Some("x"));
+    assert_eq!(dton.get_string_path("db.tags[2]"), Some("z"));
+    assert_eq!(dton.get_string_pointer("/db/tags/2"), Some("z"));
+    assert_eq!(dton.get_string_path("db.tags[3]"), None);
+
+    let stringified = json::parse(&dton.stringify().unwrap()).unwrap();
+    assert_eq!(stringified["db"]["tags"], json::array!["x", "y", "z"]);
+    assert_eq!(written(&dton), stringified);
+}
+
+#[test]
+fn update_without_base_map() {
+    let base: SmDtonBuffer = SmDtonBuilder::from_json_str(r#"[1,2]"#).unwrap().build();
+    let update = SmDtonBuilder::from_json_str(r#"{"a":{"b":1}}"#)
+        .unwrap()
+        .build();
+    let pair = SmDtonPair::new(base, update);
+    let mut dton = SmDton::new_from_pair(&pair);
+    dton.set_merge(SmDtonMergeOptions {
+        deep: true,
+        ..Default::default()
+    });
+    assert_eq!(dton.get_i64_path("a.b"), Some(1));
+    assert_eq!(written(&dton), json::parse(r#"{"a":{"b":1}}"#).unwrap());
+}
+
+// update removing "name" at the root and "host" inside "db"
+fn tombstones() -> SmDtonBuffer {
+    let mut bd = SmDtonBuilder::new();
+    let root = bd.create_node(ST::SMDT_MAP);
+    let db = bd.create_node(ST::SMDT_MAP);
+    bd.try_add_delete(root, "name").unwrap();
+    bd.try_add_delete(db, "host").unwrap();
+    bd.try_add_node(root, "db", db).unwrap();
+    return bd.build();
+}
+
+#[test]
+fn tombstone_hides_base_key() {
+    let base = SmDtonBuilder::from_json_str(BASE).unwrap().build();
+    let pair = SmDtonPair::new(base, tombstones());
+    let dton = SmDton::new_from_pair(&pair);
+    assert_eq!(dton.get_string("name"), None);
+    assert_eq!(dton.try_get_string("name").unwrap(), None);
+    assert!(!dton.is_null("name"));
+    assert_eq!(dton.get_value_path("name"), None);
+    assert_eq!(dton.get_pointer("/name"), None);
+    assert_eq!(dton.get_string_path("db.host"), None);
+    assert_eq!(dton.get_i64_path("db.port"), None);
+
+    let want = json::parse(r#"{"db":{}}"#).unwrap();
+    assert_eq!(json::parse(&dton.stringify().unwrap()).unwrap(), want);
+    assert_eq!(written(&dton), want);
+}
+
+#[test]
+fn tombstone_in_deep_merge() {
+    let base = SmDtonBuilder::from_json_str(BASE).unwrap().build();
+    let pair = SmDtonPair::new(base, tombstones());
+    let mut dton = SmDton::new_from_pair(&pair);
+    dton.set_merge(SmDtonMergeOptions {
+        deep: true,
+        ..Default::default()
+    });
+    assert_eq!(dton.get_string_path("db.host"), None);
+    assert_eq!(dton.get_string_pointer("/db/host"), None);
+    assert_eq!(dton.get_i64_path("db.port"), Some(5432));
+    assert_eq!(dton.get_string_path("name"), None);
+
+    let want =
+        json::parse(r#"{"db":{"port":5432,"opts":{"a":1,"b":2},"tags":["x","y"]}}"#).unwrap();
+    assert_eq!(json::parse(&dton.stringify().unwrap()).unwrap(), want);
+    assert_eq!(written(&dton), want);
+}
+
+#[test]
+fn combine_applies_tombstones() {
+    let base = SmDtonBuilder::from_json_str(BASE).unwrap().build();
+    let update = tombstones();
+    let lower = SmDton::new_from_buffer(&base);
+    let upper = SmDton::new_from_buffer(&update);
+    let jsn = lower.combine(&upper).unwrap();
+    assert_eq!(jsn, json::parse(r#"{"db":{}}"#).unwrap());
+}
+
+#[test]
+fn tombstone_in_text() {
+    let smb = SmDtonBuilder::from_text_str(r#"{"a":del,"b":1}"#)
+        .unwrap()
+        .build();
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    reader.validate().unwrap();
+    let voff = reader.get_field_voff(1, "a");
+    assert_eq!(reader.get_type_by_voff(voff), Some(ST::SMDT_DEL));
+    assert_eq!(reader.get_value_by_voff(voff), Some(SmDtonValue::Deleted));
+    assert_eq!(reader.try_to_text(1).unwrap(), r#"{"a":del,"b":1i64}"#);
+    assert_eq!(
+        reader.try_to_json(1).unwrap(),
+        json::parse(r#"{"b":1}"#).unwrap()
+    );
+
+    assert!(SmDtonBuilder::from_text_str(r#"[del]"#).is_err());
+    assert!(SmDtonBuilder::from_json_str(r#"{"a":del}"#).is_err());
+}
+
+// defaults, site, user and runtime layers
+fn config_stack() -> SmDtonPair {
+    let mut stack = SmDtonPair::default();
+    for text in [
+        r#"{"port":80i64,"log":{"level":"warn","file":"a.log"},"user":"root"}"#,
+        r#"{"log":{"level":"info"}}"#,
+        r#"{"user":"me","theme":"dark"}"#,
+        r#"{"theme":del,"port":8080i64}"#,
+    ] {
+        stack.push(SmDtonBuilder::from_text_str(text).unwrap().build());
+    }
+    return stack;
+}
+
+#[test]
+fn stack_resolves_top_down() {
+    let stack = config_stack();
+    assert_eq!(stack.len(), 4);
+    let mut dton = SmDton::try_new_from_pair(&stack).unwrap();
+    assert_eq!(dton.layer_count(), 4);
+    assert_eq!(dton.get_i64("port"), Some(8080));
+    assert_eq!(dton.get_string("user"), Some("me".to_string()));
+    assert_eq!(dton.get_string("theme"), None);
+    assert_eq!(dton.get_string_path("log.file"), None);
+    assert_eq!(dton.get_string_path("log.level"), Some("info"));
+
+    dton.set_merge(SmDtonMergeOptions {
+        deep: true,
+        ..Default::default()
+    });
+    assert_eq!(dton.get_string_path("log.file"), Some("a.log"));
+    let want =
+        json::parse(r#"{"port":8080,"log":{"level":"info","file":"a.log"},"user":"me"}"#).unwrap();
+    assert_eq!(json::parse(&dton.stringify().unwrap()).unwrap(), want);
+    assert_eq!(written(&dton), want);
+
+    assert!(dton.pop_layer().is_some());
+    assert_eq!(dton.layer_count(), 3);
+    assert_eq!(dton.get_i64("port"), Some(80));
+    assert_eq!(dton.get_string("theme"), Some("dark".to_string()));
+    dton.push_layer(stack.layers[3].get_buffer());
+    assert_eq!(dton.get_i64("port"), Some(8080));
+}
+
+#[test]
+fn layer_of_reports_provenance() {
+    let stack = config_stack();
+    let mut dton = SmDton::new_from_pair(&stack);
+    assert_eq!(dton.layer_of("port"), Some(3));
+    assert_eq!(dton.layer_of("user"), Some(2));
+    assert_eq!(dton.layer_of("log.level"), Some(1));
+    assert_eq!(dton.layer_of("log.file"), None);
+    assert_eq!(dton.layer_of("theme"), None);
+    assert_eq!(dton.layer_of("missing"), None);
+    assert_eq!(dton.layer_of_pointer("/log/level"), Some(1));
+    assert!(dton.try_layer_of("log[").is_err());
+
+    dton.set_merge(SmDtonMergeOptions {
+        deep: true,
+        ..Default::default()
+    });
+    assert_eq!(dton.layer_of("log"), Some(1));
+    assert_eq!(dton.layer_of("log.file"), Some(0));
+    assert_eq!(dton.layer_of_pointer("/log/file"), Some(0));
+}
+
+#[test]
+fn pair_grows_into_a_stack() {
+    let mut stack = pair();
+    assert_eq!(stack.len(), 2);
+    stack.push(
+        SmDtonBuilder::from_text_str(r#"{"name":"top"}"#)
+            .unwrap()
+            .build(),
+    );
+    let dton = SmDton::new_from_pair(&stack);
+    assert_eq!(dton.layer_count(), 3);
+    assert_eq!(dton.get_string("name"), Some("top".to_string()));
+    assert_eq!(dton.layer_of("name"), Some(2));
+
+    assert!(stack.pop().is_some());
+    let dton = SmDton::new_from_pair(&stack);
+    assert_eq!(dton.stringify(), SmDton::new_from_pair(&pair()).stringify());
+}
+
+#[test]
+fn index_paths_resolve_top_down() {
+    let mut stack = SmDtonPair::default();
+    for text in [r#"["a","b","c"]"#, r#"["x"]"#] {
+        stack.push(SmDtonBuilder::from_text_str(text).unwrap().build());
+    }
+    let dton = SmDton::new_from_pair(&stack);
+    assert_eq!(dton.get_string_path("[0]"), Some("x"));
+    assert_eq!(dton.layer_of("[0]"), Some(1));
+    assert_eq!(dton.layer_of_pointer("/0"), Some(1));
+    assert_eq!(dton.get_string_path("[2]"), Some("c"));
+    assert_eq!(dton.layer_of("[2]"), Some(0));
+    assert_eq!(dton.layer_of("[3]"), None);
+}
+
+#[test]
+fn stringify_is_pretty_alone_and_compact_layered() {
+    let base = SmDtonBuilder::from_json_str(BASE).unwrap().build();
+    let single = SmDton::new_from_buffer(&base);
+    let mut out = Vec::new();
+    let pretty = SmDtonJsonFormat {
+        pretty: true,
+        ..Default::default()
+    };
+    single.write_json(&mut out, pretty).unwrap();
+    assert_eq!(single.stringify(), Some(String::from_utf8(out).unwrap()));
+    assert_eq!(single.try_stringify().unwrap(), single.stringify());
+    assert_eq!(base.stringify(), single.stringify());
+    assert!(single
+        .stringify()
+        .unwrap()
+        .contains("\n    \"name\": \"n\""));
+
+    let pair = pair();
+    let layered = SmDton::new_from_pair(&pair);
+    let mut out = Vec::new();
+    layered
+        .write_json(&mut out, SmDtonJsonFormat::default())
+        .unwrap();
+    assert_eq!(layered.stringify(), Some(String::from_utf8(out).unwrap()));
+    assert!(!layered.stringify().unwrap().contains('\n'));
+}
+
+#[test]
+fn stringify_reports_broken_layers() {
+    let base = SmDtonBuilder::from_json_str(BASE).unwrap().build();
+    let mut dton = SmDton::new_from_buffer(&base);
+    let broken = [0u8, 1, 2];
+    dton.push_layer(&broken);
+    assert!(dton.try_stringify().is_err());
+    assert_eq!(dton.stringify(), None);
+}
+
+#[test]
+fn tombstone_in_array_is_invalid_and_skipped() {
+    let smb = SmDtonBuilder::from_json_str(r#"{"a":[1,null]}"#)
+        .unwrap()
+        .build();
+    let reader = SmDtonReader::new(smb.get_buffer());
+    let list = reader.get_node_id(1, "a").unwrap();
+    let voff = reader.get_sub_voff(list, 1);
+    let mut buf = smb.get_buffer().to_vec();
+    buf[voff] = ST::SMDT_DEL;
+
+    let reader = SmDtonReader::new(&buf);
+    assert!(matches!(
+        reader.validate(),
+        Err(SmDtonError::InvalidValueType {
+            found: ST::SMDT_DEL,
+            ..
+        })
+    ));
+    assert_eq!(
+        reader.try_to_json(1).unwrap(),
+        json::parse(r#"{"a":[1]}"#).unwrap()
+    );
+    let mut out = Vec::new();
+    reader
+        .write_json(1, &mut out, SmDtonJsonFormat::default())
+        .unwrap();
+    assert_eq!(out, br#"{"a":[1]}"#);
+}
@@ -0,0 +1,151 @@
+#![cfg(feature = "json")]
+
+mod common;
+
+use common::{build, text};
+use smdton::{
+    apply_json_patch, apply_merge_patch, parse_json_patch, try_apply_merge_patch, SmDtonError,
+    SmDtonPatchOp, SmDtonReader,
+};
+
+const DOC: &str = r#"{"db":{"host":"h","port":5432u16,"tags":["x","y"]},"key":b64"AAE=","ratio":0.5f32,"id":7u8}"#;
+
+fn ops(patch: &str) -> Vec<SmDtonPatchOp<json::JsonValue>> {
+    return parse_json_patch(&json::parse(patch).unwrap()).unwrap();
+}
+
+#[test]
+fn merge_patch_keeps_untouched_types() {
+    let smb = build(DOC);
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    let patch = json::parse(
+        r#"{"db":{"host":null,"port":6000,"user":{"name":"u","x":null}},"id":null,"new":[1,null]}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        text(&apply_merge_patch(&reader, &patch)),
+        r#"{"db":{"port":6000i64,"tags":["x","y"],"user":{"name":"u"}},"key":b64"AAE=","ratio":0.5f32,"new":[1i64,null]}"#
+    );
+}
+
+#[test]
+fn merge_patch_replaces_non_objects() {
+    let smb = build(r#"{"a":[1u8,2u8],"b":"s"}"#);
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    let patch = json::parse(r#"{"a":{"k":"$B64$AAE="},"b":["z"]}"#).unwrap();
+    assert_eq!(
+        text(&apply_merge_patch(&reader, &patch)),
+        r#"{"a":{"k":b64"AAE="},"b":["z"]}"#
+    );
+
+    let scalar = json::parse("3").unwrap();
+    assert!(matches!(
+        try_apply_merge_patch(&reader, &scalar),
+        Err(SmDtonError::NotANode { .. })
+    ));
+    assert!(apply_merge_patch(&reader, &scalar).get_buffer().is_empty());
+}
+
+#[test]
+fn merge_patch_applies_tombstones_of_the_document() {
+    let smb = build(r#"{"a":1u8,"a":2u8,"b":del}"#);
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    let patch = json::parse(r#"{"c":true}"#).unwrap();
+    assert_eq!(
+        text(&apply_merge_patch(&reader, &patch)),
+        r#"{"a":2u8,"c":true}"#
+    );
+}
+
+#[test]
+fn json_patch_operations() {
+    let smb = build(DOC);
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    let patch = ops(r#"[
+            {"op":"test","path":"/db/port","value":5432},
+            {"op":"test","path":"/db","value":{"host":"h","port":5432,"tags":["x","y"]}},
+            {"op":"test","path":"/ratio","value":0.5},
+            {"op":"add","path":"/db/tags/1","value":"w"},
+            {"op":"add","path":"/db/tags/-","value":"z"},
+            {"op":"remove","path":"/db/host"},
+            {"op":"replace","path":"/id","value":8},
+            {"op":"copy","from":"/key","path":"/db/key"},
+            {"op":"move","from":"/ratio","path":"/db/ratio"},
+            {"op":"add","path":"/a~1b","value":{"c":null}}
+        ]"#);
+    let out = apply_json_patch(&reader, &patch).unwrap();
+    assert_eq!(
+        text(&out),
+        r#"{"db":{"port":5432u16,"tags":["x","w","y","z"],"key":b64"AAE=","ratio":0.5f32},"key":b64"AAE=","id":8i64,"a/b":{"c":null}}"#
+    );
+}
+
+#[test]
+fn json_patch_failures() {
+    let smb = build(DOC);
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    let fails = |patch: &str| match apply_json_patch(&reader, &ops(patch)) {
+        Err(SmDtonError::PatchFailed { index, path }) => (index, path),
+        other => panic!("{:?}", other.map(|smb| text(&smb))),
+    };
+
+    assert_eq!(
+        fails(r#"[{"op":"test","path":"/id","value":7},{"op":"test","path":"/id","value":"7"}]"#),
+        (1, "/id".to_string())
+    );
+    assert_eq!(
+        fails(r#"[{"op":"remove","path":"/nope"}]"#),
+        (0, "/nope".to_string())
+    );
+    assert_eq!(
+        fails(r#"[{"op":"replace","path":"/db/tags/2","value":1}]"#),
+        (0, "/db/tags/2".to_string())
+    );
+    assert_eq!(
+        fails(r#"[{"op":"add","path":"/db/tags/3","value":1}]"#),
+        (0, "/db/tags/3".to_string())
+    );
+    assert_eq!(
+        fails(r#"[{"op":"move","from":"/db","path":"/db/inner"}]"#),
+        (0, "/db/inner".to_string())
+    );
+    assert_eq!(
+        fails(r#"[{"op":"add","path":"/id/x","value":1}]"#),
+        (0, "/id/x".to_string())
+    );
+
+    assert!(matches!(
+        apply_json_patch(&reader, &ops(r#"[{"op":"remove","path":"id"}]"#)),
+        Err(SmDtonError::InvalidPatch { index: 0 })
+    ));
+    assert!(matches!(
+        parse_json_patch(&json::parse(r#"[{"op":"add","path":"/a"}]"#).unwrap()),
+        Err(SmDtonError::InvalidPatch { index: 0 })
+    ));
+    assert!(matches!(
+        apply_json_patch(&reader, &ops(r#"[{"op":"replace","path":"","value":1}]"#)),
+        Err(SmDtonError::NotANode { .. })
+    ));
+}
+
+#[test]
+fn json_patch_on_root_array() {
+    let smb = build(r#"[1u8,{"k":-2i16}]"#);
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    let patch = vec![
+        SmDtonPatchOp::Test {
+            path: "/1/k".to_string(),
+            value: json::JsonValue::from(-2),
+        },
+        SmDtonPatchOp::Copy {
+            from: "/1".to_string(),
+            path: "/0".to_string(),
+        },
+        SmDtonPatchOp::Move {
+            from: "/1".to_string(),
+            path: "/-".to_string(),
+        },
+    ];
+    let out = apply_json_patch(&reader, &patch).unwrap();
+    assert_eq!(text(&out), r#"[{"k":-2i16},{"k":-2i16},1u8]"#);
+}
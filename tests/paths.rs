@@ -0,0 +1,249 @@
+#![cfg(feature = "json")]
+
+mod common;
+
+use common::build;
+use smdton::{
+    SmDton, SmDtonError, SmDtonMergeOptions, SmDtonPath, SmDtonPathSeg, SmDtonReader, SmDtonValue,
+};
+
+const DOC: &str = r#"{"a":1i64,"log":[{"lvl":2i64}],"m":{"k":"v"}}"#;
+
+#[test]
+fn reader_paths_through_scalars_are_absent() {
+    let smb = build(DOC);
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    for path in ["a.b", "a[0]", "log.lvl", "log[1].lvl", "m.k.x"] {
+        assert_eq!(reader.try_get_i64_path(1, path).unwrap(), None, "{}", path);
+        assert_eq!(reader.get_i64_path(1, path), None, "{}", path);
+    }
+    for pointer in ["/a/b", "/log/lvl", "/log/1/lvl", "/m/k/x"] {
+        assert_eq!(
+            reader.try_get_i64_pointer(1, pointer).unwrap(),
+            None,
+            "{}",
+            pointer
+        );
+        assert_eq!(reader.get_i64_pointer(1, pointer), None, "{}", pointer);
+    }
+    assert_eq!(reader.try_get_i64_path(1, "log[0].lvl").unwrap(), Some(2));
+    assert_eq!(
+        reader.try_get_i64_pointer(1, "/log/0/lvl").unwrap(),
+        Some(2)
+    );
+
+    assert!(reader.try_get_i64_path(1, "log[").is_err());
+    assert!(reader.try_get_i64_pointer(1, "a").is_err());
+}
+
+#[test]
+fn layered_paths_through_scalars_are_absent() {
+    let base = build(DOC);
+    let update = build(r#"{"m":{"j":"w"}}"#);
+    for deep in [false, true] {
+        let mut dton = SmDton::new_from_buffer(&base);
+        dton.push_layer(update.get_buffer());
+        dton.set_merge(SmDtonMergeOptions {
+            deep: deep,
+            ..Default::default()
+        });
+        for path in ["a.b", "log.lvl", "log[1].lvl", "m.j.x"] {
+            assert_eq!(dton.try_get_i64_path(path).unwrap(), None, "{}", path);
+            assert_eq!(dton.get_i64_path(path), None, "{}", path);
+        }
+        for pointer in ["/a/b", "/log/lvl", "/m/j/x"] {
+            assert_eq!(
+                dton.try_get_i64_pointer(pointer).unwrap(),
+                None,
+                "{}",
+                pointer
+            );
+        }
+        assert_eq!(dton.try_get_i64_path("log[0].lvl").unwrap(), Some(2));
+        assert!(dton.try_get_i64_path("log[").is_err());
+    }
+}
+
+#[test]
+fn pointer_of_reuses_the_parent_table() {
+    let smb = build(r#"{"a/b":{"x~":[1u8,{"y":true}]},"c":[]}"#);
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    let node = reader.get_node_id_pointer(1, "/a~1b/x~0/1").unwrap();
+    assert_eq!(
+        reader.pointer_of(node, 0),
+        Some("/a~1b/x~0/1/y".to_string())
+    );
+    assert_eq!(
+        reader.pointer_of(node, 0),
+        Some("/a~1b/x~0/1/y".to_string())
+    );
+    assert_eq!(reader.pointer_of(node, 1), None);
+
+    let copy = reader.clone();
+    let list = copy.get_node_id_path(1, "c").unwrap();
+    assert_eq!(copy.pointer_of(1, 1), Some("/c".to_string()));
+    assert_eq!(copy.pointer_of(list, 0), None);
+}
+
+#[test]
+fn path_strings_parse_into_segments() {
+    let path = SmDtonPath::parse(r"servers[2].port").unwrap();
+    assert_eq!(
+        path.segs(),
+        [
+            SmDtonPathSeg::Key("servers"),
+            SmDtonPathSeg::Index(2),
+            SmDtonPathSeg::Key("port")
+        ]
+    );
+    let path = SmDtonPath::parse(r"a\.b.c\[0\]\\[1][0]").unwrap();
+    assert_eq!(
+        path.segs(),
+        [
+            SmDtonPathSeg::Key("a.b"),
+            SmDtonPathSeg::Key(r"c[0]\"),
+            SmDtonPathSeg::Index(1),
+            SmDtonPathSeg::Index(0)
+        ]
+    );
+    assert_eq!(
+        SmDtonPath::parse("[3]").unwrap().segs(),
+        [SmDtonPathSeg::Index(3)]
+    );
+
+    for (bad, offset) in [
+        ("a[x]", 2),
+        ("a[1", 3),
+        ("a]", 1),
+        (r"a\q", 1),
+        ("a[0]b", 4),
+        ("a[]", 2),
+    ] {
+        assert_eq!(
+            SmDtonPath::parse(bad),
+            Err(SmDtonError::InvalidPath { offset: offset }),
+            "{}",
+            bad
+        );
+    }
+}
+
+#[test]
+fn reader_paths_reach_nested_values() {
+    let smb = build(
+        r#"{"servers":[{"port":80u16},{"port":443u16},{"port":8080u16,"host":"c"}],"a.b":{"c[0]":7i64}}"#,
+    );
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    assert_eq!(reader.get_u16_path(1, "servers[2].port"), Some(8080));
+    assert_eq!(reader.get_string_path(1, "servers[2].host"), Some("c"));
+    assert_eq!(reader.get_i64_path(1, r"a\.b.c\[0\]"), Some(7));
+    assert_eq!(reader.get_i64_path(1, "a.b"), None);
+    assert_eq!(reader.get_u16_path(1, "servers[3].port"), None);
+    assert_eq!(reader.get_string_path(1, "servers[0].port"), None);
+    assert!(matches!(
+        reader.try_get_string_path(1, "servers[0].port"),
+        Err(SmDtonError::TypeMismatch { .. })
+    ));
+
+    let segs = [
+        SmDtonPathSeg::Key("servers"),
+        SmDtonPathSeg::Index(1),
+        SmDtonPathSeg::Key("port"),
+    ];
+    assert_eq!(reader.get_path(1, &segs), Some(SmDtonValue::U16(443)));
+    assert_eq!(
+        reader.try_get_path(1, &segs).unwrap(),
+        Some(SmDtonValue::U16(443))
+    );
+    let list = reader.get_node_id_path(1, "servers").unwrap();
+    assert_eq!(
+        reader.get_path(list, &[SmDtonPathSeg::Index(0), SmDtonPathSeg::Key("port")]),
+        Some(SmDtonValue::U16(80))
+    );
+}
+
+#[test]
+fn layered_paths_take_the_top_layer() {
+    let base = build(r#"{"servers":[{"port":80u16}],"db":{"host":"a","port":1u16}}"#);
+    let update = build(r#"{"db":{"port":2u16},"servers":del}"#);
+    let mut dton = SmDton::new_from_buffer(&base);
+    assert_eq!(dton.get_u16_path("servers[0].port"), Some(80));
+    dton.push_layer(update.get_buffer());
+    assert_eq!(dton.get_u16_path("db.port"), Some(2));
+    assert_eq!(dton.get_u16_path("servers[0].port"), None);
+    // shallow merge, the top map hides the lower one
+    assert_eq!(dton.get_string_path("db.host"), None);
+    assert_eq!(
+        dton.get_path(&[SmDtonPathSeg::Key("db"), SmDtonPathSeg::Key("port")]),
+        Some(SmDtonValue::U16(2))
+    );
+
+    dton.set_merge(SmDtonMergeOptions {
+        deep: true,
+        ..Default::default()
+    });
+    assert_eq!(dton.get_string_path("db.host"), Some("a"));
+}
+
+#[test]
+fn pointers_unescape_tokens() {
+    let smb =
+        build(r#"{"a/b":1i64,"m~n":2i64,"~1":3i64,"":4i64,"list":[10i64,11i64],"0":{"k":"v"}}"#);
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    assert_eq!(reader.get_i64_pointer(1, "/a~1b"), Some(1));
+    assert_eq!(reader.get_i64_pointer(1, "/m~0n"), Some(2));
+    // ~01 is ~ then 1, not /
+    assert_eq!(reader.get_i64_pointer(1, "/~01"), Some(3));
+    assert_eq!(reader.get_i64_pointer(1, "/"), Some(4));
+    assert_eq!(reader.get_i64_pointer(1, "/list/1"), Some(11));
+    assert_eq!(reader.get_string_pointer(1, "/0/k"), Some("v"));
+    assert_eq!(reader.get_pointer(1, "/list/0"), Some(SmDtonValue::I64(10)));
+
+    // "-" and leading zeros name no array element
+    for pointer in ["/list/-", "/list/01", "/list/2", "/a/b"] {
+        assert_eq!(
+            reader.try_get_i64_pointer(1, pointer).unwrap(),
+            None,
+            "{}",
+            pointer
+        );
+    }
+    assert_eq!(
+        reader.try_get_i64_pointer(1, "list"),
+        Err(SmDtonError::InvalidPath { offset: 0 })
+    );
+    assert_eq!(
+        reader.try_get_i64_pointer(1, "/list/~2"),
+        Err(SmDtonError::InvalidPath { offset: 6 })
+    );
+    assert_eq!(reader.get_i64_pointer(1, "/m~n"), None);
+}
+
+#[test]
+fn pointer_of_names_entries_and_items() {
+    let smb = build(r#"{"a/b":{"x~":[1u8,{"y":true}]},"plain":0u8}"#);
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    assert_eq!(reader.pointer_of(1, 0), Some("/a~1b".to_string()));
+    assert_eq!(reader.pointer_of(1, 1), Some("/plain".to_string()));
+    assert_eq!(reader.pointer_of(1, 2), None);
+    let list = reader.get_node_id_pointer(1, "/a~1b/x~0").unwrap();
+    let pointer = reader.pointer_of(list, 0).unwrap();
+    assert_eq!(pointer, "/a~1b/x~0/0");
+    // the pointer leads back to the same value
+    assert_eq!(reader.get_u8_pointer(1, &pointer), Some(1));
+}
+
+#[test]
+fn layered_pointers_and_their_layer() {
+    let base = build(r#"{"a/b":1i64,"list":[1i64,2i64],"keep":true}"#);
+    let update = build(r#"{"a/b":5i64,"list":del}"#);
+    let mut dton = SmDton::new_from_buffer(&base);
+    dton.push_layer(update.get_buffer());
+    assert_eq!(dton.get_i64_pointer("/a~1b"), Some(5));
+    assert_eq!(dton.layer_of_pointer("/a~1b"), Some(1));
+    assert_eq!(dton.get_bool_pointer("/keep"), Some(true));
+    assert_eq!(dton.layer_of_pointer("/keep"), Some(0));
+    assert_eq!(dton.get_pointer("/list/0"), None);
+    assert_eq!(dton.layer_of_pointer("/list/0"), None);
+    assert!(dton.try_get_pointer("keep").is_err());
+}
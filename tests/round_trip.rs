@@ -0,0 +1,253 @@
+#![cfg(feature = "json")]
+
+use smdton::{SmDtonBuffer, SmDtonBuilder, SmDtonJsonFormat, SmDtonReader, SmDtonValue, ST};
+
+const BIN: &[u8] = &[0, 1, 2, 0xfe, 0xff];
+
+// one value of every type, the last two are the nested map and array
+const TYPES: [u8; 15] = [
+    ST::SMDT_NUL,
+    ST::SMDT_BOO,
+    ST::SMDT_UI8,
+    ST::SMDT_I16,
+    ST::SMDT_U16,
+    ST::SMDT_I32,
+    ST::SMDT_U32,
+    ST::SMDT_F32,
+    ST::SMDT_I64,
+    ST::SMDT_U64,
+    ST::SMDT_F64,
+    ST::SMDT_STR,
+    ST::SMDT_BIN,
+    ST::SMDT_MAP,
+    ST::SMDT_ARR,
+];
+
+const KEYS: [&str; 15] = [
+    "nul", "boo", "ui8", "i16", "u16", "i32", "u32", "f32", "i64", "u64", "f64", "str", "bin",
+    "map", "arr",
+];
+
+fn expected(index: usize) -> Option<SmDtonValue<'static>> {
+    let value = match TYPES[index] {
+        ST::SMDT_NUL => SmDtonValue::Null,
+        ST::SMDT_BOO => SmDtonValue::Bool(true),
+        ST::SMDT_UI8 => SmDtonValue::U8(200),
+        ST::SMDT_I16 => SmDtonValue::I16(-30000),
+        ST::SMDT_U16 => SmDtonValue::U16(60000),
+        ST::SMDT_I32 => SmDtonValue::I32(-2_000_000_000),
+        ST::SMDT_U32 => SmDtonValue::U32(4_000_000_000),
+        ST::SMDT_F32 => SmDtonValue::F32(0.25),
+        ST::SMDT_I64 => SmDtonValue::I64(i64::MIN),
+        ST::SMDT_U64 => SmDtonValue::U64(u64::MAX),
+        ST::SMDT_F64 => SmDtonValue::F64(-1.5e300),
+        ST::SMDT_STR => SmDtonValue::Str("é\"\n"),
+        ST::SMDT_BIN => SmDtonValue::Bin(BIN),
+        _ => return None,
+    };
+    return Some(value);
+}
+
+// a root map and an array both holding every type, the array sits under "arr"
+fn build_all() -> SmDtonBuffer {
+    let mut bd = SmDtonBuilder::new();
+    let root = bd.create_node(ST::SMDT_MAP);
+    let arr = bd.create_node(ST::SMDT_ARR);
+    let inner_map = bd.create_node(ST::SMDT_MAP);
+    let leaf_map = bd.create_node(ST::SMDT_MAP);
+    let leaf_arr = bd.create_node(ST::SMDT_ARR);
+    bd.try_add_u8(inner_map, "k", 1).unwrap();
+
+    for (index, key) in KEYS.iter().enumerate() {
+        match expected(index) {
+            Some(SmDtonValue::Null) => {
+                bd.try_add_null(root, key).unwrap();
+                bd.try_push_null(arr).unwrap();
+            }
+            Some(SmDtonValue::Bool(v)) => {
+                bd.try_add_bool(root, key, v).unwrap();
+                bd.try_push_bool(arr, v).unwrap();
+            }
+            Some(SmDtonValue::U8(v)) => {
+                bd.try_add_u8(root, key, v).unwrap();
+                bd.try_push_u8(arr, v).unwrap();
+            }
+            Some(SmDtonValue::I16(v)) => {
+                bd.try_add_i16(root, key, v).unwrap();
+                bd.try_push_i16(arr, v).unwrap();
+            }
+            Some(SmDtonValue::U16(v)) => {
+                bd.try_add_u16(root, key, v).unwrap();
+                bd.try_push_u16(arr, v).unwrap();
+            }
+            Some(SmDtonValue::I32(v)) => {
+                bd.try_add_i32(root, key, v).unwrap();
+                bd.try_push_i32(arr, v).unwrap();
+            }
+            Some(SmDtonValue::U32(v)) => {
+                bd.try_add_u32(root, key, v).unwrap();
+                bd.try_push_u32(arr, v).unwrap();
+            }
+            Some(SmDtonValue::F32(v)) => {
+                bd.try_add_f32(root, key, v).unwrap();
+                bd.try_push_f32(arr, v).unwrap();
+            }
+            Some(SmDtonValue::I64(v)) => {
+                bd.try_add_i64(root, key, v).unwrap();
+                bd.try_push_i64(arr, v).unwrap();
+            }
+            Some(SmDtonValue::U64(v)) => {
+                bd.try_add_u64(root, key, v).unwrap();
+                bd.try_push_u64(arr, v).unwrap();
+            }
+            Some(SmDtonValue::F64(v)) => {
+                bd.try_add_f64(root, key, v).unwrap();
+                bd.try_push_f64(arr, v).unwrap();
+            }
+            Some(SmDtonValue::Str(v)) => {
+                bd.try_add_string(root, key, v).unwrap();
+                bd.try_push_string(arr, v).unwrap();
+            }
+            Some(SmDtonValue::Bin(v)) => {
+                bd.try_add_bin(root, key, v).unwrap();
+                bd.try_push_bin(arr, v).unwrap();
+            }
+            _ if TYPES[index] == ST::SMDT_MAP => {
+                bd.try_add_node(root, key, inner_map).unwrap();
+                bd.try_push_node(arr, leaf_map).unwrap();
+            }
+            _ => {
+                bd.try_add_node(root, key, arr).unwrap();
+                bd.try_push_node(arr, leaf_arr).unwrap();
+            }
+        }
+    }
+    return bd.build();
+}
+
+fn check_value(reader: &SmDtonReader, voff: usize, index: usize, what: &str) {
+    assert_eq!(
+        reader.get_type_by_voff(voff),
+        Some(TYPES[index]),
+        "{}",
+        what
+    );
+    let value = reader.try_get_value_by_voff(voff).unwrap();
+    match expected(index) {
+        Some(want) => assert_eq!(value, want, "{}", what),
+        None => assert!(value.node_id().is_some(), "{}", what),
+    }
+}
+
+fn array_oid(reader: &SmDtonReader) -> usize {
+    let voff = reader.get_field_voff(1, "arr");
+    return reader
+        .try_get_value_by_voff(voff)
+        .unwrap()
+        .node_id()
+        .unwrap();
+}
+
+#[test]
+fn every_type_in_map_and_array() {
+    let smb = build_all();
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    reader.validate().unwrap();
+
+    assert_eq!(reader.node_sub_num(1), KEYS.len());
+    for (index, key) in KEYS.iter().enumerate() {
+        check_value(&reader, reader.get_field_voff(1, key), index, key);
+    }
+
+    let arr = array_oid(&reader);
+    assert_eq!(reader.node_type(arr), ST::SMDT_ARR);
+    assert_eq!(reader.node_sub_num(arr), KEYS.len());
+    for index in 0..KEYS.len() {
+        let voff = reader.try_get_sub_voff(arr, index).unwrap().unwrap();
+        check_value(&reader, voff, index, &format!("arr[{}]", index));
+    }
+}
+
+#[test]
+fn binary_survives_json_in_map_and_array() {
+    let smb = build_all();
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    let jsn = reader.try_to_json(1).unwrap();
+    let text = format!("$B64${}", "AAEC/v8=");
+    assert_eq!(jsn["bin"], text.as_str());
+    assert_eq!(jsn["arr"][12], text.as_str());
+
+    let back = SmDtonBuilder::try_new_from_json(&jsn).unwrap().build();
+    let again = SmDtonReader::try_new(back.get_buffer()).unwrap();
+    assert_eq!(again.get_bin(1, "bin"), Some(BIN));
+    let arr = array_oid(&again);
+    let voff = again.try_get_sub_voff(arr, 12).unwrap().unwrap();
+    assert_eq!(again.get_bin_by_voff(voff), Some(BIN));
+    assert_eq!(again.try_to_json(1).unwrap(), jsn);
+
+    let streamed = SmDtonBuilder::from_json_str(&jsn.dump()).unwrap().build();
+    assert_eq!(streamed.get_buffer(), back.get_buffer());
+}
+
+#[test]
+fn json_round_trip_is_stable() {
+    let smb = build_all();
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    let jsn = reader.try_to_json(1).unwrap();
+    assert_eq!(jsn["arr"].len(), KEYS.len());
+
+    let back = SmDtonBuilder::try_new_from_json(&jsn).unwrap().build();
+    let again = SmDtonReader::try_new(back.get_buffer()).unwrap();
+    assert_eq!(again.try_to_json(1).unwrap(), jsn);
+
+    let mut out = Vec::new();
+    reader
+        .write_json(1, &mut out, SmDtonJsonFormat::default())
+        .unwrap();
+    let written = json::parse(std::str::from_utf8(&out).unwrap()).unwrap();
+    assert_eq!(written, jsn);
+}
+
+#[test]
+fn text_round_trip_keeps_types() {
+    let smb = build_all();
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    let text = reader.try_to_text(1).unwrap();
+
+    let back = SmDtonBuilder::from_text_str(&text).unwrap().build();
+    let again = SmDtonReader::try_new(back.get_buffer()).unwrap();
+    for (index, key) in KEYS.iter().enumerate() {
+        check_value(&again, again.get_field_voff(1, key), index, key);
+    }
+    let arr = array_oid(&again);
+    for index in 0..KEYS.len() {
+        let voff = again.try_get_sub_voff(arr, index).unwrap().unwrap();
+        check_value(&again, voff, index, &format!("arr[{}]", index));
+    }
+    assert_eq!(again.try_to_text(1).unwrap(), text);
+
+    let mut out = Vec::new();
+    let format = SmDtonJsonFormat {
+        pretty: true,
+        ..Default::default()
+    };
+    reader.write_text(1, &mut out, format).unwrap();
+    let pretty = SmDtonBuilder::from_text_reader(out.as_slice())
+        .unwrap()
+        .build();
+    assert_eq!(pretty.get_buffer(), back.get_buffer());
+}
+
+#[test]
+fn invalid_base64_in_array() {
+    let jsn = json::parse(r#"{"a":["$B64$AAEC","$B64$!!"]}"#).unwrap();
+    assert!(SmDtonBuilder::try_new_from_json(&jsn).is_err());
+    assert!(SmDtonBuilder::from_json_str(&jsn.dump()).is_err());
+
+    let mut bd = SmDtonBuilder::new_from_json(&jsn);
+    let smb = bd.build();
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    let jsn = reader.try_to_json(1).unwrap();
+    assert_eq!(jsn["a"][0], "$B64$AAEC");
+    assert_eq!(jsn["a"][1], "$B64$!!");
+}
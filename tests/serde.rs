@@ -0,0 +1,397 @@
+#![cfg(all(feature = "json", feature = "serde"))]
+
+mod common;
+
+use common::{build, text};
+use serde::{Deserialize, Serialize, Serializer};
+use smdton::{
+    from_buffer, from_dton, from_reader, to_buffer, to_buffer_with, SmDton, SmDtonArrayMerge,
+    SmDtonBuilder, SmDtonError, SmDtonMergeOptions, SmDtonOptions, SmDtonPair, SmDtonReader, ST,
+};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Db<'a> {
+    host: Option<&'a str>,
+    port: u16,
+    tags: Vec<&'a str>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Config<'a> {
+    #[serde(borrow)]
+    db: Db<'a>,
+    name: &'a str,
+}
+
+const BASE: &str = r#"{"db":{"host":"h","port":5432u16,"tags":["x","y"]},"name":"n"}"#;
+const UPDATE: &str = r#"{"db":{"port":6000u16,"tags":["z"]}}"#;
+
+#[test]
+fn from_dton_follows_deep_merge() {
+    let base = build(BASE);
+    let update = build(UPDATE);
+    let mut dton = SmDton::new_from_buffer(&base);
+    dton.push_layer(update.get_buffer());
+    dton.set_merge(SmDtonMergeOptions {
+        deep: true,
+        ..Default::default()
+    });
+    let config: Config = from_dton(&dton).unwrap();
+    assert_eq!(
+        config,
+        Config {
+            db: Db {
+                host: Some("h"),
+                port: 6000,
+                tags: vec!["z"],
+            },
+            name: "n",
+        }
+    );
+
+    dton.set_merge(SmDtonMergeOptions {
+        deep: true,
+        arrays: SmDtonArrayMerge::Concat,
+    });
+    let config: Config = from_dton(&dton).unwrap();
+    assert_eq!(config.db.tags, vec!["x", "y", "z"]);
+}
+
+#[test]
+fn from_dton_agrees_with_flatten_and_lookups() {
+    let base = build(BASE);
+    // the repeated key within one layer replaces, the layers merge
+    let update = build(r#"{"db":{"port":1u16,"tags":["z"],"port":6000u16,"host":del}}"#);
+    let mut dton = SmDton::new_from_buffer(&base);
+    dton.push_layer(update.get_buffer());
+    for merge in [
+        SmDtonMergeOptions::default(),
+        SmDtonMergeOptions {
+            deep: true,
+            ..Default::default()
+        },
+        SmDtonMergeOptions {
+            deep: true,
+            arrays: SmDtonArrayMerge::Concat,
+        },
+    ] {
+        dton.set_merge(merge);
+        let flat = dton.try_flatten().unwrap();
+        let config: Config = from_dton(&dton).unwrap();
+        assert_eq!(from_buffer::<Config>(&flat).unwrap(), config);
+        assert_eq!(dton.get_u16_path("db.port"), Some(config.db.port));
+        assert_eq!(dton.get_string_path("db.host"), config.db.host);
+        assert_eq!(
+            dton.get_string_path("db.tags[2]"),
+            config.db.tags.get(2).copied()
+        );
+    }
+}
+
+#[test]
+fn from_dton_replaces_nested_maps_by_default() {
+    let base = build(BASE);
+    let update = build(r#"{"db":{"port":6000u16,"tags":[]}}"#);
+    let mut dton = SmDton::new_from_buffer(&base);
+    dton.push_layer(update.get_buffer());
+    let config: Config = from_dton(&dton).unwrap();
+    assert_eq!(config.db.host, None);
+    assert_eq!(config.name, "n");
+}
+
+#[test]
+fn from_dton_drops_nested_tombstones() {
+    let base = build(BASE);
+    let update = build(r#"{"db":{"host":del}}"#);
+    let mut dton = SmDton::new_from_buffer(&base);
+    dton.push_layer(update.get_buffer());
+    dton.set_merge(SmDtonMergeOptions {
+        deep: true,
+        ..Default::default()
+    });
+    let config: Config = from_dton(&dton).unwrap();
+    assert_eq!(config.db.host, None);
+    assert_eq!(config.db.port, 5432);
+    assert_eq!(dton.get_string_path("db.host"), None);
+}
+
+struct Raw<'a>(&'a [u8]);
+
+impl Serialize for Raw<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.serialize_bytes(self.0);
+    }
+}
+
+#[derive(Serialize)]
+enum Mode {
+    Off,
+    Level(u8),
+    Pair(i16, bool),
+    Named { ratio: f32 },
+}
+
+#[derive(Serialize)]
+struct Unit;
+
+#[derive(Serialize)]
+struct Record<'a> {
+    small: u8,
+    tiny: i8,
+    port: u16,
+    delta: i32,
+    count: u32,
+    ratio: f64,
+    wide: i128,
+    flag: bool,
+    letter: char,
+    name: &'a str,
+    raw: Raw<'a>,
+    list: Vec<u8>,
+    missing: Option<u16>,
+    present: Option<u16>,
+    unit: Unit,
+    pair: (u8, &'a str),
+    modes: Vec<Mode>,
+    ids: BTreeMap<u32, &'a str>,
+}
+
+#[test]
+fn to_buffer_maps_rust_types_to_tags() {
+    let record = Record {
+        small: 1,
+        tiny: -1,
+        port: 5432,
+        delta: -7,
+        count: 7,
+        ratio: 0.5,
+        wide: -3,
+        flag: true,
+        letter: 'z',
+        name: "n",
+        raw: Raw(&[0, 1]),
+        list: vec![2, 3],
+        missing: None,
+        present: Some(80),
+        unit: Unit,
+        pair: (4, "p"),
+        modes: vec![
+            Mode::Off,
+            Mode::Level(2),
+            Mode::Pair(-1, false),
+            Mode::Named { ratio: 0.25 },
+        ],
+        ids: BTreeMap::from([(10, "ten"), (2, "two")]),
+    };
+    let smb = to_buffer(&record).unwrap();
+    assert_eq!(
+        text(&smb),
+        concat!(
+            r#"{"small":1u8,"tiny":-1i16,"port":5432u16,"delta":-7i32,"count":7u32,"ratio":0.5f64,"#,
+            r#""wide":-3i64,"flag":true,"letter":"z","name":"n","raw":b64"AAE=","list":[2u8,3u8],"#,
+            r#""missing":null,"present":80u16,"unit":null,"pair":[4u8,"p"],"#,
+            r#""modes":["Off",{"Level":2u8},{"Pair":[-1i16,false]},{"Named":{"ratio":0.25f32}}],"#,
+            r#""ids":{"2":"two","10":"ten"}}"#
+        )
+    );
+}
+
+#[test]
+fn to_buffer_rejects_what_has_no_encoding() {
+    assert!(to_buffer(&5u8).is_err());
+    assert!(to_buffer("text").is_err());
+    assert!(matches!(
+        to_buffer(&[u128::MAX]),
+        Err(SmDtonError::Custom(_))
+    ));
+    let keyed = BTreeMap::from([((1u8, 2u8), 3u8)]);
+    assert!(to_buffer(&keyed).is_err());
+    assert_eq!(text(&to_buffer(&vec![1u16, 2]).unwrap()), "[1u16,2u16]");
+}
+
+#[test]
+fn to_buffer_with_takes_builder_options() {
+    let ids = BTreeMap::from([("b", 1u8), ("a", 2u8)]);
+    let smb = to_buffer_with(
+        &[("z", ids.clone())],
+        SmDtonOptions {
+            sorted_keys: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(smb.get_buffer()[0], ST::SMTY_DTR | ST::SMTY_FLAG_SORTED);
+    assert_eq!(text(&smb), r#"[["z",{"a":2u8,"b":1u8}]]"#);
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+enum Kind {
+    Off,
+    Level(u8),
+    Pair(i16, bool),
+    Named { ratio: f32 },
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Server<'a> {
+    host: &'a str,
+    port: u16,
+    key: Option<&'a [u8]>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Cluster<'a> {
+    #[serde(borrow)]
+    servers: Vec<Server<'a>>,
+    kinds: Vec<Kind>,
+    weight: Option<f64>,
+    limit: Option<u32>,
+    owner: Option<String>,
+    ids: BTreeMap<u32, i64>,
+}
+
+const CLUSTER: &str = r#"{"servers":[{"host":"a","port":80u16,"key":b64"AAE="},{"host":"b","port":443u16,"key":null}],"kinds":["Off",{"Level":2u8},{"Pair":[-1i16,true]},{"Named":{"ratio":0.5f32}}],"weight":2i64,"limit":null,"ids":{"7":-1i64}}"#;
+
+#[test]
+fn from_buffer_reads_nested_structs_and_enums() {
+    let smb = build(CLUSTER);
+    let cluster: Cluster = from_buffer(&smb).unwrap();
+    assert_eq!(
+        cluster,
+        Cluster {
+            servers: vec![
+                Server {
+                    host: "a",
+                    port: 80,
+                    key: Some(&[0, 1]),
+                },
+                Server {
+                    host: "b",
+                    port: 443,
+                    key: None,
+                },
+            ],
+            kinds: vec![
+                Kind::Off,
+                Kind::Level(2),
+                Kind::Pair(-1, true),
+                Kind::Named { ratio: 0.5 },
+            ],
+            weight: Some(2.0),
+            limit: None,
+            owner: None,
+            ids: BTreeMap::from([(7, -1)]),
+        }
+    );
+
+    // strings and bytes point into the buffer
+    let range = smb.get_buffer().as_ptr_range();
+    assert!(range.contains(&cluster.servers[0].host.as_ptr()));
+    assert!(range.contains(&cluster.servers[0].key.unwrap().as_ptr()));
+}
+
+#[test]
+fn from_buffer_errors_name_the_field() {
+    let smb = build(
+        r#"{"servers":[{"host":"a","port":1u16},{"host":"b","port":2u16},{"host":"c","port":"x"}],"kinds":[],"ids":{}}"#,
+    );
+    let err = from_buffer::<Cluster>(&smb).unwrap_err();
+    match &err {
+        SmDtonError::Field { path, .. } => assert_eq!(path, "servers[2].port"),
+        other => panic!("{:?}", other),
+    }
+    assert!(err.to_string().starts_with("servers[2].port: "));
+
+    let smb = build(r#"{"servers":[],"kinds":["Loud"],"ids":{}}"#);
+    match from_buffer::<Cluster>(&smb).unwrap_err() {
+        SmDtonError::Field { path, .. } => assert_eq!(path, "kinds[0]"),
+        other => panic!("{:?}", other),
+    }
+
+    // a u16 does not hold 70000
+    let smb = build(r#"{"host":"a","port":70000u32}"#);
+    match from_buffer::<Server>(&smb).unwrap_err() {
+        SmDtonError::Field { path, .. } => assert_eq!(path, "port"),
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn serde_round_trips_through_a_buffer() {
+    let smb = to_buffer(&BTreeMap::from([("b", Some(1u8)), ("a", None)])).unwrap();
+    let back: BTreeMap<String, Option<u8>> = from_buffer(&smb).unwrap();
+    assert_eq!(
+        back,
+        BTreeMap::from([("b".to_string(), Some(1)), ("a".to_string(), None)])
+    );
+}
+
+#[test]
+fn from_dton_prefers_the_update_layer() {
+    let pair = SmDtonPair::new(
+        build(r#"{"host":"a","port":80u16,"key":b64"AAE="}"#),
+        build(r#"{"port":8080u16,"key":del}"#),
+    );
+    let dton = SmDton::new_from_pair(&pair);
+    let server: Server = from_dton(&dton).unwrap();
+    assert_eq!(
+        server,
+        Server {
+            host: "a",
+            port: 8080,
+            key: None,
+        }
+    );
+}
+
+#[derive(Debug, Deserialize)]
+struct Chain {
+    #[allow(dead_code)]
+    a: Option<Box<Chain>>,
+}
+
+// the error below any field wrappers
+fn cause(mut err: SmDtonError) -> SmDtonError {
+    while let SmDtonError::Field { error, .. } = err {
+        err = *error;
+    }
+    return err;
+}
+
+#[test]
+fn cyclic_buffers_fail_to_deserialize() {
+    let smb = build(r#"{"a":{"b":1u8}}"#);
+    let reader = SmDtonReader::new(smb.get_buffer());
+    let voff = reader.get_field_voff(1, "a");
+    let mut buf = smb.get_buffer().to_vec();
+    assert_eq!(buf[voff], ST::SMDT_MAP);
+    // "a" now points back at the root
+    buf[voff + 1] = 1;
+
+    let reader = SmDtonReader::new(&buf);
+    let err = from_reader::<Chain>(&reader).unwrap_err();
+    assert_eq!(cause(err), SmDtonError::RepeatedNode(1));
+
+    let base = build(r#"{"x":1u8}"#);
+    let mut dton = SmDton::new_from_buffer(&base);
+    dton.push_layer(&buf);
+    let err = from_dton::<Chain>(&dton).unwrap_err();
+    assert_eq!(cause(err), SmDtonError::RepeatedNode(1));
+}
+
+#[test]
+fn nesting_past_the_limit_fails_to_deserialize() {
+    let mut builder = SmDtonBuilder::new();
+    let root = builder.create_node(ST::SMDT_MAP);
+    let mut up = root;
+    for _ in 0..300 {
+        let sub = builder.create_node(ST::SMDT_MAP);
+        builder.add_node(up, "a", sub);
+        up = sub;
+    }
+    let smb = builder.build();
+    let reader = SmDtonReader::new(smb.get_buffer());
+    let err = from_reader::<Chain>(&reader).unwrap_err();
+    assert!(matches!(cause(err), SmDtonError::TooDeep { .. }));
+}
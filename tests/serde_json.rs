@@ -0,0 +1,71 @@
+#![cfg(feature = "serde_json")]
+
+mod common;
+
+use common::{build, text};
+use serde_json::{json, Value};
+use smdton::{SmDtonBuffer, SmDtonBuilder, SmDtonError, SmDtonOptions, SmDtonReader};
+
+#[test]
+fn values_convert_both_ways() {
+    let jsn = json!({
+        "name": "n",
+        "port": 5432,
+        "neg": -1,
+        "big": u64::MAX,
+        "ratio": 0.5,
+        "on": true,
+        "none": null,
+        "list": [1, "x", {"k": []}],
+        "raw": "$B64$AAE="
+    });
+    // serde_json maps keep their keys sorted
+    let smb = SmDtonBuffer::try_from(&jsn).unwrap();
+    assert_eq!(
+        text(&smb),
+        r#"{"big":18446744073709551615u64,"list":[1i64,"x",{"k":[]}],"name":"n","neg":-1i64,"none":null,"on":true,"port":5432i64,"ratio":0.5f64,"raw":b64"AAE="}"#
+    );
+    assert_eq!(Value::try_from(&smb).unwrap(), jsn);
+    let owned = SmDtonBuffer::try_from(jsn.clone()).unwrap();
+    assert_eq!(owned.get_buffer(), smb.get_buffer());
+}
+
+#[test]
+fn readers_and_builders_take_either_backend() {
+    let smb = build(r#"{"a":{"b":[1u8,2.5f32,-3i16]},"c":"d"}"#);
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    let value: Value = reader.to_json_value(1).unwrap();
+    assert_eq!(value, json!({"a": {"b": [1, 2.5, -3]}, "c": "d"}));
+    let inner: Value = reader
+        .try_to_json_value(reader.get_node_id(1, "a").unwrap())
+        .unwrap();
+    assert_eq!(inner, json!({"b": [1, 2.5, -3]}));
+
+    let narrow = SmDtonOptions {
+        narrow_ints: true,
+        ..Default::default()
+    };
+    let built = SmDtonBuilder::new_from_json_with(&value, narrow).build();
+    assert_eq!(text(&built), r#"{"a":{"b":[1u8,2.5f64,-3i16]},"c":"d"}"#);
+
+    #[cfg(feature = "json")]
+    {
+        let old: json::JsonValue = reader.to_json_value(1).unwrap();
+        assert_eq!(old.dump(), value.to_string());
+    }
+}
+
+#[test]
+fn conversions_report_errors() {
+    assert!(SmDtonBuffer::try_from(json!(5)).is_err());
+    let bad = json!({"raw": "$B64$@@"});
+    assert!(matches!(
+        SmDtonBuffer::try_from(&bad),
+        Err(SmDtonError::InvalidBase64)
+    ));
+    let nan = build(r#"{"x":nanf64,"y":-inff32}"#);
+    assert_eq!(
+        Value::try_from(&nan).unwrap(),
+        json!({"x": null, "y": null})
+    );
+}
@@ -0,0 +1,103 @@
+#![cfg(feature = "json")]
+
+mod common;
+
+use common::text;
+use smdton::{SmDtonBuilder, SmDtonError, SmDtonOptions, SmDtonReader, ST};
+
+const DOC: &str =
+    r#"{"zeta":1,"b":2,"é":3,"a":4,"b":5,"":6,"inner":{"y":7,"x":8},"list":[{"q":9,"p":10}]}"#;
+
+fn sorted() -> SmDtonOptions {
+    return SmDtonOptions {
+        sorted_keys: true,
+        ..Default::default()
+    };
+}
+
+#[test]
+fn sorted_keys_order_every_map_and_set_the_flag() {
+    let smb = SmDtonBuilder::from_json_str_with(DOC, sorted())
+        .unwrap()
+        .build();
+    assert_eq!(smb.get_buffer()[0], ST::SMTY_DTR | ST::SMTY_FLAG_SORTED);
+    assert_eq!(
+        text(&smb),
+        r#"{"":6i64,"a":4i64,"b":2i64,"b":5i64,"inner":{"x":8i64,"y":7i64},"list":[{"p":10i64,"q":9i64}],"zeta":1i64,"é":3i64}"#
+    );
+
+    let plain = SmDtonBuilder::from_json_str(DOC).unwrap().build();
+    assert_eq!(plain.get_buffer()[0], ST::SMTY_DTR);
+}
+
+#[test]
+fn sorted_unsorted_and_indexed_lookups_agree() {
+    let sorted = SmDtonBuilder::from_json_str_with(DOC, sorted())
+        .unwrap()
+        .build();
+    let plain = SmDtonBuilder::from_json_str(DOC).unwrap().build();
+    let readers = [
+        SmDtonReader::try_new(sorted.get_buffer()).unwrap(),
+        SmDtonReader::try_new(plain.get_buffer()).unwrap(),
+        SmDtonReader::try_new(plain.get_buffer())
+            .unwrap()
+            .with_key_index(),
+        SmDtonReader::try_new(sorted.get_buffer())
+            .unwrap()
+            .with_key_index(),
+    ];
+    for reader in readers.iter() {
+        // the last of repeated keys wins
+        assert_eq!(reader.get_i64(1, "b"), Some(5));
+        assert_eq!(reader.try_get_i64(1, "b").unwrap(), Some(5));
+        for (key, want) in [("zeta", 1), ("é", 3), ("a", 4), ("", 6)] {
+            assert_eq!(reader.get_i64(1, key), Some(want), "{}", key);
+            assert_eq!(reader.try_get_i64(1, key).unwrap(), Some(want), "{}", key);
+        }
+        for key in ["c", "aa", "zz", "e"] {
+            assert_eq!(reader.get_i64(1, key), None, "{}", key);
+            assert_eq!(reader.try_get_i64(1, key).unwrap(), None, "{}", key);
+        }
+        assert_eq!(reader.get_i64_path(1, "inner.x"), Some(8));
+        assert_eq!(reader.get_i64_path(1, "list[0].p"), Some(10));
+        assert_eq!(reader.get_i64_path(1, "list[0].r"), None);
+    }
+
+    // the index is shared by clones and answers repeated lookups
+    let indexed = readers[2].clone();
+    assert_eq!(indexed.get_i64(1, "a"), Some(4));
+    assert_eq!(indexed.get_i64(1, "a"), Some(4));
+}
+
+#[test]
+fn validate_rejects_a_sorted_flag_on_unsorted_keys() {
+    let plain = SmDtonBuilder::from_json_str(DOC).unwrap().build();
+    let mut bad = plain.get_buffer().to_vec();
+    bad[0] |= ST::SMTY_FLAG_SORTED;
+    assert_eq!(
+        SmDtonReader::try_new(&bad).err(),
+        Some(SmDtonError::UnsortedKeys(1))
+    );
+    assert_eq!(
+        SmDtonReader::new(&bad).validate(),
+        Err(SmDtonError::UnsortedKeys(1))
+    );
+
+    let sorted = SmDtonBuilder::from_json_str_with(DOC, sorted())
+        .unwrap()
+        .build();
+    let reader = SmDtonReader::try_new(sorted.get_buffer()).unwrap();
+    assert_eq!(reader.validate(), Ok(()));
+}
+
+#[test]
+fn sorted_keys_apply_to_json_values() {
+    let jsn = json::parse(r#"{"b":1,"a":{"d":2,"c":3}}"#).unwrap();
+    let smb = SmDtonBuilder::new_from_json_with(&jsn, sorted()).build();
+    assert_eq!(text(&smb), r#"{"a":{"c":3i64,"d":2i64},"b":1i64}"#);
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    assert_eq!(
+        reader.try_to_json(1).unwrap().dump(),
+        r#"{"a":{"c":3,"d":2},"b":1}"#
+    );
+}
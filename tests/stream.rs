@@ -0,0 +1,146 @@
+#![cfg(feature = "json")]
+
+mod common;
+
+use common::text;
+use smdton::{SmDtonBuilder, SmDtonError, SmDtonOptions, SmDtonReader};
+use std::io::{self, Read};
+
+const DOC: &str = r#" {"name":"café 😀","esc":"a\"b\\c\/\n\t","n":[-12.5e-1,0,18446744073709551615,true,false,null],"raw":"$B64$AAEC","plain":"$B6","nested":{"deep":[[{}],[]]},"é":"ü"} "#;
+
+// hands out the text a few bytes per read, with an interruption first
+struct Chunked<'t> {
+    text: &'t [u8],
+    step: usize,
+    interrupted: bool,
+}
+
+impl Read for Chunked<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.interrupted {
+            self.interrupted = true;
+            return Err(io::Error::from(io::ErrorKind::Interrupted));
+        }
+        let n = self.step.min(buf.len()).min(self.text.len());
+        buf[..n].copy_from_slice(&self.text[..n]);
+        self.text = &self.text[n..];
+        return Ok(n);
+    }
+}
+
+struct Broken;
+
+impl Read for Broken {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        return Err(io::Error::from(io::ErrorKind::ConnectionReset));
+    }
+}
+
+fn chunked(text: &str, step: usize) -> Chunked<'_> {
+    return Chunked {
+        text: text.as_bytes(),
+        step: step,
+        interrupted: false,
+    };
+}
+
+#[test]
+fn split_chunks_build_what_the_tree_builds() {
+    let jsn = json::parse(DOC).unwrap();
+    let want = text(&SmDtonBuilder::new_from_json(&jsn).build());
+    assert_eq!(
+        want,
+        r#"{"name":"café 😀","esc":"a\"b\\c/\n\t","n":[-1.25f64,0i64,18446744073709551615u64,true,false,null],"raw":b64"AAEC","plain":"$B6","nested":{"deep":[[{}],[]]},"é":"ü"}"#
+    );
+    assert_eq!(
+        text(&SmDtonBuilder::from_json_str(DOC).unwrap().build()),
+        want
+    );
+    for step in [1, 2, 3, 7, 64] {
+        let smb = SmDtonBuilder::from_json_reader(chunked(DOC, step))
+            .unwrap()
+            .build();
+        assert_eq!(text(&smb), want, "step {}", step);
+    }
+}
+
+#[test]
+fn stream_errors_carry_text_offsets() {
+    for (doc, offset) in [
+        (r#"{"a":1,}"#, 7),
+        (r#"{"a":tru}"#, 8),
+        (r#"{"a" 1}"#, 5),
+        (r#"{"a":"x"#, 7),
+        (r#"{"a":"\q"}"#, 8),
+        ("", 0),
+    ] {
+        for step in [1, 64] {
+            assert_eq!(
+                SmDtonBuilder::from_json_reader(chunked(doc, step)).err(),
+                Some(SmDtonError::InvalidJson { offset: offset }),
+                "{} step {}",
+                doc,
+                step
+            );
+        }
+    }
+    assert_eq!(
+        SmDtonBuilder::from_json_str(r#"{"a":1} x"#).err(),
+        Some(SmDtonError::TrailingBytes { offset: 8 })
+    );
+    assert_eq!(
+        SmDtonBuilder::from_json_reader(&b"{\"a\":\"\xff\"}"[..]).err(),
+        Some(SmDtonError::InvalidUtf8 { offset: 6 })
+    );
+    assert_eq!(
+        SmDtonBuilder::from_json_str(r#"{"raw":"$B64$@@"}"#).err(),
+        Some(SmDtonError::InvalidBase64)
+    );
+    assert_eq!(
+        SmDtonBuilder::from_json_reader(Broken).err(),
+        Some(SmDtonError::Io(io::ErrorKind::ConnectionReset))
+    );
+    let deep = "[".repeat(10000);
+    assert!(matches!(
+        SmDtonBuilder::from_json_str(&deep),
+        Err(SmDtonError::TooDeep { .. })
+    ));
+}
+
+#[test]
+fn long_integers_overflow_cleanly_across_chunks() {
+    let doc = r#"{"a":184467440737095516160,"b":18446744073709551615.5,"c":18446744073709551620,"d":18446744073709551615.000,"e":-92233720368547758080}"#;
+    let want = r#"{"a":184467440737095500000f64,"b":18446744073709552000f64,"c":18446744073709552000f64,"d":18446744073709551615u64,"e":-92233720368547760000f64}"#;
+    let exact = SmDtonOptions {
+        exact_numbers: true,
+        ..Default::default()
+    };
+    for step in [1, 2, 3, 5, 7, 20, 64] {
+        let smb = SmDtonBuilder::from_json_reader(chunked(doc, step))
+            .unwrap()
+            .build();
+        assert_eq!(text(&smb), want, "step {}", step);
+        let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+        assert_eq!(reader.get_f64(1, "a"), Some(184467440737095516160.0));
+
+        for part in [
+            "184467440737095516160",
+            "18446744073709551615.5",
+            "18446744073709551620",
+        ] {
+            let doc = format!(r#"{{"x":{}}}"#, part);
+            assert_eq!(
+                SmDtonBuilder::from_json_reader_with(chunked(&doc, step), exact).err(),
+                Some(SmDtonError::InexactNumber),
+                "{} step {}",
+                part,
+                step
+            );
+        }
+        let doc = r#"{"x":18446744073709551615.000}"#;
+        let smb = SmDtonBuilder::from_json_reader_with(chunked(doc, step), exact)
+            .unwrap()
+            .build();
+        assert_eq!(text(&smb), r#"{"x":18446744073709551615u64}"#);
+    }
+}
@@ -0,0 +1,9 @@
+use smdton::DtonBuild;
+
+#[derive(DtonBuild)]
+struct Server {
+    #[dton(flatten)]
+    port: u16,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use smdton::DtonView;
+
+#[derive(DtonView)]
+struct Wrapper<T> {
+    value: T,
+}
+
+fn main() {}
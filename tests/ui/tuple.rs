@@ -0,0 +1,6 @@
+use smdton::DtonBuild;
+
+#[derive(DtonBuild)]
+struct Pair(u8, u8);
+
+fn main() {}
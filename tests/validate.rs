@@ -0,0 +1,134 @@
+#![cfg(feature = "json")]
+
+mod common;
+
+use common::build;
+use smdton::{SmDton, SmDtonError, SmDtonReader, ST};
+
+const DOC: &str = r#"{"name":"ab","port":5432u16,"tags":["x",null,{"k":-1i64}],"key":b64"AAE="}"#;
+
+fn find(buf: &[u8], part: &[u8]) -> usize {
+    return buf.windows(part.len()).position(|w| w == part).unwrap();
+}
+
+#[test]
+fn built_buffers_validate_at_every_offset_size() {
+    let long = "x".repeat(300);
+    let longer = "y".repeat(70000);
+    for text in [
+        DOC.to_string(),
+        format!(r#"{{"s":"{}"}}"#, long),
+        format!(r#"{{"s":"{}"}}"#, longer),
+    ] {
+        let smb = build(&text);
+        let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+        assert_eq!(reader.validate(), Ok(()));
+    }
+    assert_eq!(build(&format!(r#"{{"s":"{}"}}"#, long)).get_buffer()[1], 2);
+    assert_eq!(
+        build(&format!(r#"{{"s":"{}"}}"#, longer)).get_buffer()[1],
+        4
+    );
+}
+
+#[test]
+fn broken_heads_are_reported() {
+    let smb = build(DOC);
+    let buf = smb.get_buffer();
+    assert_eq!(
+        SmDtonReader::try_new(&[]).err(),
+        Some(SmDtonError::Truncated { offset: 0 })
+    );
+
+    let mut bad = buf.to_vec();
+    bad[0] = 0x02;
+    assert_eq!(
+        SmDtonReader::try_new(&bad).err(),
+        Some(SmDtonError::InvalidHeader(0x02))
+    );
+
+    let mut bad = buf.to_vec();
+    bad[1] = 3;
+    assert_eq!(
+        SmDtonReader::try_new(&bad).err(),
+        Some(SmDtonError::InvalidOffsetSize(3))
+    );
+
+    let oz = buf[1] as usize;
+    let mut bad = buf.to_vec();
+    bad[2 + 3 * oz] = 0;
+    assert_eq!(
+        SmDtonReader::try_new(&bad).err(),
+        Some(SmDtonError::InvalidSentinel { offset: 2 + 3 * oz })
+    );
+}
+
+#[test]
+fn broken_bodies_are_reported() {
+    let smb = build(DOC);
+    let buf = smb.get_buffer();
+
+    let mut bad = buf.to_vec();
+    let at = find(&bad, b"ab\0");
+    bad[at] = 0xff;
+    assert!(matches!(
+        SmDtonReader::try_new(&bad),
+        Err(SmDtonError::InvalidUtf8 { .. })
+    ));
+
+    let mut bad = buf.to_vec();
+    bad.push(0);
+    assert_eq!(
+        SmDtonReader::try_new(&bad).err(),
+        Some(SmDtonError::TrailingBytes { offset: buf.len() })
+    );
+
+    let mut bad = buf.to_vec();
+    let reader = SmDtonReader::new(buf);
+    let voff = reader.get_field_voff(1, "port");
+    bad[voff] = 0x7e;
+    assert_eq!(
+        SmDtonReader::try_new(&bad).err(),
+        Some(SmDtonError::InvalidValueType {
+            offset: voff,
+            found: 0x7e
+        })
+    );
+}
+
+#[test]
+fn every_truncation_fails() {
+    let smb = build(DOC);
+    let buf = smb.get_buffer();
+    for len in 0..buf.len() {
+        assert!(SmDtonReader::try_new(&buf[..len]).is_err(), "{}", len);
+    }
+}
+
+#[test]
+fn corrupted_bytes_never_panic() {
+    let smb = build(DOC);
+    let buf = smb.get_buffer();
+    for at in 0..buf.len() {
+        for byte in [0u8, 1, 0x77, 0xff, ST::SMDT_MAP, ST::SMDT_ARR] {
+            let mut bad = buf.to_vec();
+            bad[at] = byte;
+            let checked = SmDtonReader::try_new(&bad);
+
+            // the unchecked reader answers None instead of panicking
+            let reader = SmDtonReader::new(&bad);
+            let _ = reader.get_string(1, "name");
+            let _ = reader.get_u16(1, "port");
+            let _ = reader.get_string_path(1, "tags[2].k");
+            let _ = reader.to_json(1);
+            let _ = reader.try_to_text(1);
+            if checked.is_ok() {
+                assert!(reader.try_to_json(1).is_ok());
+            }
+            let mut dton = SmDton::new_from_buffer(&smb);
+            dton.push_layer(&bad);
+            let _ = dton.stringify();
+            let _ = dton.get_string_path("tags[0]");
+        }
+    }
+}
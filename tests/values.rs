@@ -0,0 +1,86 @@
+#![cfg(feature = "json")]
+
+mod common;
+
+use common::build;
+use smdton::{SmDtonNodeRef, SmDtonReader, SmDtonValue};
+
+const DOC: &str = r#"{"n":null,"t":true,"b":7u8,"s":-2i16,"w":3u16,"i":-4i32,"u":5u32,"f":0.5f32,"l":-6i64,"q":8u64,"d":1.5f64,"name":"ab","raw":b64"AAE=","m":{"k":1i64},"arr":[1i64,"x"]}"#;
+
+#[test]
+fn values_borrow_every_kind() {
+    let smb = build(DOC);
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    assert_eq!(reader.get_value(1, "n"), Some(SmDtonValue::Null));
+    assert_eq!(reader.get_value(1, "t"), Some(SmDtonValue::Bool(true)));
+    assert_eq!(reader.get_value(1, "b"), Some(SmDtonValue::U8(7)));
+    assert_eq!(reader.get_value(1, "s"), Some(SmDtonValue::I16(-2)));
+    assert_eq!(reader.get_value(1, "w"), Some(SmDtonValue::U16(3)));
+    assert_eq!(reader.get_value(1, "i"), Some(SmDtonValue::I32(-4)));
+    assert_eq!(reader.get_value(1, "u"), Some(SmDtonValue::U32(5)));
+    assert_eq!(reader.get_value(1, "f"), Some(SmDtonValue::F32(0.5)));
+    assert_eq!(reader.get_value(1, "l"), Some(SmDtonValue::I64(-6)));
+    assert_eq!(reader.get_value(1, "q"), Some(SmDtonValue::U64(8)));
+    assert_eq!(reader.get_value(1, "d"), Some(SmDtonValue::F64(1.5)));
+    assert_eq!(reader.get_value(1, "name"), Some(SmDtonValue::Str("ab")));
+    assert_eq!(reader.get_value(1, "raw"), Some(SmDtonValue::Bin(&[0, 1])));
+    assert_eq!(reader.get_value(1, "missing"), None);
+
+    let m = reader.get_value(1, "m").unwrap();
+    let arr = reader.get_value(1, "arr").unwrap();
+    let (mid, aid) = (m.node_id().unwrap(), arr.node_id().unwrap());
+    assert_eq!(m, SmDtonValue::Map(SmDtonNodeRef { oid: mid }));
+    assert_eq!(arr, SmDtonValue::Arr(SmDtonNodeRef { oid: aid }));
+    assert_eq!(reader.get_i64(mid, "k"), Some(1));
+    assert_eq!(reader.get_value_by_id(aid, 1), Some(SmDtonValue::Str("x")));
+    assert_eq!(SmDtonValue::Str("ab").node_id(), None);
+}
+
+#[test]
+fn tombstones_are_values() {
+    let smb = build(r#"{"gone":del}"#);
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    assert_eq!(reader.get_value(1, "gone"), Some(SmDtonValue::Deleted));
+}
+
+#[test]
+fn entries_and_items_walk_a_node_in_order() {
+    let smb = build(DOC);
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    let keys: Vec<&str> = reader.iter_entries(1).map(|(key, _)| key).collect();
+    assert_eq!(
+        keys,
+        ["n", "t", "b", "s", "w", "i", "u", "f", "l", "q", "d", "name", "raw", "m", "arr"]
+    );
+    let (key, value) = reader.iter_entries(1).nth(11).unwrap();
+    assert_eq!((key, value), ("name", SmDtonValue::Str("ab")));
+
+    let aid = reader.get_node_id(1, "arr").unwrap();
+    let items: Vec<SmDtonValue> = reader.iter_items(aid).collect();
+    assert_eq!(items, [SmDtonValue::I64(1), SmDtonValue::Str("x")]);
+
+    // entries need keys, arrays yield none
+    assert_eq!(reader.iter_entries(aid).count(), 0);
+    // items of a map are its values
+    let mid = reader.get_node_id(1, "m").unwrap();
+    assert_eq!(
+        reader.iter_items(mid).collect::<Vec<_>>(),
+        [SmDtonValue::I64(1)]
+    );
+    assert_eq!(reader.iter_items(999).count(), 0);
+}
+
+#[test]
+fn iterators_stop_at_an_undecodable_entry() {
+    let smb = build(r#"{"a":1i64,"b":171u8,"c":true}"#);
+    let mut bad = smb.get_buffer().to_vec();
+    let at = bad.windows(2).position(|w| w == [0x12, 171]).unwrap();
+    bad[at] = 0x7e;
+    let reader = SmDtonReader::new(&bad);
+    let entries: Vec<_> = reader.iter_entries(1).collect();
+    assert_eq!(entries, [("a", SmDtonValue::I64(1))]);
+    assert_eq!(
+        reader.iter_items(1).collect::<Vec<_>>(),
+        [SmDtonValue::I64(1)]
+    );
+}
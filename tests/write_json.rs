@@ -0,0 +1,133 @@
+#![cfg(feature = "json")]
+
+mod common;
+
+use common::build;
+use smdton::{SmDton, SmDtonBuffer, SmDtonError, SmDtonJsonFormat, SmDtonReader};
+use std::io::{self, Write};
+
+const DOC: &str =
+    r#"{"b":1u8,"a":{"y":[1.5f64,"x\n\u0001"],"x":{}},"k":b64"AAE=","e":[],"f":nanf64,"b":2u8}"#;
+
+fn written(smb: &SmDtonBuffer, oid: usize, format: SmDtonJsonFormat) -> String {
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    let mut out = Vec::new();
+    reader.write_json(oid, &mut out, format).unwrap();
+    return String::from_utf8(out).unwrap();
+}
+
+#[test]
+fn compact_output_matches_to_json() {
+    let smb = build(DOC);
+    let out = written(&smb, 1, SmDtonJsonFormat::default());
+    assert_eq!(
+        out,
+        r#"{"b":2,"a":{"y":[1.5,"x\n\u0001"],"x":{}},"k":"$B64$AAE=","e":[],"f":null}"#
+    );
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    // the json crate dumps nan as null too
+    assert_eq!(out, reader.try_to_json(1).unwrap().dump());
+}
+
+#[test]
+fn pretty_indent_and_sorted_keys() {
+    let smb = build(DOC);
+    let format = SmDtonJsonFormat {
+        pretty: true,
+        indent: 2,
+        sort_keys: true,
+        b64_prefix: "",
+    };
+    assert_eq!(
+        written(&smb, 1, format),
+        concat!(
+            "{\n",
+            "  \"a\": {\n",
+            "    \"x\": {},\n",
+            "    \"y\": [\n",
+            "      1.5,\n",
+            "      \"x\\n\\u0001\"\n",
+            "    ]\n",
+            "  },\n",
+            "  \"b\": 2,\n",
+            "  \"e\": [],\n",
+            "  \"f\": null,\n",
+            "  \"k\": \"AAE=\"\n",
+            "}"
+        )
+    );
+
+    // a nested node alone, four spaces by default
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    let oid = reader.get_node_id_path(1, "a.y").unwrap();
+    let format = SmDtonJsonFormat {
+        pretty: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        written(&smb, oid, format),
+        "[\n    1.5,\n    \"x\\n\\u0001\"\n]"
+    );
+}
+
+#[test]
+fn layers_merge_into_one_document() {
+    let base = build(r#"{"a":1u8,"m":{"x":1u8},"gone":true}"#);
+    let update = build(r#"{"c":3u8,"a":2u8,"gone":del}"#);
+    let mut dton = SmDton::new_from_buffer(&base);
+    dton.push_layer(update.get_buffer());
+    let mut out = Vec::new();
+    let format = SmDtonJsonFormat {
+        sort_keys: true,
+        ..Default::default()
+    };
+    dton.write_json(&mut out, format).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        r#"{"a":2,"c":3,"m":{"x":1}}"#
+    );
+
+    // one layer writes the same as its reader
+    let single = SmDton::new_from_buffer(&base);
+    let mut out = Vec::new();
+    single
+        .write_json(&mut out, SmDtonJsonFormat::default())
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        written(&base, 1, SmDtonJsonFormat::default())
+    );
+}
+
+struct Full;
+
+impl Write for Full {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        return Err(io::Error::from(io::ErrorKind::WriteZero));
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        return Ok(());
+    }
+}
+
+#[test]
+fn write_errors_are_reported() {
+    let smb = build(DOC);
+    let reader = SmDtonReader::try_new(smb.get_buffer()).unwrap();
+    assert_eq!(
+        reader.write_json(1, &mut Full, SmDtonJsonFormat::default()),
+        Err(SmDtonError::Io(io::ErrorKind::WriteZero))
+    );
+    let mut out = Vec::new();
+    assert_eq!(
+        reader.write_json(99, &mut out, SmDtonJsonFormat::default()),
+        Err(SmDtonError::InvalidNodeId(99))
+    );
+    let empty = SmDtonBuffer::new();
+    let dton = SmDton::new_from_buffer(&empty);
+    assert_eq!(
+        dton.write_json(&mut out, SmDtonJsonFormat::default()),
+        Err(SmDtonError::InvalidNodeId(1))
+    );
+}